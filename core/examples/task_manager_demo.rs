@@ -15,7 +15,11 @@ async fn main() {
         .send(CreateTask {
             name: "Quick Task".to_string(),
             message: "A simple 2-second task".to_string(),
-            task_type: TaskType::Quick { timeout_ms: None },
+            task_type: TaskType::Quick {
+                timeout_ms: None,
+                duration: None,
+            },
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -25,7 +29,11 @@ async fn main() {
         .send(CreateTask {
             name: "Long Task".to_string(),
             message: "A background 10-second task".to_string(),
-            task_type: TaskType::Long { timeout_ms: None },
+            task_type: TaskType::Long {
+                timeout_ms: None,
+                duration: None,
+            },
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -39,6 +47,7 @@ async fn main() {
                 timeout_ms: None,
                 error_type: ErrorType::Immediate,
             },
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -56,6 +65,7 @@ async fn main() {
             task.name,
             task.message,
             match task.status {
+                TaskStatus::Queued => "Queued",
                 TaskStatus::InProgress => "In Progress",
                 TaskStatus::Completed => "Completed",
                 TaskStatus::Error => "Error",
@@ -0,0 +1,242 @@
+use crate::{TaskMetadata, TaskStatus};
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+const PARTITION_PREFIX: &str = "tasks-";
+const PARTITION_SUFFIX: &str = ".jsonl.gz";
+
+// Date-partitioned, gzip-compressed JSON Lines store for tasks that have
+// aged out of the manager's memory. Each day gets its own file named after
+// the task's `started_at` date, and every append writes a new gzip member so
+// files never need to be rewritten.
+//
+// Appends are serialized, queries never wait for them: a query reads each
+// partition only up to the length its last finished append left, so it
+// does not see a gzip member that is still being written.
+#[derive(Debug, Clone)]
+pub struct TaskArchive {
+    root: PathBuf,
+    write_lock: Arc<Mutex<()>>,
+    // Partition -> bytes fully written, for partitions being appended to
+    committed: Arc<RwLock<HashMap<PathBuf, u64>>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArchiveQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub status: Option<TaskStatus>,
+    pub name: Option<String>,
+    pub limit: Option<usize>,
+    // Only tasks of this namespace, or of every namespace not in `private`
+    // when None, the same rule as live task queries
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub private: BTreeSet<String>,
+}
+
+impl ArchiveQuery {
+    pub fn matches(&self, task: &TaskMetadata) -> bool {
+        let visible = match &self.namespace {
+            Some(namespace) => &task.namespace == namespace,
+            None => !self.private.contains(&task.namespace),
+        };
+        if !visible {
+            return false;
+        }
+
+        if let Some(from) = self.from {
+            if task.started_at < from {
+                return false;
+            }
+        }
+
+        if let Some(to) = self.to {
+            if task.started_at > to {
+                return false;
+            }
+        }
+
+        if let Some(status) = &self.status {
+            if &task.status != status {
+                return false;
+            }
+        }
+
+        if let Some(name) = &self.name {
+            if !task.name.to_lowercase().contains(&name.to_lowercase()) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl TaskArchive {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            write_lock: Arc::new(Mutex::new(())),
+            committed: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn partition_path(&self, date: NaiveDate) -> PathBuf {
+        self.root.join(format!(
+            "{}{}{}",
+            PARTITION_PREFIX,
+            date.format("%Y-%m-%d"),
+            PARTITION_SUFFIX
+        ))
+    }
+
+    // Appends tasks to their date partitions and returns how many were written.
+    pub fn append(&self, tasks: &[TaskMetadata]) -> io::Result<usize> {
+        if tasks.is_empty() {
+            return Ok(0);
+        }
+
+        let mut partitions: BTreeMap<NaiveDate, Vec<&TaskMetadata>> = BTreeMap::new();
+        for task in tasks {
+            partitions
+                .entry(task.started_at.date_naive())
+                .or_default()
+                .push(task);
+        }
+
+        // Compressed before taking the lock, only the file writes are serialized
+        let mut members = Vec::new();
+        for (date, tasks) in partitions {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            for task in tasks {
+                serde_json::to_writer(&mut encoder, task)?;
+                encoder.write_all(b"\n")?;
+            }
+            members.push((self.partition_path(date), encoder.finish()?));
+        }
+
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        fs::create_dir_all(&self.root)?;
+        for (path, member) in members {
+            self.append_member(&path, &member)?;
+        }
+
+        Ok(tasks.len())
+    }
+
+    fn append_member(&self, path: &Path, member: &[u8]) -> io::Result<()> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let length = file.metadata()?.len();
+        // Published before the write so queries stop at the old end
+        self.set_committed(path, length);
+
+        let written = (&file).write_all(member).and_then(|_| file.sync_data());
+        match written {
+            Ok(()) => {
+                self.set_committed(path, length + member.len() as u64);
+                Ok(())
+            }
+            Err(e) => {
+                // Drop the partial member so the partition stays readable
+                let _ = file.set_len(length);
+                Err(e)
+            }
+        }
+    }
+
+    fn set_committed(&self, path: &Path, length: u64) {
+        self.committed
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.to_path_buf(), length);
+    }
+
+    // How much of a partition a query may read
+    fn readable_length(&self, path: &Path) -> io::Result<u64> {
+        // The file's length first: an append that starts after this has
+        // already published the old end below
+        let length = fs::metadata(path)?.len();
+        let committed = self
+            .committed
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(path)
+            .copied();
+        Ok(committed.map_or(length, |committed| committed.min(length)))
+    }
+
+    // Scans the partitions overlapping the query's date range, oldest first.
+    pub fn query(&self, query: &ArchiveQuery) -> io::Result<Vec<TaskMetadata>> {
+        let from_date = query.from.map(|from| from.date_naive());
+        let to_date = query.to.map(|to| to.date_naive());
+
+        let mut results = Vec::new();
+        for (date, path) in self.partitions()? {
+            if from_date.is_some_and(|from| date < from) || to_date.is_some_and(|to| date > to) {
+                continue;
+            }
+
+            let length = self.readable_length(&path)?;
+            let file = File::open(&path)?.take(length);
+            let reader = BufReader::new(MultiGzDecoder::new(file));
+            for line in reader.lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let task: TaskMetadata = serde_json::from_str(&line)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+                if query.matches(&task) {
+                    results.push(task);
+                    if query.limit.is_some_and(|limit| results.len() >= limit) {
+                        return Ok(results);
+                    }
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    fn partitions(&self) -> io::Result<Vec<(NaiveDate, PathBuf)>> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut partitions = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let date = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(PARTITION_PREFIX))
+                .and_then(|name| name.strip_suffix(PARTITION_SUFFIX))
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+
+            if let Some(date) = date {
+                partitions.push((date, path));
+            }
+        }
+
+        partitions.sort();
+        Ok(partitions)
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{ApiError, ErrorType};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const MAX_PROGRESS_STEPS: u32 = 100;
+
+// Faults injected into a task run. Each fault rolls independently against
+// the task's seeded RNG, so a recorded seed replays the same faults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FaultProfile {
+    #[serde(default)]
+    pub latency_spike: Option<LatencySpike>,
+    #[serde(default)]
+    pub intermittent_failure: Option<IntermittentFailure>,
+    // Work never finishes, the task runs into its timeout
+    #[serde(default)]
+    pub hang_probability: f32,
+    // The work panics, the panic is caught and reported as a task error
+    #[serde(default)]
+    pub panic_probability: f32,
+    #[serde(default)]
+    pub progress: Option<ProgressFault>,
+    // The executor freezes without a word, no heartbeats, no timeout and no
+    // result, until its lease expires
+    #[serde(default)]
+    pub heartbeat_loss_probability: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LatencySpike {
+    pub probability: f32,
+    pub extra_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IntermittentFailure {
+    pub probability: f32,
+    pub error_type: ErrorType,
+}
+
+// Splits the work into steps that report progress. Each step takes
+// `slowdown` times longer than the one before, and with `stall_at` the
+// task stops advancing at that fraction and hangs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProgressFault {
+    pub steps: u32,
+    #[serde(default = "default_slowdown")]
+    pub slowdown: f32,
+    #[serde(default)]
+    pub stall_at: Option<f32>,
+}
+
+fn default_slowdown() -> f32 {
+    1.0
+}
+
+impl FaultProfile {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if let Some(spike) = &self.latency_spike {
+            validate_probability("latency_spike.probability", spike.probability)?;
+        }
+        if let Some(failure) = &self.intermittent_failure {
+            validate_probability("intermittent_failure.probability", failure.probability)?;
+        }
+        validate_probability("hang_probability", self.hang_probability)?;
+        validate_probability("panic_probability", self.panic_probability)?;
+        validate_probability(
+            "heartbeat_loss_probability",
+            self.heartbeat_loss_probability,
+        )?;
+
+        if let Some(progress) = &self.progress {
+            if !(1..=MAX_PROGRESS_STEPS).contains(&progress.steps) {
+                return Err(ApiError::validation_error(
+                    format!(
+                        "Progress steps must be between 1 and {}",
+                        MAX_PROGRESS_STEPS
+                    ),
+                    Some(serde_json::json!({
+                        "field": "progress.steps",
+                        "provided_value": progress.steps
+                    })),
+                ));
+            }
+            if !(progress.slowdown > 0.0 && progress.slowdown <= 10.0) {
+                return Err(ApiError::validation_error(
+                    "Progress slowdown must be greater than 0 and at most 10".to_string(),
+                    Some(serde_json::json!({
+                        "field": "progress.slowdown",
+                        "provided_value": progress.slowdown
+                    })),
+                ));
+            }
+            if let Some(stall_at) = progress.stall_at {
+                validate_probability("progress.stall_at", stall_at)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Rolls every fault once for a single task run
+    pub fn plan<R: Rng + ?Sized>(&self, rng: &mut R) -> FaultPlan {
+        let mut plan = FaultPlan::default();
+
+        if let Some(spike) = &self.latency_spike {
+            if rng.gen::<f32>() < spike.probability {
+                plan.extra_latency_ms = spike.extra_ms;
+            }
+        }
+        if let Some(failure) = &self.intermittent_failure {
+            if rng.gen::<f32>() < failure.probability {
+                plan.failure = Some(failure.error_type.clone());
+            }
+        }
+        plan.hang = rng.gen::<f32>() < self.hang_probability;
+        plan.panic = rng.gen::<f32>() < self.panic_probability;
+
+        if let Some(progress) = &self.progress {
+            plan.steps = progress.steps;
+            plan.slowdown = progress.slowdown;
+            plan.stall_at = progress.stall_at;
+        }
+        // Only rolled when enabled so profiles without it replay the same
+        // faults and durations as before it existed
+        plan.heartbeat_loss = self.heartbeat_loss_probability > 0.0
+            && rng.gen::<f32>() < self.heartbeat_loss_probability;
+
+        plan
+    }
+}
+
+fn validate_probability(field: &str, value: f32) -> Result<(), ApiError> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(ApiError::validation_error(
+            format!("'{}' must be between 0.0 and 1.0", field),
+            Some(serde_json::json!({
+                "field": field,
+                "provided_value": value,
+                "valid_range": "0.0-1.0"
+            })),
+        ));
+    }
+    Ok(())
+}
+
+// The outcome of rolling a FaultProfile, carried by StartTask
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FaultPlan {
+    pub extra_latency_ms: u64,
+    pub failure: Option<ErrorType>,
+    pub hang: bool,
+    pub panic: bool,
+    pub steps: u32,
+    pub slowdown: f32,
+    pub stall_at: Option<f32>,
+    #[serde(default)]
+    pub heartbeat_loss: bool,
+}
+
+impl Default for FaultPlan {
+    fn default() -> Self {
+        Self {
+            extra_latency_ms: 0,
+            failure: None,
+            hang: false,
+            panic: false,
+            steps: 1,
+            slowdown: 1.0,
+            stall_at: None,
+            heartbeat_loss: false,
+        }
+    }
+}
+
+impl FaultPlan {
+    // Offsets from the start of the work at which each step ends. Every
+    // step is `slowdown` times the previous one and the latency spike is
+    // spread evenly, so the total is `work + extra_latency`.
+    pub fn step_offsets(&self, work: Duration) -> Vec<Duration> {
+        let steps = self.steps.max(1);
+        let weights: Vec<f64> = (0..steps)
+            .map(|step| (self.slowdown as f64).powi(step as i32))
+            .collect();
+        let total_weight: f64 = weights.iter().sum();
+        let total = work + Duration::from_millis(self.extra_latency_ms);
+
+        let mut elapsed = 0.0;
+        weights
+            .iter()
+            .map(|weight| {
+                elapsed += weight / total_weight;
+                total.mul_f64(elapsed.min(1.0))
+            })
+            .collect()
+    }
+
+    // Short labels recorded on the task so injected faults are visible
+    pub fn describe(&self) -> Vec<String> {
+        let mut faults = Vec::new();
+        if self.extra_latency_ms > 0 {
+            faults.push(format!("latency_spike:+{}ms", self.extra_latency_ms));
+        }
+        if let Some(error_type) = &self.failure {
+            faults.push(format!("failure:{:?}", error_type));
+        }
+        if self.hang {
+            faults.push("hang".to_string());
+        }
+        if self.panic {
+            faults.push("panic".to_string());
+        }
+        if self.slowdown != 1.0 {
+            faults.push(format!("slowdown:x{}", self.slowdown));
+        }
+        if let Some(stall_at) = self.stall_at {
+            faults.push(format!("stall:{}%", (stall_at * 100.0).round()));
+        }
+        if self.heartbeat_loss {
+            faults.push("heartbeat_loss".to_string());
+        }
+        faults
+    }
+}
+
+// Runtime fault configuration of a manager. A profile on the task itself
+// wins over the one for its type, which wins over the global one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ChaosConfig {
+    pub global: Option<FaultProfile>,
+    // By registered task type name
+    pub per_type: HashMap<String, FaultProfile>,
+}
+
+impl ChaosConfig {
+    pub fn profile_for(&self, task_kind: &str) -> Option<&FaultProfile> {
+        self.per_type.get(task_kind).or(self.global.as_ref())
+    }
+}
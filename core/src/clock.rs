@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+// Source of wall-clock time and timers for the task actors. The system
+// clock is used in production, tests swap in a ManualClock.
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    // The deadline is fixed when this is called, not when the future is first polled
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+// Virtual clock that only moves when told to. Sleepers wake as soon as
+// `advance` moves the time past their deadline.
+#[derive(Clone)]
+pub struct ManualClock {
+    state: Arc<Mutex<ManualClockState>>,
+}
+
+struct ManualClockState {
+    now: DateTime<Utc>,
+    sleepers: Vec<(DateTime<Utc>, oneshot::Sender<()>)>,
+}
+
+impl ManualClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ManualClockState {
+                now: start,
+                sleepers: Vec::new(),
+            })),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.now = offset(state.now, duration);
+
+        let now = state.now;
+        let mut sleepers = std::mem::take(&mut state.sleepers);
+        sleepers.sort_by_key(|(deadline, _)| *deadline);
+
+        for (deadline, waker) in sleepers {
+            if deadline <= now {
+                let _ = waker.send(());
+            } else {
+                state.sleepers.push((deadline, waker));
+            }
+        }
+    }
+
+    // Number of sleeps still waiting for the clock to reach their deadline
+    pub fn pending_sleepers(&self) -> usize {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .sleepers
+            .iter()
+            .filter(|(_, waker)| !waker.is_closed())
+            .count()
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new(Utc::now())
+    }
+}
+
+impl fmt::Debug for ManualClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        f.debug_struct("ManualClock")
+            .field("now", &state.now)
+            .field("sleepers", &state.sleepers.len())
+            .finish()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).now
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let deadline = offset(state.now, duration);
+
+        if deadline <= state.now {
+            return Box::pin(std::future::ready(()));
+        }
+
+        let (waker, wait) = oneshot::channel();
+        state.sleepers.push((deadline, waker));
+
+        Box::pin(async move {
+            let _ = wait.await;
+        })
+    }
+}
+
+fn offset(time: DateTime<Utc>, duration: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(duration)
+        .ok()
+        .and_then(|duration| time.checked_add_signed(duration))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
@@ -0,0 +1,105 @@
+use crate::ApiError;
+use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
+use rand_distr::{LogNormal, Normal, Pareto};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// Samples are clamped to this so a heavy tail can't park a task for days
+const MAX_SAMPLED_MS: f64 = 3_600_000.0;
+
+// Simulated work duration of a task, sampled once per run from the task's
+// seeded RNG. All parameters are in milliseconds except the shape ones.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DurationDistribution {
+    Constant { ms: u64 },
+    Uniform { min_ms: u64, max_ms: u64 },
+    Normal { mean_ms: f64, std_dev_ms: f64 },
+    // Parameterised by the median (e^mu) rather than mu itself
+    LogNormal { median_ms: f64, sigma: f64 },
+    // Heavy tail, `scale_ms` is the minimum and smaller `shape` means a longer tail
+    Pareto { scale_ms: f64, shape: f64 },
+}
+
+impl DurationDistribution {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let invalid = |message: &str| {
+            Err(ApiError::validation_error(
+                format!("Invalid duration distribution: {}", message),
+                Some(serde_json::json!({
+                    "field": "duration",
+                    "provided_value": self
+                })),
+            ))
+        };
+
+        match *self {
+            DurationDistribution::Constant { ms } if ms as f64 > MAX_SAMPLED_MS => {
+                invalid("ms cannot exceed one hour")
+            }
+            DurationDistribution::Uniform { min_ms, max_ms } if min_ms > max_ms => {
+                invalid("min_ms must not be greater than max_ms")
+            }
+            DurationDistribution::Uniform { max_ms, .. } if max_ms as f64 > MAX_SAMPLED_MS => {
+                invalid("max_ms cannot exceed one hour")
+            }
+            DurationDistribution::Normal {
+                mean_ms,
+                std_dev_ms,
+            } if !(non_negative(mean_ms) && non_negative(std_dev_ms)) => {
+                invalid("mean_ms and std_dev_ms must be non-negative")
+            }
+            DurationDistribution::LogNormal { median_ms, sigma }
+                if !(positive(median_ms) && non_negative(sigma)) =>
+            {
+                invalid("median_ms must be positive and sigma non-negative")
+            }
+            DurationDistribution::Pareto { scale_ms, shape }
+                if !(positive(scale_ms) && positive(shape)) =>
+            {
+                invalid("scale_ms and shape must be positive")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Invalid parameters sample as zero instead of panicking
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Duration {
+        let ms = match *self {
+            DurationDistribution::Constant { ms } => ms as f64,
+            DurationDistribution::Uniform { min_ms, max_ms } => {
+                if min_ms >= max_ms {
+                    min_ms as f64
+                } else {
+                    Uniform::new_inclusive(min_ms, max_ms).sample(rng) as f64
+                }
+            }
+            DurationDistribution::Normal {
+                mean_ms,
+                std_dev_ms,
+            } => Normal::new(mean_ms, std_dev_ms)
+                .map(|d| d.sample(rng))
+                .unwrap_or(0.0),
+            DurationDistribution::LogNormal { median_ms, sigma } => {
+                LogNormal::new(median_ms.ln(), sigma)
+                    .map(|d| d.sample(rng))
+                    .unwrap_or(0.0)
+            }
+            DurationDistribution::Pareto { scale_ms, shape } => Pareto::new(scale_ms, shape)
+                .map(|d| d.sample(rng))
+                .unwrap_or(0.0),
+        };
+
+        let ms = if ms.is_finite() { ms } else { MAX_SAMPLED_MS };
+        Duration::from_millis(ms.clamp(0.0, MAX_SAMPLED_MS).round() as u64)
+    }
+}
+
+fn non_negative(value: f64) -> bool {
+    value.is_finite() && value >= 0.0
+}
+
+fn positive(value: f64) -> bool {
+    value.is_finite() && value > 0.0
+}
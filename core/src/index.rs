@@ -0,0 +1,143 @@
+use crate::{TaskMetadata, TaskStatus};
+use chrono::{DateTime, Utc};
+use im::{HashMap, OrdSet};
+use std::ops::Bound;
+use uuid::Uuid;
+
+// Where a task sits in every index, by start time with the id breaking ties
+pub type TaskKey = (DateTime<Utc>, Uuid);
+
+pub fn task_key(task: &TaskMetadata) -> TaskKey {
+    (task.started_at, task.id)
+}
+
+// Conditions the indexes can answer, a task must meet all that are set
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskFilter {
+    pub status: Option<TaskStatus>,
+    // TaskType::kind
+    pub task_type: Option<String>,
+    pub tag: Option<String>,
+    // Started at or after
+    pub started_after: Option<DateTime<Utc>>,
+    // Started strictly before
+    pub started_before: Option<DateTime<Utc>>,
+}
+
+impl TaskFilter {
+    pub fn matches(&self, task: &TaskMetadata) -> bool {
+        self.status
+            .as_ref()
+            .is_none_or(|status| task.status == *status)
+            && self
+                .task_type
+                .as_ref()
+                .is_none_or(|task_type| task.task_type == *task_type)
+            && self.tag.as_ref().is_none_or(|tag| task.tags.contains(tag))
+            && self
+                .started_after
+                .is_none_or(|after| task.started_at >= after)
+            && self
+                .started_before
+                .is_none_or(|before| task.started_at < before)
+    }
+}
+
+// Secondary indexes over the tasks of a snapshot. They are persistent
+// collections, so the copy every snapshot gets shares everything but the
+// paths to the tasks that changed with the one before.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TaskIndex {
+    by_started: OrdSet<TaskKey>,
+    by_status: HashMap<TaskStatus, OrdSet<TaskKey>>,
+    by_type: HashMap<String, OrdSet<TaskKey>>,
+    by_tag: HashMap<String, OrdSet<TaskKey>>,
+}
+
+impl TaskIndex {
+    pub(crate) fn insert(&mut self, task: &TaskMetadata) {
+        let key = task_key(task);
+        self.by_started.insert(key);
+        self.by_status
+            .entry(task.status.clone())
+            .or_default()
+            .insert(key);
+        self.by_type
+            .entry(task.task_type.clone())
+            .or_default()
+            .insert(key);
+        for tag in &task.tags {
+            self.by_tag.entry(tag.clone()).or_default().insert(key);
+        }
+    }
+
+    // `task` as it was indexed
+    pub(crate) fn remove(&mut self, task: &TaskMetadata) {
+        let key = task_key(task);
+        self.by_started.remove(&key);
+        remove_key(&mut self.by_status, &task.status, &key);
+        remove_key(&mut self.by_type, &task.task_type, &key);
+        for tag in &task.tags {
+            remove_key(&mut self.by_tag, tag, &key);
+        }
+    }
+
+    // Keys of the tasks that may match `filter`, oldest first. Walks the
+    // smallest index the filter names, limited to the start time range, so
+    // only the conditions of the other indexes are left to check.
+    pub(crate) fn candidates<'a>(
+        &'a self,
+        filter: &TaskFilter,
+    ) -> Box<dyn DoubleEndedIterator<Item = &'a TaskKey> + 'a> {
+        let mut sets = vec![&self.by_started];
+        let lookups = [
+            filter
+                .status
+                .as_ref()
+                .map(|status| self.by_status.get(status)),
+            filter
+                .task_type
+                .as_ref()
+                .map(|task_type| self.by_type.get(task_type)),
+            filter.tag.as_ref().map(|tag| self.by_tag.get(tag)),
+        ];
+        for lookup in lookups.into_iter().flatten() {
+            match lookup {
+                Some(set) => sets.push(set),
+                None => return Box::new(std::iter::empty()),
+            }
+        }
+        let smallest = sets
+            .into_iter()
+            .min_by_key(|set| set.len())
+            .expect("by_started is always there");
+
+        let start = match filter.started_after {
+            Some(after) => Bound::Included((after, Uuid::nil())),
+            None => Bound::Unbounded,
+        };
+        let end = match filter.started_before {
+            Some(before) => Bound::Excluded((before, Uuid::nil())),
+            None => Bound::Unbounded,
+        };
+        if let (Some(after), Some(before)) = (filter.started_after, filter.started_before) {
+            if after >= before {
+                return Box::new(std::iter::empty());
+            }
+        }
+        Box::new(smallest.range((start, end)))
+    }
+}
+
+fn remove_key<K>(index: &mut HashMap<K, OrdSet<TaskKey>>, value: &K, key: &TaskKey)
+where
+    K: std::hash::Hash + Eq + Clone,
+{
+    let Some(set) = index.get_mut(value) else {
+        return;
+    };
+    set.remove(key);
+    if set.is_empty() {
+        index.remove(value);
+    }
+}
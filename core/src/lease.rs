@@ -0,0 +1,272 @@
+use crate::{ApiError, TaskManagerActor, TaskStatus};
+use actix::{AsyncContext, Context, Handler, Message};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use uuid::Uuid;
+
+// How long an executor may go without a heartbeat before its task is
+// reclaimed. Local tasks heartbeat three times per lease.
+pub const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(5);
+
+const MAX_ATTEMPTS_LIMIT: u32 = 10;
+const MAX_BACKOFF_MS: u64 = 60_000;
+
+// What happens to a task whose executor is lost, either because its lease
+// expired or because its remote worker disconnected. Errors reported by the
+// task itself are final.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetryPolicy {
+    // Including the first run, 1 means never retry
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    // Wait before the second attempt, doubled for every further one
+    #[serde(default)]
+    pub backoff_ms: u64,
+}
+
+fn default_max_attempts() -> u32 {
+    1
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            backoff_ms: 0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if !(1..=MAX_ATTEMPTS_LIMIT).contains(&self.max_attempts) {
+            return Err(ApiError::validation_error(
+                format!(
+                    "retry.max_attempts must be between 1 and {}",
+                    MAX_ATTEMPTS_LIMIT
+                ),
+                Some(serde_json::json!({
+                    "field": "retry.max_attempts",
+                    "provided_value": self.max_attempts
+                })),
+            ));
+        }
+        if self.backoff_ms > MAX_BACKOFF_MS {
+            return Err(ApiError::validation_error(
+                format!("retry.backoff_ms cannot exceed {}", MAX_BACKOFF_MS),
+                Some(serde_json::json!({
+                    "field": "retry.backoff_ms",
+                    "provided_value": self.backoff_ms,
+                    "max_value": MAX_BACKOFF_MS
+                })),
+            ));
+        }
+        Ok(())
+    }
+
+    // Wait before starting `attempt`, which is 2 or later
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(2).min(16);
+        Duration::from_millis(self.backoff_ms.saturating_mul(1 << doublings))
+    }
+}
+
+// Heartbeat from the executor running `attempt` of a task
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "()")]
+pub struct RenewLease {
+    pub id: Uuid,
+    pub attempt: u32,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct CheckLease {
+    id: Uuid,
+    attempt: u32,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct RetryTask {
+    id: Uuid,
+    attempt: u32,
+}
+
+impl TaskManagerActor {
+    // Starts the lease of a task that just went InProgress
+    pub(crate) fn grant_lease(&mut self, task_id: Uuid, attempt: u32, ctx: &mut Context<Self>) {
+        let now = self.clock.now();
+        if let Some(mut metadata) = self.task_metadata.get_mut(&task_id) {
+            metadata.lease_expires_at = Some(lease_expiry(now, self.lease_duration));
+        }
+        self.schedule_lease_check(task_id, attempt, self.lease_duration, ctx);
+    }
+
+    pub(crate) fn renew_lease(&mut self, task_id: Uuid, attempt: u32) {
+        let expires_at = lease_expiry(self.clock.now(), self.lease_duration);
+        if let Some(mut metadata) = self.task_metadata.get_mut(&task_id) {
+            if metadata.attempt == attempt && metadata.status == TaskStatus::InProgress {
+                metadata.lease_expires_at = Some(expires_at);
+            }
+        }
+    }
+
+    fn schedule_lease_check(
+        &mut self,
+        task_id: Uuid,
+        attempt: u32,
+        after: Duration,
+        ctx: &mut Context<Self>,
+    ) {
+        if self.schedule_lightweight_lease_check(task_id, attempt, after, ctx) {
+            return;
+        }
+
+        let check_sleep = self.clock.sleep(after);
+        let addr = ctx.address();
+        actix::spawn(async move {
+            check_sleep.await;
+            addr.do_send(CheckLease {
+                id: task_id,
+                attempt,
+            });
+        });
+    }
+
+    // Reclaims the task once its lease has run out, otherwise looks again
+    // when it is due to
+    pub(crate) fn check_lease(&mut self, task_id: Uuid, attempt: u32, ctx: &mut Context<Self>) {
+        let Some(metadata) = self.task_metadata.get(&task_id) else {
+            return;
+        };
+        if metadata.attempt != attempt || metadata.status != TaskStatus::InProgress {
+            return;
+        }
+
+        let now = self.clock.now();
+        if let Some(remaining) = metadata
+            .lease_expires_at
+            .and_then(|expires_at| (expires_at - now).to_std().ok())
+            .filter(|remaining| !remaining.is_zero())
+        {
+            self.schedule_lease_check(task_id, attempt, remaining, ctx);
+            return;
+        }
+
+        let reason = format!(
+            "Lease expired after {}ms without a heartbeat",
+            self.lease_duration.as_millis()
+        );
+        self.reclaim_task(task_id, ctx);
+        self.retry_or_fail(task_id, reason, ctx);
+        self.admit_waiting(ctx);
+    }
+
+    // Takes the task away from its executor, which is assumed to be gone
+    fn reclaim_task(&mut self, task_id: Uuid, ctx: &mut Context<Self>) {
+        if let Some(task_addr) = self.tasks.remove(&task_id) {
+            task_addr.do_send(AbandonTask);
+        } else if !self.abandon_lightweight(task_id) {
+            self.reclaim_remote(task_id, ctx);
+        }
+    }
+
+    // Starts the next attempt after the policy's backoff, or fails the
+    // task with `reason` once it is out of attempts
+    pub(crate) fn retry_or_fail(&mut self, task_id: Uuid, reason: String, ctx: &mut Context<Self>) {
+        let now = self.clock.now();
+        let span = self.span_of(&task_id);
+        let policy = self
+            .specs
+            .get(&task_id)
+            .and_then(|spec| spec.retry.clone())
+            .unwrap_or_default();
+        let Some(mut metadata) = self.task_metadata.get_mut(&task_id) else {
+            return;
+        };
+
+        if metadata.attempt >= metadata.max_attempts {
+            metadata.lease_expires_at = None;
+            metadata.mark_error_at(reason, false, now);
+            drop(metadata);
+            self.record_outcome(&task_id);
+            self.specs.remove(&task_id);
+            return;
+        }
+
+        let attempt = metadata.attempt + 1;
+        tracing::warn!(
+            parent: &span,
+            reason = %reason,
+            attempt,
+            "task lost its executor, retrying"
+        );
+        let error = format!("attempt {}: {}", metadata.attempt, reason);
+        metadata.attempt_errors.push(error);
+        metadata.status = TaskStatus::Queued;
+        metadata.queued_at = Some(now);
+        metadata.progress = 0.0;
+        metadata.worker = None;
+        metadata.lease_expires_at = None;
+
+        let backoff_sleep = self.clock.sleep(policy.backoff(attempt));
+        let addr = ctx.address();
+        actix::spawn(async move {
+            backoff_sleep.await;
+            addr.do_send(RetryTask {
+                id: task_id,
+                attempt,
+            });
+        });
+    }
+}
+
+fn lease_expiry(now: DateTime<Utc>, lease: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(lease)
+        .ok()
+        .and_then(|lease| now.checked_add_signed(lease))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+// Stops a local executor whose lease was reclaimed without reporting back
+#[derive(Message)]
+#[rtype(result = "()")]
+pub(crate) struct AbandonTask;
+
+impl Handler<RenewLease> for TaskManagerActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: RenewLease, _ctx: &mut Self::Context) -> Self::Result {
+        self.renew_lease(msg.id, msg.attempt);
+    }
+}
+
+impl Handler<CheckLease> for TaskManagerActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: CheckLease, ctx: &mut Self::Context) -> Self::Result {
+        self.check_lease(msg.id, msg.attempt, ctx);
+    }
+}
+
+impl Handler<RetryTask> for TaskManagerActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: RetryTask, ctx: &mut Self::Context) -> Self::Result {
+        // Cancelled while waiting for the backoff
+        let Some(metadata) = self.task_metadata.get(&msg.id) else {
+            return;
+        };
+        if metadata.status != TaskStatus::Queued || metadata.attempt + 1 != msg.attempt {
+            return;
+        }
+        let Some(spec) = self.specs.get(&msg.id).cloned() else {
+            return;
+        };
+
+        let seed = metadata.seed.unwrap_or_default();
+        self.launch_task(msg.id, &spec, seed, msg.attempt, ctx);
+    }
+}
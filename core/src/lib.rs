@@ -1,22 +1,127 @@
-use actix::{Actor, ActorContext, Addr, AsyncContext, Context, Handler, Message, MessageResult};
+use actix::{
+    fut, Actor, ActorContext, ActorFutureExt, Addr, AsyncContext, Context, Handler, Message,
+    MessageResult, Recipient, ResponseActFuture, SpawnHandle, WrapFuture,
+};
 use chrono::{DateTime, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
+use tracing::{debug, info, warn, Span};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub mod archive;
+pub mod chaos;
+pub mod clock;
+pub mod distributions;
+pub mod index;
+pub mod lease;
+pub mod lightweight;
+pub mod load;
+pub mod metrics;
+pub mod namespaces;
+pub mod otel;
+pub mod query;
+pub mod query_language;
+pub mod quotas;
+pub mod router;
+pub mod scheduler;
+pub mod search;
+pub mod snapshot;
+pub mod spans;
+pub mod stats;
+pub mod task_types;
+pub mod templates;
+pub mod workers;
+
+pub use archive::{ArchiveQuery, TaskArchive};
+pub use chaos::{
+    ChaosConfig, FaultPlan, FaultProfile, IntermittentFailure, LatencySpike, ProgressFault,
+};
+pub use clock::{Clock, ManualClock, SharedClock, SystemClock};
+pub use distributions::DurationDistribution;
+pub use index::{task_key, TaskFilter, TaskKey};
+pub use lease::{RenewLease, RetryPolicy, DEFAULT_LEASE_DURATION};
+pub use lightweight::ExecutionMode;
+pub use load::{
+    ArrivalPattern, GetLoadStats, LoadGeneratorActor, LoadGeneratorConfig, LoadMixEntry, LoadStats,
+    StartLoad, StopLoad,
+};
+pub use metrics::{MeasureMailboxLatency, Metrics, DURATION_BUCKETS, METRICS_PREFIX};
+pub use namespaces::{
+    validate_namespace, ConfigureNamespace, GetNamespace, GetNamespaceTask, GetNamespaceTasks,
+    ListNamespaces, NamespaceConfig, NamespaceInfo, Visibility, DEFAULT_NAMESPACE,
+};
+pub use otel::{OtlpConfig, OtlpExporter, OtlpProtocol, TRACEPARENT};
+pub use query::{SortField, SortOrder, TaskCursor, TaskPage, TaskQuery, MAX_PAGE_SIZE};
+pub use query_language::QUERY_FIELDS;
+pub use quotas::{Quota, QuotaUsage, SubmitTask, QUOTA_WINDOW};
+pub use router::{shard_for, TaskManagerRouter};
+pub use scheduler::{GetSchedulerReport, QueueReport, SchedulerReport};
+pub use search::{Fragment, Highlight, SearchHit, SearchResults};
+pub use snapshot::{SnapshotCell, TaskReader, TaskSnapshot, DEFAULT_SNAPSHOT_INTERVAL};
+pub use stats::{
+    DurationPercentiles, StatsSummary, StatusCounts, TaskStats, WindowStats, ALL_TASKS_WINDOW,
+    STATS_WINDOWS,
+};
+pub use task_types::{
+    ParamKind, ParamSchema, TaskParams, TaskTypeDefinition, TaskTypeInfo, TaskTypeRegistry,
+};
+pub use templates::{
+    CreateTemplate, DeleteTemplate, GetTemplate, InstantiateTemplate, ListTemplates, TaskTemplate,
+    TaskTemplateActor, TemplateOverrides, UpdateTemplate,
+};
+pub use workers::{
+    Affinity, GetWorkers, RegisterWorker, TaskAssignment, WorkerCommand, WorkerDisconnected,
+    WorkerInfo, WorkerReport, WorkerRequest,
+};
+
+// How often the manager looks for finished tasks past their retention window
+const ARCHIVE_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum TaskStatus {
+    // Waiting for a remote worker to lease it
+    Queued,
+    #[default]
     InProgress,
     Completed,
     Error,
 }
 
+impl TaskStatus {
+    // Queued and running tasks can still be cancelled and are never archived
+    pub fn is_active(&self) -> bool {
+        matches!(self, TaskStatus::Queued | TaskStatus::InProgress)
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['_', '-'], "").as_str() {
+            "queued" => Ok(TaskStatus::Queued),
+            "inprogress" => Ok(TaskStatus::InProgress),
+            "completed" => Ok(TaskStatus::Completed),
+            "error" => Ok(TaskStatus::Error),
+            _ => Err(format!("Invalid task status: '{}'", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskMetadata {
     pub id: Uuid,
     pub name: String,
     pub message: String,
+    // Name the task type is registered under, see CreateTask::type_name.
+    // Empty for tasks recorded before it was kept
+    #[serde(default)]
+    pub task_type: String,
     pub status: TaskStatus,
     pub started_at: DateTime<Utc>,
     pub finished_at: Option<DateTime<Utc>>,
@@ -26,6 +131,82 @@ pub struct TaskMetadata {
     pub actual_duration_ms: Option<u64>,
     pub cancelled_at: Option<DateTime<Utc>>,
     pub timeout_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Seed of the RNG behind every random decision for this task,
+    // pass it back in CreateTask to replay the same run
+    #[serde(default)]
+    pub seed: Option<u64>,
+    // Fraction of the simulated work done, 0.0-1.0
+    #[serde(default)]
+    pub progress: f32,
+    // Faults injected into this run, see FaultPlan::describe
+    #[serde(default)]
+    pub faults: Vec<String>,
+    // Set for tasks routed to remote workers, started_at is reset when a
+    // worker takes the task
+    #[serde(default)]
+    pub queued_at: Option<DateTime<Utc>>,
+    // Name of the remote worker running the task
+    #[serde(default)]
+    pub worker: Option<String>,
+    // Why a queued remote task has not been assigned yet
+    #[serde(default)]
+    pub queue_reason: Option<String>,
+    // Which run of the task this is, retries after a lost executor count up
+    #[serde(default = "default_attempt")]
+    pub attempt: u32,
+    #[serde(default = "default_attempt")]
+    pub max_attempts: u32,
+    // The executor must heartbeat before this or the task is reclaimed
+    #[serde(default)]
+    pub lease_expires_at: Option<DateTime<Utc>>,
+    // Why earlier attempts were abandoned, oldest first
+    #[serde(default)]
+    pub attempt_errors: Vec<String>,
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+}
+
+fn default_attempt() -> u32 {
+    1
+}
+
+fn default_namespace() -> String {
+    DEFAULT_NAMESPACE.to_string()
+}
+
+// Same defaults as a record deserialized without the optional fields
+impl Default for TaskMetadata {
+    fn default() -> Self {
+        Self {
+            id: Uuid::default(),
+            name: String::new(),
+            message: String::new(),
+            task_type: String::new(),
+            status: TaskStatus::default(),
+            started_at: DateTime::default(),
+            finished_at: None,
+            result: None,
+            error: None,
+            timeout_ms: 0,
+            actual_duration_ms: None,
+            cancelled_at: None,
+            timeout_at: None,
+            tags: Vec::new(),
+            seed: None,
+            progress: 0.0,
+            faults: Vec::new(),
+            queued_at: None,
+            worker: None,
+            queue_reason: None,
+            attempt: default_attempt(),
+            max_attempts: default_attempt(),
+            lease_expires_at: None,
+            attempt_errors: Vec::new(),
+            namespace: default_namespace(),
+        }
+    }
 }
 
 impl TaskMetadata {
@@ -37,15 +218,23 @@ impl TaskMetadata {
     }
 
     pub fn mark_completed(&mut self, result: String) {
+        self.mark_completed_at(result, Utc::now());
+    }
+
+    pub fn mark_completed_at(&mut self, result: String, now: DateTime<Utc>) {
         self.status = TaskStatus::Completed;
-        self.finished_at = Some(Utc::now());
+        self.progress = 1.0;
+        self.finished_at = Some(now);
         self.result = Some(result);
         self.calculate_duration();
     }
 
     pub fn mark_error(&mut self, error: String, is_timeout: bool) {
+        self.mark_error_at(error, is_timeout, Utc::now());
+    }
+
+    pub fn mark_error_at(&mut self, error: String, is_timeout: bool, now: DateTime<Utc>) {
         self.status = TaskStatus::Error;
-        let now = Utc::now();
         self.finished_at = Some(now);
         self.error = Some(error);
         if is_timeout {
@@ -55,8 +244,11 @@ impl TaskMetadata {
     }
 
     pub fn mark_cancelled(&mut self) {
+        self.mark_cancelled_at(Utc::now());
+    }
+
+    pub fn mark_cancelled_at(&mut self, now: DateTime<Utc>) {
         self.status = TaskStatus::Error;
-        let now = Utc::now();
         self.finished_at = Some(now);
         self.cancelled_at = Some(now);
         self.error = Some("Task was cancelled".to_string());
@@ -75,15 +267,27 @@ impl TaskMetadata {
 #[derive(Debug)]
 pub struct TaskActor {
     pub metadata: TaskMetadata,
+    finished_recipient: Option<Recipient<TaskFinished>>,
+    progress_recipient: Option<Recipient<TaskProgress>>,
+    // Where heartbeats go and how often, see with_lease_recipient
+    lease: Option<(Recipient<RenewLease>, Duration)>,
+    clock: SharedClock,
+    span: Span,
 }
 
 impl TaskActor {
     pub fn new(name: String, message: String, timeout_ms: u64) -> Self {
         Self {
+            finished_recipient: None,
+            progress_recipient: None,
+            lease: None,
+            clock: Arc::new(SystemClock),
+            span: Span::none(),
             metadata: TaskMetadata {
                 id: Uuid::new_v4(),
                 name,
                 message,
+                task_type: String::new(),
                 status: TaskStatus::InProgress,
                 started_at: Utc::now(),
                 finished_at: None,
@@ -93,28 +297,156 @@ impl TaskActor {
                 actual_duration_ms: None,
                 cancelled_at: None,
                 timeout_at: None,
+                tags: Vec::new(),
+                seed: None,
+                progress: 0.0,
+                faults: Vec::new(),
+                queued_at: None,
+                worker: None,
+                queue_reason: None,
+                attempt: 1,
+                max_attempts: 1,
+                lease_expires_at: None,
+                attempt_errors: Vec::new(),
+                namespace: default_namespace(),
             },
         }
     }
+
+    // Runs under an id handed out by the manager, retries reuse the task's id
+    pub fn with_id(mut self, id: Uuid) -> Self {
+        self.metadata.id = id;
+        self
+    }
+
+    pub fn with_namespace(mut self, namespace: String) -> Self {
+        self.metadata.namespace = namespace;
+        self
+    }
+
+    pub fn with_task_type(mut self, task_type: &str) -> Self {
+        self.metadata.task_type = task_type.to_string();
+        self
+    }
+
+    pub fn with_attempt(mut self, attempt: u32) -> Self {
+        self.metadata.attempt = attempt;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.metadata.tags = tags;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.metadata.seed = Some(seed);
+        self
+    }
+
+    // Timestamps and timers come from `clock`, started_at is re-read from it
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.metadata.started_at = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    // Final metadata is sent here when the actor stops
+    pub fn with_finished_recipient(mut self, recipient: Recipient<TaskFinished>) -> Self {
+        self.finished_recipient = Some(recipient);
+        self
+    }
+
+    // Progress reported by the work loop is forwarded here
+    pub fn with_progress_recipient(mut self, recipient: Recipient<TaskProgress>) -> Self {
+        self.progress_recipient = Some(recipient);
+        self
+    }
+
+    pub fn with_faults(mut self, faults: Vec<String>) -> Self {
+        self.metadata.faults = faults;
+        self
+    }
+
+    // Everything the actor logs goes into `span`, the manager's span of
+    // the task
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    // Once the work starts a heartbeat is sent here every `interval` for
+    // as long as the actor runs
+    pub fn with_lease_recipient(
+        mut self,
+        recipient: Recipient<RenewLease>,
+        interval: Duration,
+    ) -> Self {
+        self.lease = Some((recipient, interval));
+        self
+    }
+
+    fn start_heartbeats(&self, ctx: &mut Context<Self>) {
+        let Some((recipient, interval)) = self.lease.clone() else {
+            return;
+        };
+
+        // Sent straight from here rather than through the actor so a
+        // heartbeat due before a lease check always reaches the manager first
+        let clock = self.clock.clone();
+        let task = ctx.address().downgrade();
+        let renew = RenewLease {
+            id: self.metadata.id,
+            attempt: self.metadata.attempt,
+        };
+        actix::spawn(async move {
+            loop {
+                clock.sleep(interval).await;
+                if !task.upgrade().is_some_and(|addr| addr.connected()) {
+                    break;
+                }
+                recipient.do_send(renew.clone());
+            }
+        });
+    }
 }
 
 impl Actor for TaskActor {
     type Context = Context<Self>;
 
     fn started(&mut self, _ctx: &mut Self::Context) {
-        println!("TaskActor {} started", self.metadata.id);
+        debug!(parent: &self.span, "task actor started");
     }
 
     fn stopped(&mut self, _ctx: &mut Self::Context) {
-        println!("TaskActor {} stopped", self.metadata.id);
+        debug!(parent: &self.span, "task actor stopped");
+
+        if let Some(recipient) = self.finished_recipient.take() {
+            recipient.do_send(TaskFinished {
+                id: self.metadata.id,
+                metadata: self.metadata.clone(),
+            });
+        }
     }
 }
 
-#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[derive(Message, Debug, Clone, Default, Serialize, Deserialize)]
 #[rtype(result = "()")]
 pub struct StartTask {
     #[serde(with = "duration_serde")]
     pub duration: Duration,
+    // When set the work ends with this error instead of completing
+    #[serde(default)]
+    pub failure: Option<String>,
+    #[serde(default)]
+    pub faults: FaultPlan,
+}
+
+// Sent by the work loop after each step
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "()")]
+pub struct ReportProgress {
+    pub progress: f32,
 }
 
 #[derive(Message, Debug, Clone, Serialize, Deserialize)]
@@ -166,45 +498,130 @@ impl Handler<StartTask> for TaskActor {
     type Result = ();
 
     fn handle(&mut self, msg: StartTask, ctx: &mut Self::Context) -> Self::Result {
-        println!(
-            "Starting task {} with duration {:?} (timeout: {}ms)",
-            self.metadata.name, msg.duration, self.metadata.timeout_ms
+        info!(
+            parent: &self.span,
+            work_ms = msg.duration.as_millis() as u64,
+            timeout_ms = self.metadata.timeout_ms,
+            attempt = self.metadata.attempt,
+            "task started"
         );
 
+        // A frozen executor does nothing at all, not even time itself out,
+        // only its lease expiring gets the task back
+        if msg.faults.heartbeat_loss {
+            return;
+        }
+        self.start_heartbeats(ctx);
+
         let addr = ctx.address();
         let timeout = Duration::from_millis(self.metadata.timeout_ms);
 
-        // Set up timeout handler
-        ctx.run_later(timeout, move |_act, ctx| {
-            ctx.address().do_send(TimeoutTask);
-        });
+        // Set up timeout handler, dropped with the actor once it stops
+        let timeout_sleep = self.clock.sleep(timeout);
+        ctx.spawn(
+            timeout_sleep
+                .into_actor(self)
+                .map(|_, _act, ctx| ctx.notify(TimeoutTask)),
+        );
 
-        // Start the actual work
+        // Start the actual work, one sleep per progress step. Offsets are
+        // measured from the start so a manual clock can jump past several.
+        let clock = self.clock.clone();
+        let work_start = clock.now();
+        let plan = msg.faults;
+        let offsets = plan.step_offsets(msg.duration);
         let work_addr = addr.clone();
         actix::spawn(async move {
-            tokio::time::sleep(msg.duration).await;
-            let _ = work_addr
-                .send(CompleteTask {
-                    result: "Task completed successfully".to_string(),
-                })
-                .await;
+            if plan.hang {
+                return;
+            }
+
+            let steps = offsets.len();
+            for (index, offset) in offsets.into_iter().enumerate() {
+                let progress = (index + 1) as f32 / steps as f32;
+                if plan.stall_at.is_some_and(|stall_at| progress > stall_at) {
+                    return;
+                }
+
+                let elapsed = (clock.now() - work_start).to_std().unwrap_or_default();
+                clock.sleep(offset.saturating_sub(elapsed)).await;
+                work_addr.do_send(ReportProgress { progress });
+            }
+
+            let failure = if plan.panic {
+                run_panicking_work().await
+            } else {
+                msg.failure
+            };
+
+            match failure {
+                Some(error) => {
+                    let _ = work_addr.send(ErrorTask { error }).await;
+                }
+                None => {
+                    let _ = work_addr
+                        .send(CompleteTask {
+                            result: "Task completed successfully".to_string(),
+                        })
+                        .await;
+                }
+            }
         });
     }
 }
 
+// Panics inside a separate local task so the panic is caught by the runtime
+// instead of taking down the arbiter, returns the panic as an error message
+async fn run_panicking_work() -> Option<String> {
+    let outcome = tokio::task::spawn_local(async {
+        panic!("Injected panic during task work");
+    })
+    .await;
+
+    match outcome {
+        Ok(()) => None,
+        Err(e) if e.is_panic() => {
+            let payload = e.into_panic();
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            Some(format!("Task panicked: {}", message))
+        }
+        Err(e) => Some(format!("Task work was aborted: {}", e)),
+    }
+}
+
+impl Handler<ReportProgress> for TaskActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: ReportProgress, _ctx: &mut Self::Context) -> Self::Result {
+        if self.metadata.status != TaskStatus::InProgress {
+            return;
+        }
+
+        self.metadata.progress = msg.progress;
+        if let Some(recipient) = &self.progress_recipient {
+            recipient.do_send(TaskProgress {
+                id: self.metadata.id,
+                progress: msg.progress,
+            });
+        }
+    }
+}
+
 impl Handler<TimeoutTask> for TaskActor {
     type Result = ();
 
     fn handle(&mut self, _msg: TimeoutTask, ctx: &mut Self::Context) -> Self::Result {
         if self.metadata.status == TaskStatus::InProgress {
-            self.metadata.mark_error(
+            self.metadata.mark_error_at(
                 format!("Task timed out after {}ms", self.metadata.timeout_ms),
                 true,
+                self.clock.now(),
             );
-            println!(
-                "Task {} timed out after {}ms",
-                self.metadata.name, self.metadata.timeout_ms
-            );
+            spans::record_outcome(&self.span, &self.metadata);
             ctx.stop();
         }
     }
@@ -214,13 +631,9 @@ impl Handler<CompleteTask> for TaskActor {
     type Result = ();
 
     fn handle(&mut self, msg: CompleteTask, ctx: &mut Self::Context) -> Self::Result {
-        self.metadata.mark_completed(msg.result);
-        println!(
-            "Task {} completed in {}ms: {:?}",
-            self.metadata.name,
-            self.metadata.actual_duration_ms.unwrap_or(0),
-            self.metadata.result
-        );
+        self.metadata
+            .mark_completed_at(msg.result, self.clock.now());
+        spans::record_outcome(&self.span, &self.metadata);
         ctx.stop();
     }
 }
@@ -229,13 +642,9 @@ impl Handler<ErrorTask> for TaskActor {
     type Result = ();
 
     fn handle(&mut self, msg: ErrorTask, ctx: &mut Self::Context) -> Self::Result {
-        self.metadata.mark_error(msg.error, false);
-        println!(
-            "Task {} failed after {}ms: {:?}",
-            self.metadata.name,
-            self.metadata.actual_duration_ms.unwrap_or(0),
-            self.metadata.error
-        );
+        self.metadata
+            .mark_error_at(msg.error, false, self.clock.now());
+        spans::record_outcome(&self.span, &self.metadata);
         ctx.stop();
     }
 }
@@ -244,12 +653,19 @@ impl Handler<CancelTask> for TaskActor {
     type Result = ();
 
     fn handle(&mut self, _msg: CancelTask, ctx: &mut Self::Context) -> Self::Result {
-        self.metadata.mark_cancelled();
-        println!(
-            "Task {} cancelled after {}ms",
-            self.metadata.name,
-            self.metadata.actual_duration_ms.unwrap_or(0)
-        );
+        self.metadata.mark_cancelled_at(self.clock.now());
+        spans::record_outcome(&self.span, &self.metadata);
+        ctx.stop();
+    }
+}
+
+impl Handler<lease::AbandonTask> for TaskActor {
+    type Result = ();
+
+    fn handle(&mut self, _msg: lease::AbandonTask, ctx: &mut Self::Context) -> Self::Result {
+        // The manager has already given the task to someone else
+        self.finished_recipient = None;
+        warn!(parent: &self.span, "task abandoned after its lease expired");
         ctx.stop();
     }
 }
@@ -265,20 +681,208 @@ impl Handler<GetTaskStatus> for TaskActor {
 #[derive(Debug)]
 pub struct TaskManagerActor {
     tasks: HashMap<Uuid, Addr<TaskActor>>,
-    task_metadata: HashMap<Uuid, TaskMetadata>,
+    task_metadata: snapshot::TaskTable,
+    archive: Option<TaskArchive>,
+    retention: Duration,
+    // Expired tasks whose archive write has not finished, kept in memory
+    // until it has
+    archiving: HashSet<Uuid>,
+    clock: SharedClock,
+    // Hands out the per-task seeds of tasks created without one
+    rng: StdRng,
+    chaos: ChaosConfig,
+    workers: workers::WorkerPool,
+    lease_duration: Duration,
+    // Creation requests of tasks that may have to be started later, those
+    // with a retry policy or waiting for a namespace slot
+    specs: HashMap<Uuid, CreateTask>,
+    namespaces: namespaces::Namespaces,
+    scheduler: scheduler::FairScheduler,
+    execution_mode: ExecutionMode,
+    lightweight: lightweight::LightweightTasks,
+    snapshot: SnapshotCell,
+    snapshot_interval: Duration,
+    metrics: Metrics,
+    // Open spans of the tasks that have not finished, see spans::task_span
+    spans: HashMap<Uuid, Span>,
+    // Status polls of local tasks after their timeout, cancelled when the
+    // task reports back first
+    watchdogs: HashMap<Uuid, SpawnHandle>,
+    // Task type names worker capabilities are checked against
+    task_types: TaskTypeRegistry,
 }
 
 impl TaskManagerActor {
     pub fn new() -> Self {
         Self {
             tasks: HashMap::new(),
-            task_metadata: HashMap::new(),
+            task_metadata: snapshot::TaskTable::default(),
+            archive: None,
+            retention: Duration::ZERO,
+            archiving: HashSet::new(),
+            clock: Arc::new(SystemClock),
+            rng: StdRng::from_entropy(),
+            chaos: ChaosConfig::default(),
+            workers: workers::WorkerPool::default(),
+            lease_duration: DEFAULT_LEASE_DURATION,
+            specs: HashMap::new(),
+            namespaces: namespaces::initial_namespaces(Utc::now()),
+            scheduler: scheduler::FairScheduler::default(),
+            execution_mode: ExecutionMode::default(),
+            lightweight: lightweight::LightweightTasks::default(),
+            snapshot: SnapshotCell::default(),
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            metrics: Metrics::default(),
+            spans: HashMap::new(),
+            watchdogs: HashMap::new(),
+            task_types: TaskTypeRegistry::with_builtin_types(),
         }
     }
 
+    // Makes the sequence of per-task seeds, and so every random
+    // decision of the run, reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    // Types registered beyond the built-in ones, for worker capabilities
+    pub fn with_task_types(mut self, task_types: TaskTypeRegistry) -> Self {
+        self.task_types = task_types;
+        self
+    }
+
+    // Shared with every task the manager starts
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // How long executors may go without a heartbeat, local tasks send
+    // three per lease
+    pub fn with_lease_duration(mut self, lease_duration: Duration) -> Self {
+        self.lease_duration = lease_duration;
+        self
+    }
+
+    // How local tasks are run, remote tasks are unaffected
+    pub fn with_execution_mode(mut self, execution_mode: ExecutionMode) -> Self {
+        self.execution_mode = execution_mode;
+        self
+    }
+
+    // Publishes snapshots into `cell` instead of one of its own, for
+    // readers set up before the manager is built
+    pub fn with_snapshot_cell(mut self, cell: SnapshotCell) -> Self {
+        self.snapshot = cell;
+        self
+    }
+
+    // At most one snapshot per `interval` is published, see TaskReader
+    pub fn with_snapshot_interval(mut self, interval: Duration) -> Self {
+        self.snapshot_interval = interval;
+        self
+    }
+
+    // Task counters go here as snapshots are published, share one between
+    // shards to count them all together
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    // Finished tasks older than `retention` are moved into the archive
+    // instead of being kept in memory forever
+    pub fn with_archive(mut self, archive: TaskArchive, retention: Duration) -> Self {
+        self.archive = Some(archive);
+        self.retention = retention;
+        self
+    }
+
     fn cleanup_finished_task(&mut self, task_id: Uuid) {
-        if let Some(_addr) = self.tasks.remove(&task_id) {
-            println!("Cleaning up finished task: {}", task_id);
+        if self.tasks.remove(&task_id).is_some() {
+            debug!(parent: &self.span_of(&task_id), "task actor cleaned up");
+        }
+    }
+
+    // Moves finished tasks past their namespace's retention, or the
+    // manager's, into the archive. Without an archive only namespaces with
+    // their own retention are pruned, and their tasks are dropped.
+    // Compressing and writing happen on a blocking thread, the tasks leave
+    // memory once they are on disk.
+    fn archive_expired_tasks(&mut self) -> ResponseActFuture<Self, usize> {
+        let expired = self.expired_tasks();
+        if expired.is_empty() {
+            return Box::pin(fut::ready(0));
+        }
+        let ids: Vec<Uuid> = expired.iter().map(|task| task.id).collect();
+
+        let Some(archive) = self.archive.clone() else {
+            info!(count = ids.len(), "dropped expired tasks");
+            self.remove_expired(&ids);
+            return Box::pin(fut::ready(ids.len()));
+        };
+
+        self.archiving.extend(ids.iter().copied());
+        let write = tokio::task::spawn_blocking(move || archive.append(&expired));
+        Box::pin(write.into_actor(self).map(move |written, act, _ctx| {
+            for id in &ids {
+                act.archiving.remove(id);
+            }
+            match written {
+                Ok(Ok(count)) => {
+                    info!(count, "archived expired tasks");
+                    act.remove_expired(&ids);
+                    count
+                }
+                Ok(Err(e)) => {
+                    tracing::error!(error = %e, "failed to archive expired tasks");
+                    0
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "archive write did not finish");
+                    0
+                }
+            }
+        }))
+    }
+
+    // Finished tasks past their namespace's retention, or the manager's
+    // when there is an archive, that are not being archived already
+    fn expired_tasks(&self) -> Vec<TaskMetadata> {
+        let now = self.clock.now();
+        self.task_metadata
+            .values()
+            .filter(|task| !task.status.is_active())
+            .filter(|task| !self.archiving.contains(&task.id))
+            .filter(|task| {
+                let retention = match self.namespace_retention(&task.namespace) {
+                    Some(retention) => retention,
+                    None if self.archive.is_some() => self.retention,
+                    None => return false,
+                };
+                let cutoff = chrono::Duration::from_std(retention)
+                    .ok()
+                    .and_then(|retention| now.checked_sub_signed(retention))
+                    .unwrap_or(DateTime::<Utc>::MIN_UTC);
+                task.finished_at
+                    .is_some_and(|finished_at| finished_at <= cutoff)
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn remove_expired(&mut self, ids: &[Uuid]) {
+        let expired: Vec<TaskMetadata> = ids
+            .iter()
+            .filter_map(|id| self.task_metadata.get(id).cloned())
+            .collect();
+        self.record_archived_runtime(&expired);
+        for id in ids {
+            self.task_metadata.remove(id);
+            self.tasks.remove(id);
+            self.specs.remove(id);
+            self.watchdogs.remove(id);
         }
     }
 }
@@ -292,30 +896,92 @@ impl Default for TaskManagerActor {
 impl Actor for TaskManagerActor {
     type Context = Context<Self>;
 
-    fn started(&mut self, _ctx: &mut Self::Context) {
-        println!("TaskManagerActor started");
+    fn started(&mut self, ctx: &mut Self::Context) {
+        info!("task manager started");
+
+        // Namespaces can set a retention even without an archive
+        ctx.run_interval(ARCHIVE_SWEEP_INTERVAL, |act, ctx| {
+            ctx.spawn(act.archive_expired_tasks().map(|_, _, _| ()));
+        });
+        ctx.run_interval(self.snapshot_interval, |act, _ctx| {
+            act.publish_snapshot();
+        });
     }
 
     fn stopped(&mut self, _ctx: &mut Self::Context) {
-        println!("TaskManagerActor stopped");
+        info!("task manager stopped");
     }
 }
 
-#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[derive(Message, Debug, Clone, Default, Serialize, Deserialize)]
 #[rtype(result = "Uuid")]
 pub struct CreateTask {
     pub name: String,
     pub message: String,
     pub task_type: TaskType,
+    // Name of the type in the TaskTypeRegistry it was resolved from
+    #[serde(default)]
+    pub type_name: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Fixed RNG seed for this task, drawn from the manager when missing
+    #[serde(default)]
+    pub seed: Option<u64>,
+    // Overrides the manager's per-type and global fault profiles
+    #[serde(default)]
+    pub faults: Option<FaultProfile>,
+    #[serde(default)]
+    pub executor: Executor,
+    // Runs the task again when its executor is lost, see RetryPolicy
+    #[serde(default)]
+    pub retry: Option<RetryPolicy>,
+    // Worker labels a remote task needs or prefers, ignored for local tasks
+    #[serde(default)]
+    pub affinity: Affinity,
+    // DEFAULT_NAMESPACE when unset
+    #[serde(default)]
+    pub namespace: Option<String>,
+    // W3C traceparent of whatever asked for the task, its span continues
+    // that trace when spans are exported, see otel
+    #[serde(default)]
+    pub traceparent: Option<String>,
+    // Picked by TaskManagerRouter so the task lands on the shard that owns
+    // its id, a fresh one is drawn when unset
+    #[serde(skip)]
+    pub id: Option<Uuid>,
+}
+
+impl CreateTask {
+    // The registered type name, TaskType::kind for tasks built directly
+    pub fn type_name(&self) -> &str {
+        self.type_name
+            .as_deref()
+            .unwrap_or_else(|| self.task_type.kind())
+    }
+}
+
+// Where a task runs: a local TaskActor, or the next remote worker that
+// leases it. Remote tasks stay Queued until then.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Executor {
+    #[default]
+    Local,
+    Remote,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TaskType {
     Quick {
         timeout_ms: Option<u64>,
+        // Work duration, a fixed share of the timeout when unset
+        #[serde(default)]
+        duration: Option<DurationDistribution>,
     },
     Long {
         timeout_ms: Option<u64>,
+        #[serde(default)]
+        duration: Option<DurationDistribution>,
     },
     Error {
         timeout_ms: Option<u64>,
@@ -325,14 +991,34 @@ pub enum TaskType {
         name: String,
         timeout_ms: u64,
         failure_rate: Option<f32>, // 0.0-1.0, probability of failure
+        #[serde(default)]
+        duration: Option<DurationDistribution>,
     },
 }
 
+impl Default for TaskType {
+    fn default() -> Self {
+        TaskType::Quick {
+            timeout_ms: None,
+            duration: None,
+        }
+    }
+}
+
 impl TaskType {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TaskType::Quick { .. } => "quick",
+            TaskType::Long { .. } => "long",
+            TaskType::Error { .. } => "error",
+            TaskType::Custom { .. } => "custom",
+        }
+    }
+
     pub fn get_timeout(&self) -> Duration {
         let timeout_ms = match self {
-            TaskType::Quick { timeout_ms } => timeout_ms.unwrap_or(2000), // 2s default
-            TaskType::Long { timeout_ms } => timeout_ms.unwrap_or(10000), // 10s default
+            TaskType::Quick { timeout_ms, .. } => timeout_ms.unwrap_or(2000), // 2s default
+            TaskType::Long { timeout_ms, .. } => timeout_ms.unwrap_or(10000), // 10s default
             TaskType::Error { timeout_ms, .. } => timeout_ms.unwrap_or(5000), // 5s default
             TaskType::Custom { timeout_ms, .. } => *timeout_ms,
         };
@@ -349,12 +1035,46 @@ impl TaskType {
         }
     }
 
+    // Sampled from the configured distribution, otherwise a fixed share of
+    // the timeout (should be less than timeout for successful completion)
+    pub fn sample_work_duration<R: Rng + ?Sized>(&self, rng: &mut R) -> Duration {
+        let timeout = self.get_timeout();
+        match self {
+            TaskType::Quick {
+                duration: Some(duration),
+                ..
+            }
+            | TaskType::Long {
+                duration: Some(duration),
+                ..
+            }
+            | TaskType::Custom {
+                duration: Some(duration),
+                ..
+            } => duration.sample(rng),
+            TaskType::Quick { .. } => Duration::from_millis(timeout.as_millis() as u64 * 3 / 4), // 75% of timeout
+            TaskType::Long { .. } => Duration::from_millis(timeout.as_millis() as u64 * 8 / 10), // 80% of timeout
+            TaskType::Error { error_type, .. } => {
+                match error_type {
+                    ErrorType::Timeout => timeout + Duration::from_millis(1000), // Intentionally exceed timeout
+                    ErrorType::Random => Duration::from_millis(timeout.as_millis() as u64 / 2), // 50% of timeout
+                    _ => Duration::from_millis(timeout.as_millis() as u64 / 3), // 33% of timeout
+                }
+            }
+            TaskType::Custom { .. } => Duration::from_millis(timeout.as_millis() as u64 * 3 / 4), // 75% of timeout
+        }
+    }
+
     pub fn should_fail(&self) -> Option<ErrorType> {
+        self.should_fail_with(&mut rand::thread_rng())
+    }
+
+    pub fn should_fail_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<ErrorType> {
         match self {
             TaskType::Error { error_type, .. } => Some(error_type.clone()),
             TaskType::Custom { failure_rate, .. } => {
                 if let Some(rate) = failure_rate {
-                    if rand::random::<f32>() < *rate {
+                    if rng.gen::<f32>() < *rate {
                         Some(ErrorType::Random)
                     } else {
                         None
@@ -368,7 +1088,7 @@ impl TaskType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ErrorType {
     Immediate,       // Fails immediately
     Timeout,         // Fails after timeout
@@ -377,6 +1097,19 @@ pub enum ErrorType {
     ValidationError, // Simulates validation error
 }
 
+impl ErrorType {
+    pub fn failure_message(&self) -> String {
+        match self {
+            ErrorType::Immediate => "Immediate failure simulation",
+            ErrorType::Timeout => "Timeout simulation",
+            ErrorType::Random => "Random failure simulation",
+            ErrorType::NetworkError => "Network error simulation: connection reset by peer",
+            ErrorType::ValidationError => "Validation error simulation: input rejected",
+        }
+        .to_string()
+    }
+}
+
 #[derive(Message, Debug, Clone, Serialize, Deserialize)]
 #[rtype(result = "Vec<TaskMetadata>")]
 pub struct GetAllTasks;
@@ -400,6 +1133,31 @@ pub struct TaskFinished {
     pub metadata: TaskMetadata,
 }
 
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "()")]
+pub struct TaskProgress {
+    pub id: Uuid,
+    pub progress: f32,
+}
+
+// Sets or, with `profile: None`, clears the fault profile for one task
+// type kind, or the global one when `task_type` is None
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "()")]
+pub struct SetFaultProfile {
+    pub task_type: Option<String>,
+    pub profile: Option<FaultProfile>,
+}
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "ChaosConfig")]
+pub struct GetChaosConfig;
+
+// Forces an archive sweep, returns the number of tasks moved out of memory
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "usize")]
+pub struct ArchiveExpiredTasks;
+
 // Enhanced Error Types for Better Error Handling
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ApiErrorType {
@@ -461,19 +1219,19 @@ impl ApiError {
             ),
             details: Some(serde_json::json!({
                 "task_id": task_id,
-                "allowed_statuses": ["InProgress"]
+                "allowed_statuses": ["Queued", "InProgress"]
             })),
             error_code: "TASK_ALREADY_COMPLETED".to_string(),
         }
     }
 
-    pub fn invalid_task_type(task_type: &str) -> Self {
+    pub fn invalid_task_type(task_type: &str, valid_types: &[String]) -> Self {
         Self {
             error_type: ApiErrorType::InvalidTaskType,
             message: format!("Invalid task type: '{}'", task_type),
             details: Some(serde_json::json!({
                 "provided_type": task_type,
-                "valid_types": ["quick", "long", "error", "custom"]
+                "valid_types": valid_types
             })),
             error_code: "INVALID_TASK_TYPE".to_string(),
         }
@@ -521,6 +1279,9 @@ pub struct TaskCreateResponse {
 pub struct TaskListResponse {
     pub tasks: Vec<TaskMetadata>,
     pub total: usize,
+    // Passed back as `cursor` for the next page, unset on the last one
+    #[serde(default)]
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -536,56 +1297,155 @@ impl Handler<CreateTask> for TaskManagerActor {
     type Result = MessageResult<CreateTask>;
 
     fn handle(&mut self, msg: CreateTask, ctx: &mut Self::Context) -> Self::Result {
-        let timeout = msg.task_type.get_timeout();
-        let task_name = if msg.name.is_empty() {
-            msg.task_type.get_name()
+        MessageResult(self.create_task(msg, ctx))
+    }
+}
+
+impl TaskManagerActor {
+    pub(crate) fn create_task(&mut self, msg: CreateTask, ctx: &mut Context<Self>) -> Uuid {
+        let task_id = msg.id.unwrap_or_else(Uuid::new_v4);
+        let seed = msg.seed.unwrap_or_else(|| self.rng.gen());
+        let span = self.open_span(task_id, &msg);
+        info!(parent: &span, executor = ?msg.executor, seed, "task created");
+        self.record_admission(msg.namespace());
+        if !self.has_free_slot(msg.namespace()) {
+            self.hold_task(task_id, msg, seed);
+            return task_id;
+        }
+        self.launch_task(task_id, &msg, seed, 1, ctx);
+
+        // Kept so a task whose executor is lost can be started again
+        if msg.retry.is_some() {
+            self.specs.insert(task_id, msg);
+        }
+        task_id
+    }
+
+    // Starts one attempt of a task. Retries keep the task id, and their
+    // seed is offset by the attempt so the first attempt replays as before.
+    pub(crate) fn launch_task(
+        &mut self,
+        task_id: Uuid,
+        spec: &CreateTask,
+        seed: u64,
+        attempt: u32,
+        ctx: &mut Context<Self>,
+    ) {
+        let timeout = spec.task_type.get_timeout();
+        let task_name = if spec.name.is_empty() {
+            spec.task_type.get_name()
         } else {
-            msg.name.clone()
+            spec.name.clone()
         };
+        let max_attempts = spec.retry.as_ref().map_or(1, |retry| retry.max_attempts);
+        let attempt_errors = self
+            .task_metadata
+            .get(&task_id)
+            .map(|metadata| metadata.attempt_errors.clone())
+            .unwrap_or_default();
+
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(u64::from(attempt - 1)));
+
+        // The task type's own failure is rolled first, then any injected faults
+        let failure = spec.task_type.should_fail_with(&mut rng);
+        let mut plan = spec
+            .faults
+            .as_ref()
+            .or_else(|| self.chaos.profile_for(spec.type_name()))
+            .map(|profile| profile.plan(&mut rng))
+            .unwrap_or_default();
+        let failure = failure.or(plan.failure.clone());
+
+        if spec.executor == Executor::Remote {
+            let now = self.clock.now();
+            let metadata = TaskMetadata {
+                id: task_id,
+                name: task_name,
+                message: spec.message.clone(),
+                task_type: spec.type_name().to_string(),
+                status: TaskStatus::Queued,
+                started_at: now,
+                timeout_ms: timeout.as_millis() as u64,
+                tags: spec.tags.clone(),
+                seed: Some(seed),
+                faults: plan.describe(),
+                queued_at: Some(now),
+                attempt,
+                max_attempts,
+                attempt_errors,
+                namespace: spec.namespace().to_string(),
+                ..Default::default()
+            };
+            let work_duration = spec.task_type.sample_work_duration(&mut rng);
+            let assignment = TaskAssignment::new(
+                &metadata,
+                spec.task_type.clone(),
+                work_duration,
+                failure,
+                plan,
+                spec.affinity.clone(),
+            );
+            self.enqueue_remote(metadata, assignment, ctx);
+            return;
+        }
 
-        let task = TaskActor::new(task_name, msg.message.clone(), timeout.as_millis() as u64);
+        let task = TaskActor::new(task_name, spec.message.clone(), timeout.as_millis() as u64)
+            .with_id(task_id)
+            .with_task_type(spec.type_name())
+            .with_namespace(spec.namespace().to_string())
+            .with_attempt(attempt)
+            .with_tags(spec.tags.clone())
+            .with_seed(seed)
+            .with_faults(plan.describe())
+            .with_clock(self.clock.clone())
+            .with_span(self.open_span(task_id, spec));
+
+        let mut initial_metadata = task.metadata.clone();
+        initial_metadata.max_attempts = max_attempts;
+        initial_metadata.attempt_errors = attempt_errors;
+
+        if self.execution_mode == ExecutionMode::Lightweight {
+            // Drawn in the same order as below so a seed plays out the same
+            let work_duration = match failure {
+                Some(ErrorType::Immediate) => Duration::ZERO,
+                _ => spec.task_type.sample_work_duration(&mut rng),
+            };
+            self.task_metadata.insert(task_id, initial_metadata);
+            self.start_lightweight(task_id, attempt, work_duration, failure, plan, ctx);
+            return;
+        }
 
-        let task_id = task.metadata.id;
-        let initial_metadata = task.metadata.clone();
-        let task_addr = task.start();
+        let task_addr = task
+            .with_finished_recipient(ctx.address().recipient())
+            .with_progress_recipient(ctx.address().recipient())
+            .with_lease_recipient(ctx.address().recipient(), self.lease_duration / 3)
+            .start();
 
         // Store initial metadata
         self.task_metadata.insert(task_id, initial_metadata);
         self.tasks.insert(task_id, task_addr.clone());
+        self.grant_lease(task_id, attempt, ctx);
 
         // Check if task should fail immediately
-        if let Some(error_type) = msg.task_type.should_fail() {
-            match error_type {
-                ErrorType::Immediate => {
-                    let error_addr = task_addr.clone();
-                    actix::spawn(async move {
-                        let _ = error_addr
-                            .send(ErrorTask {
-                                error: "Immediate failure simulation".to_string(),
-                            })
-                            .await;
-                    });
-                    return MessageResult(task_id);
-                }
-                _ => {
-                    // Other error types will be handled during execution
-                }
-            }
+        // Other error types will be handled during execution
+        if let Some(ErrorType::Immediate) = failure {
+            let error_addr = task_addr.clone();
+            actix::spawn(async move {
+                let _ = error_addr
+                    .send(ErrorTask {
+                        error: ErrorType::Immediate.failure_message(),
+                    })
+                    .await;
+            });
+            return;
         }
 
-        // Calculate work duration (should be less than timeout for successful completion)
-        let work_duration = match &msg.task_type {
-            TaskType::Quick { .. } => Duration::from_millis(timeout.as_millis() as u64 * 3 / 4), // 75% of timeout
-            TaskType::Long { .. } => Duration::from_millis(timeout.as_millis() as u64 * 8 / 10), // 80% of timeout
-            TaskType::Error { error_type, .. } => {
-                match error_type {
-                    ErrorType::Timeout => timeout + Duration::from_millis(1000), // Intentionally exceed timeout
-                    ErrorType::Random => Duration::from_millis(timeout.as_millis() as u64 / 2), // 50% of timeout
-                    _ => Duration::from_millis(timeout.as_millis() as u64 / 3), // 33% of timeout
-                }
-            }
-            TaskType::Custom { .. } => Duration::from_millis(timeout.as_millis() as u64 * 3 / 4), // 75% of timeout
-        };
+        // A timeout failure is a hang, the timeout handler does the rest
+        if let Some(ErrorType::Timeout) = failure {
+            plan.hang = true;
+        }
+
+        let work_duration = spec.task_type.sample_work_duration(&mut rng);
 
         // Send start message
         let start_addr = task_addr.clone();
@@ -593,31 +1453,35 @@ impl Handler<CreateTask> for TaskManagerActor {
             let _ = start_addr
                 .send(StartTask {
                     duration: work_duration,
+                    failure: failure
+                        .filter(|error_type| *error_type != ErrorType::Timeout)
+                        .map(|error_type| error_type.failure_message()),
+                    faults: plan,
                 })
                 .await;
         });
 
         // Set up task completion notification
-        let manager_addr = ctx.address();
-        let notification_timeout = timeout + Duration::from_millis(500);
-
-        actix::spawn(async move {
-            tokio::time::sleep(notification_timeout).await;
-
-            // Try to get final status and notify manager
-            if let Ok(final_metadata) = task_addr.send(GetTaskStatus).await {
-                let _ = manager_addr
-                    .send(TaskFinished {
-                        id: task_id,
-                        metadata: final_metadata,
-                    })
-                    .await;
-            } else {
-                // Task already stopped, we'll get notified through other means
+        let notification_sleep = self.clock.sleep(timeout + Duration::from_millis(500));
+        let watchdog = async move {
+            notification_sleep.await;
+            task_addr.send(GetTaskStatus).await.ok()
+        }
+        .into_actor(self)
+        .map(move |final_metadata, _act, ctx| {
+            // Without it the task already stopped, we'll get notified
+            // through other means
+            if let Some(metadata) = final_metadata {
+                ctx.notify(TaskFinished {
+                    id: task_id,
+                    metadata,
+                });
             }
         });
-
-        MessageResult(task_id)
+        let handle = ctx.spawn(watchdog);
+        if let Some(previous) = self.watchdogs.insert(task_id, handle) {
+            ctx.cancel_future(previous);
+        }
     }
 }
 
@@ -640,34 +1504,123 @@ impl Handler<GetTask> for TaskManagerActor {
 impl Handler<CancelTaskById> for TaskManagerActor {
     type Result = bool;
 
-    fn handle(&mut self, msg: CancelTaskById, _ctx: &mut Self::Context) -> Self::Result {
-        if let Some(task_addr) = self.tasks.get(&msg.id) {
+    fn handle(&mut self, msg: CancelTaskById, ctx: &mut Self::Context) -> Self::Result {
+        let cancelled = if self.cancel_remote(msg.id, ctx) {
+            true
+        } else if let Some(task_addr) = self.tasks.get(&msg.id) {
             let cancel_addr = task_addr.clone();
             actix::spawn(async move {
                 let _ = cancel_addr.send(CancelTask).await;
             });
 
             // Update metadata to show cancelled status
-            if let Some(metadata) = self.task_metadata.get_mut(&msg.id) {
-                metadata.mark_cancelled();
+            if let Some(mut metadata) = self.task_metadata.get_mut(&msg.id) {
+                metadata.mark_cancelled_at(self.clock.now());
             }
 
+            true
+        } else if self.cancel_lightweight(msg.id) {
+            true
+        } else if self
+            .task_metadata
+            .get(&msg.id)
+            .is_some_and(|metadata| metadata.status.is_active())
+        {
+            // Waiting out the backoff before a retry, or for a namespace slot
+            let now = self.clock.now();
+            if let Some(mut metadata) = self.task_metadata.get_mut(&msg.id) {
+                metadata.mark_cancelled_at(now);
+            }
+            self.record_outcome(&msg.id);
+            self.specs.remove(&msg.id);
+            self.release_waiting(msg.id);
             true
         } else {
             false
+        };
+
+        if cancelled {
+            self.admit_waiting(ctx);
         }
+        cancelled
     }
 }
 
 impl Handler<TaskFinished> for TaskManagerActor {
     type Result = ();
 
-    fn handle(&mut self, msg: TaskFinished, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: TaskFinished, ctx: &mut Self::Context) -> Self::Result {
+        let mut metadata = msg.metadata;
+        // A status poll of a task that is still running, or a report from
+        // an attempt that was already reclaimed
+        if metadata.status.is_active() {
+            return;
+        }
+        if let Some(stored) = self.task_metadata.get(&msg.id) {
+            if stored.attempt != metadata.attempt {
+                return;
+            }
+            metadata.max_attempts = stored.max_attempts;
+            metadata.attempt_errors = stored.attempt_errors.clone();
+        }
+        metadata.lease_expires_at = None;
+        if let Some(watchdog) = self.watchdogs.remove(&msg.id) {
+            ctx.cancel_future(watchdog);
+        }
+
         // Update stored metadata with final results
-        self.task_metadata.insert(msg.id, msg.metadata);
+        self.task_metadata.insert(msg.id, metadata);
+        self.specs.remove(&msg.id);
 
         // Clean up the task actor reference
         self.cleanup_finished_task(msg.id);
+        self.admit_waiting(ctx);
+    }
+}
+
+impl Handler<TaskProgress> for TaskManagerActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: TaskProgress, _ctx: &mut Self::Context) -> Self::Result {
+        let span = self.span_of(&msg.id);
+        if let Some(mut metadata) = self.task_metadata.get_mut(&msg.id) {
+            if metadata.status == TaskStatus::InProgress {
+                metadata.progress = msg.progress;
+                debug!(parent: &span, progress = msg.progress, "task progress");
+            }
+        }
+    }
+}
+
+impl Handler<SetFaultProfile> for TaskManagerActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetFaultProfile, _ctx: &mut Self::Context) -> Self::Result {
+        match (msg.task_type, msg.profile) {
+            (None, profile) => self.chaos.global = profile,
+            (Some(task_type), Some(profile)) => {
+                self.chaos.per_type.insert(task_type, profile);
+            }
+            (Some(task_type), None) => {
+                self.chaos.per_type.remove(&task_type);
+            }
+        }
+    }
+}
+
+impl Handler<GetChaosConfig> for TaskManagerActor {
+    type Result = MessageResult<GetChaosConfig>;
+
+    fn handle(&mut self, _msg: GetChaosConfig, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.chaos.clone())
+    }
+}
+
+impl Handler<ArchiveExpiredTasks> for TaskManagerActor {
+    type Result = ResponseActFuture<Self, usize>;
+
+    fn handle(&mut self, _msg: ArchiveExpiredTasks, _ctx: &mut Self::Context) -> Self::Result {
+        self.archive_expired_tasks()
     }
 }
 
@@ -693,6 +1646,7 @@ pub struct WsMessage {
 pub struct WebSocketMonitorActor {
     messages: VecDeque<WsMessage>,
     max_messages: usize,
+    metrics: Metrics,
 }
 
 impl WebSocketMonitorActor {
@@ -700,9 +1654,16 @@ impl WebSocketMonitorActor {
         Self {
             messages: VecDeque::new(),
             max_messages: 1000, // Keep last 1000 messages
+            metrics: Metrics::default(),
         }
     }
 
+    // Every logged message is also counted here, by direction and type
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
     fn add_message(&mut self, msg: WsMessage) {
         if self.messages.len() >= self.max_messages {
             self.messages.pop_front();
@@ -721,11 +1682,11 @@ impl Actor for WebSocketMonitorActor {
     type Context = Context<Self>;
 
     fn started(&mut self, _ctx: &mut Self::Context) {
-        println!("📡 WebSocketMonitorActor started");
+        info!("websocket monitor started");
     }
 
     fn stopped(&mut self, _ctx: &mut Self::Context) {
-        println!("📡 WebSocketMonitorActor stopped");
+        info!("websocket monitor stopped");
     }
 }
 
@@ -754,6 +1715,8 @@ impl Handler<LogWebSocketMessage> for WebSocketMonitorActor {
     type Result = ();
 
     fn handle(&mut self, msg: LogWebSocketMessage, _ctx: &mut Self::Context) -> Self::Result {
+        self.metrics
+            .record_websocket_message(&msg.direction, &msg.message_type, msg.size_bytes);
         let ws_message = WsMessage {
             id: Uuid::new_v4(),
             session_id: msg.session_id,
@@ -783,7 +1746,7 @@ impl Handler<GetWebSocketMessages> for WebSocketMonitorActor {
         };
 
         // Sort by timestamp (newest first)
-        messages.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        messages.sort_by_key(|m| std::cmp::Reverse(m.timestamp));
 
         // Apply limit
         if let Some(limit) = msg.limit {
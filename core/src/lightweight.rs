@@ -0,0 +1,414 @@
+use crate::{
+    run_panicking_work, ErrorType, FaultPlan, TaskManagerActor, TaskMetadata, TaskProgress,
+    TaskStatus,
+};
+use actix::{AsyncContext, Context, Handler, Message};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+// Resolution of the timer wheel, timeouts fire up to this much late
+const WHEEL_TICK: Duration = Duration::from_millis(10);
+const WHEEL_SLOTS: usize = 512;
+
+// How the manager runs local tasks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionMode {
+    // One TaskActor per task with its own timers
+    #[default]
+    Actor,
+    // One plain future per task doing the work. Timeouts, heartbeats and
+    // lease checks sit on a timer wheel in the manager, so a task costs a
+    // few map entries instead of an actor and a handful of futures.
+    Lightweight,
+}
+
+// Ordered so a heartbeat due at the same time as a lease check renews the
+// lease first, as it would with a task actor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum TimerKind {
+    Heartbeat,
+    Timeout,
+    LeaseCheck,
+}
+
+#[derive(Debug, Clone)]
+struct Timer {
+    deadline: DateTime<Utc>,
+    id: Uuid,
+    attempt: u32,
+    kind: TimerKind,
+}
+
+// Hashed timing wheel. Timers are filed under the tick their deadline falls
+// in and a slot may hold timers for later turns of the wheel, only those
+// whose deadline has passed are taken out.
+#[derive(Debug)]
+struct TimerWheel {
+    slots: Vec<Vec<Timer>>,
+    // Last tick that is over, set by the first insert
+    cursor: Option<i64>,
+    len: usize,
+}
+
+impl Default for TimerWheel {
+    fn default() -> Self {
+        Self {
+            slots: vec![Vec::new(); WHEEL_SLOTS],
+            cursor: None,
+            len: 0,
+        }
+    }
+}
+
+fn tick_of(time: DateTime<Utc>) -> i64 {
+    time.timestamp_millis()
+        .div_euclid(WHEEL_TICK.as_millis() as i64)
+}
+
+fn slot_of(tick: i64) -> usize {
+    tick.rem_euclid(WHEEL_SLOTS as i64) as usize
+}
+
+impl TimerWheel {
+    fn insert(
+        &mut self,
+        now: DateTime<Utc>,
+        after: Duration,
+        id: Uuid,
+        attempt: u32,
+        kind: TimerKind,
+    ) {
+        let cursor = *self.cursor.get_or_insert_with(|| tick_of(now) - 1);
+        let deadline = chrono::Duration::from_std(after)
+            .ok()
+            .and_then(|after| now.checked_add_signed(after))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        // Never filed under a tick the wheel will not visit again
+        let tick = tick_of(deadline).max(cursor + 1);
+
+        self.slots[slot_of(tick)].push(Timer {
+            deadline,
+            id,
+            attempt,
+            kind,
+        });
+        self.len += 1;
+    }
+
+    // Takes out every timer due by `now`, earliest first
+    fn advance(&mut self, now: DateTime<Utc>) -> Vec<Timer> {
+        let now_tick = tick_of(now);
+        let Some(cursor) = self.cursor.filter(|cursor| *cursor < now_tick) else {
+            return Vec::new();
+        };
+
+        let mut due = Vec::new();
+        // A jump of a whole turn or more visits every slot once
+        let ticks = (now_tick - cursor).min(WHEEL_SLOTS as i64);
+        for tick in cursor + 1..=cursor + ticks {
+            let slot = &mut self.slots[slot_of(tick)];
+            let mut index = 0;
+            while index < slot.len() {
+                if slot[index].deadline <= now {
+                    due.push(slot.swap_remove(index));
+                } else {
+                    index += 1;
+                }
+            }
+        }
+        // The current tick is only partly over, it is visited again
+        self.cursor = Some(now_tick - 1);
+        self.len -= due.len();
+
+        due.sort_by_key(|timer| (timer.deadline, timer.kind));
+        due
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+#[derive(Debug)]
+struct RunningTask {
+    attempt: u32,
+    // None while the task hangs or its executor is frozen
+    work: Option<JoinHandle<()>>,
+}
+
+// Local tasks run in ExecutionMode::Lightweight
+#[derive(Debug, Default)]
+pub(crate) struct LightweightTasks {
+    running: HashMap<Uuid, RunningTask>,
+    wheel: TimerWheel,
+    // Whether a WheelTick is on its way
+    ticking: bool,
+}
+
+impl LightweightTasks {
+    fn is_running(&self, id: &Uuid, attempt: u32) -> bool {
+        self.running
+            .get(id)
+            .is_some_and(|task| task.attempt == attempt)
+    }
+}
+
+// Sent by the work future of a lightweight task once its work is done
+#[derive(Message)]
+#[rtype(result = "()")]
+struct WorkFinished {
+    id: Uuid,
+    attempt: u32,
+    error: Option<String>,
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct WheelTick;
+
+impl TaskManagerActor {
+    // Runs an attempt whose InProgress metadata is already stored. Follows
+    // TaskActor step for step, see its StartTask handler.
+    pub(crate) fn start_lightweight(
+        &mut self,
+        task_id: Uuid,
+        attempt: u32,
+        work_duration: Duration,
+        failure: Option<ErrorType>,
+        plan: FaultPlan,
+        ctx: &mut Context<Self>,
+    ) {
+        self.lightweight.running.insert(
+            task_id,
+            RunningTask {
+                attempt,
+                work: None,
+            },
+        );
+        self.grant_lease(task_id, attempt, ctx);
+        tracing::info!(
+            parent: &self.span_of(&task_id),
+            work_ms = work_duration.as_millis() as u64,
+            attempt,
+            "task started"
+        );
+
+        if let Some(ErrorType::Immediate) = failure {
+            ctx.address().do_send(WorkFinished {
+                id: task_id,
+                attempt,
+                error: Some(ErrorType::Immediate.failure_message()),
+            });
+            return;
+        }
+        if plan.heartbeat_loss {
+            return;
+        }
+
+        let timeout = self
+            .task_metadata
+            .get(&task_id)
+            .map_or(Duration::ZERO, |metadata| {
+                Duration::from_millis(metadata.timeout_ms)
+            });
+        self.add_timer(task_id, attempt, timeout, TimerKind::Timeout, ctx);
+        self.add_timer(
+            task_id,
+            attempt,
+            self.lease_duration / 3,
+            TimerKind::Heartbeat,
+            ctx,
+        );
+
+        // A timeout failure is a hang, the timeout timer does the rest
+        if plan.hang || failure == Some(ErrorType::Timeout) {
+            return;
+        }
+
+        let clock = self.clock.clone();
+        let manager = ctx.address();
+        let offsets = plan.step_offsets(work_duration);
+        let failure = failure.map(|error_type| error_type.failure_message());
+        let work = actix::spawn(async move {
+            let work_start = clock.now();
+            let steps = offsets.len();
+            for (index, offset) in offsets.into_iter().enumerate() {
+                let progress = (index + 1) as f32 / steps as f32;
+                if plan.stall_at.is_some_and(|stall_at| progress > stall_at) {
+                    return;
+                }
+
+                let elapsed = (clock.now() - work_start).to_std().unwrap_or_default();
+                clock.sleep(offset.saturating_sub(elapsed)).await;
+                manager.do_send(TaskProgress {
+                    id: task_id,
+                    progress,
+                });
+            }
+
+            let error = if plan.panic {
+                run_panicking_work().await
+            } else {
+                failure
+            };
+            manager.do_send(WorkFinished {
+                id: task_id,
+                attempt,
+                error,
+            });
+        });
+        if let Some(task) = self.lightweight.running.get_mut(&task_id) {
+            task.work = Some(work);
+        }
+    }
+
+    // Files the next lease check of a lightweight task on the wheel, false
+    // for tasks run any other way
+    pub(crate) fn schedule_lightweight_lease_check(
+        &mut self,
+        task_id: Uuid,
+        attempt: u32,
+        after: Duration,
+        ctx: &mut Context<Self>,
+    ) -> bool {
+        if !self.lightweight.is_running(&task_id, attempt) {
+            return false;
+        }
+        self.add_timer(task_id, attempt, after, TimerKind::LeaseCheck, ctx);
+        true
+    }
+
+    // Drops a lightweight task without a final report, its work is stopped
+    // where it is. False when no lightweight task runs under `task_id`.
+    pub(crate) fn abandon_lightweight(&mut self, task_id: Uuid) -> bool {
+        let Some(task) = self.lightweight.running.remove(&task_id) else {
+            return false;
+        };
+        if let Some(work) = task.work {
+            work.abort();
+        }
+        true
+    }
+
+    pub(crate) fn cancel_lightweight(&mut self, task_id: Uuid) -> bool {
+        if !self.abandon_lightweight(task_id) {
+            return false;
+        }
+        self.finish_lightweight(task_id, |metadata, now| metadata.mark_cancelled_at(now));
+        true
+    }
+
+    fn finish_lightweight(
+        &mut self,
+        task_id: Uuid,
+        finish: impl FnOnce(&mut TaskMetadata, DateTime<Utc>),
+    ) {
+        let now = self.clock.now();
+        if let Some(mut metadata) = self.task_metadata.get_mut(&task_id) {
+            finish(&mut metadata, now);
+            metadata.lease_expires_at = None;
+        }
+        self.record_outcome(&task_id);
+        self.specs.remove(&task_id);
+    }
+
+    fn add_timer(
+        &mut self,
+        task_id: Uuid,
+        attempt: u32,
+        after: Duration,
+        kind: TimerKind,
+        ctx: &mut Context<Self>,
+    ) {
+        self.lightweight
+            .wheel
+            .insert(self.clock.now(), after, task_id, attempt, kind);
+        self.schedule_wheel_tick(ctx);
+    }
+
+    // The wheel only ticks while it holds timers
+    fn schedule_wheel_tick(&mut self, ctx: &mut Context<Self>) {
+        if self.lightweight.ticking || self.lightweight.wheel.is_empty() {
+            return;
+        }
+        self.lightweight.ticking = true;
+
+        let tick_sleep = self.clock.sleep(WHEEL_TICK);
+        let addr = ctx.address();
+        actix::spawn(async move {
+            tick_sleep.await;
+            addr.do_send(WheelTick);
+        });
+    }
+}
+
+impl Handler<WorkFinished> for TaskManagerActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: WorkFinished, ctx: &mut Self::Context) -> Self::Result {
+        // Timed out, cancelled or reclaimed in the meantime
+        if !self.lightweight.is_running(&msg.id, msg.attempt) {
+            return;
+        }
+        self.lightweight.running.remove(&msg.id);
+
+        self.finish_lightweight(msg.id, |metadata, now| match msg.error {
+            Some(error) => metadata.mark_error_at(error, false, now),
+            None => metadata.mark_completed_at("Task completed successfully".to_string(), now),
+        });
+        self.admit_waiting(ctx);
+    }
+}
+
+impl Handler<WheelTick> for TaskManagerActor {
+    type Result = ();
+
+    fn handle(&mut self, _msg: WheelTick, ctx: &mut Self::Context) -> Self::Result {
+        self.lightweight.ticking = false;
+        let now = self.clock.now();
+
+        let mut finished = false;
+        for timer in self.lightweight.wheel.advance(now) {
+            match timer.kind {
+                TimerKind::Heartbeat => {
+                    if self.lightweight.is_running(&timer.id, timer.attempt) {
+                        self.renew_lease(timer.id, timer.attempt);
+                        self.add_timer(
+                            timer.id,
+                            timer.attempt,
+                            self.lease_duration / 3,
+                            TimerKind::Heartbeat,
+                            ctx,
+                        );
+                    }
+                }
+                TimerKind::Timeout => {
+                    let in_progress = self
+                        .task_metadata
+                        .get(&timer.id)
+                        .is_some_and(|metadata| metadata.status == TaskStatus::InProgress);
+                    if !in_progress || !self.lightweight.is_running(&timer.id, timer.attempt) {
+                        continue;
+                    }
+                    self.abandon_lightweight(timer.id);
+                    self.finish_lightweight(timer.id, |metadata, now| {
+                        let error = format!("Task timed out after {}ms", metadata.timeout_ms);
+                        metadata.mark_error_at(error, true, now);
+                    });
+                    finished = true;
+                }
+                TimerKind::LeaseCheck => self.check_lease(timer.id, timer.attempt, ctx),
+            }
+        }
+
+        if finished {
+            self.admit_waiting(ctx);
+        }
+        self.schedule_wheel_tick(ctx);
+    }
+}
@@ -0,0 +1,474 @@
+use crate::{ApiError, CreateTask, SharedClock, SystemClock};
+use actix::{Actor, AsyncContext, Context, Handler, Message, MessageResult, Recipient};
+use chrono::{DateTime, Utc};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand_distr::Exp;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+const MAX_RATE_PER_SEC: f64 = 1000.0;
+// Window for the recent throughput figure
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(10);
+const LOAD_TAG: &str = "load-generator";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ArrivalPattern {
+    // Evenly spaced arrivals
+    Constant {
+        rate_per_sec: f64,
+    },
+    // Exponentially distributed gaps with the given mean rate
+    Poisson {
+        rate_per_sec: f64,
+    },
+    // Rate climbs linearly from start to peak over `ramp_secs`, then holds.
+    // Every `burst_every_secs` an extra `burst_size` tasks arrive at once.
+    BurstyRamp {
+        start_rate_per_sec: f64,
+        peak_rate_per_sec: f64,
+        ramp_secs: u64,
+        #[serde(default)]
+        burst_every_secs: u64,
+        #[serde(default)]
+        burst_size: u32,
+    },
+}
+
+impl ArrivalPattern {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let rates = match *self {
+            ArrivalPattern::Constant { rate_per_sec }
+            | ArrivalPattern::Poisson { rate_per_sec } => {
+                vec![("rate_per_sec", rate_per_sec)]
+            }
+            ArrivalPattern::BurstyRamp {
+                start_rate_per_sec,
+                peak_rate_per_sec,
+                ramp_secs,
+                ..
+            } => {
+                if ramp_secs == 0 {
+                    return Err(ApiError::validation_error(
+                        "ramp_secs must be at least 1".to_string(),
+                        Some(serde_json::json!({ "field": "pattern.ramp_secs" })),
+                    ));
+                }
+                vec![
+                    ("start_rate_per_sec", start_rate_per_sec),
+                    ("peak_rate_per_sec", peak_rate_per_sec),
+                ]
+            }
+        };
+
+        for (field, rate) in rates {
+            if !(rate > 0.0 && rate <= MAX_RATE_PER_SEC) {
+                return Err(ApiError::validation_error(
+                    format!(
+                        "Rate must be greater than 0 and at most {} tasks per second",
+                        MAX_RATE_PER_SEC
+                    ),
+                    Some(serde_json::json!({
+                        "field": format!("pattern.{}", field),
+                        "provided_value": rate
+                    })),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn rate_at(&self, elapsed: Duration) -> f64 {
+        match *self {
+            ArrivalPattern::Constant { rate_per_sec }
+            | ArrivalPattern::Poisson { rate_per_sec } => rate_per_sec,
+            ArrivalPattern::BurstyRamp {
+                start_rate_per_sec,
+                peak_rate_per_sec,
+                ramp_secs,
+                ..
+            } => {
+                let ramp = (elapsed.as_secs_f64() / ramp_secs.max(1) as f64).min(1.0);
+                start_rate_per_sec + (peak_rate_per_sec - start_rate_per_sec) * ramp
+            }
+        }
+    }
+
+    fn next_gap(&self, elapsed: Duration, rng: &mut StdRng) -> Duration {
+        let rate = self
+            .rate_at(elapsed)
+            .clamp(f64::MIN_POSITIVE, MAX_RATE_PER_SEC);
+        match self {
+            ArrivalPattern::Poisson { .. } => Exp::new(rate)
+                .map(|exp| Duration::from_secs_f64(exp.sample(rng)))
+                .unwrap_or(Duration::from_secs(1)),
+            _ => Duration::from_secs_f64(1.0 / rate),
+        }
+    }
+
+    fn burst(&self) -> Option<(Duration, u32)> {
+        match *self {
+            ArrivalPattern::BurstyRamp {
+                burst_every_secs,
+                burst_size,
+                ..
+            } if burst_every_secs > 0 && burst_size > 0 => {
+                Some((Duration::from_secs(burst_every_secs), burst_size))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadMixEntry {
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    pub task: CreateTask,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadGeneratorConfig {
+    pub pattern: ArrivalPattern,
+    pub mix: Vec<LoadMixEntry>,
+    // The run stops by itself after this long or this many tasks
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+    #[serde(default)]
+    pub max_tasks: Option<u64>,
+    // Makes arrivals and the task mix reproducible
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl LoadGeneratorConfig {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        self.pattern.validate()?;
+
+        if self.mix.iter().all(|entry| entry.weight == 0) {
+            return Err(ApiError::validation_error(
+                "Task mix needs at least one entry with a weight above 0".to_string(),
+                Some(serde_json::json!({ "field": "mix" })),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LoadStats {
+    pub running: bool,
+    pub pattern: Option<ArrivalPattern>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub stopped_at: Option<DateTime<Utc>>,
+    pub tasks_created: u64,
+    pub elapsed_secs: f64,
+    pub target_rate_per_sec: f64,
+    // Over the whole run and over the last THROUGHPUT_WINDOW
+    pub achieved_rate_per_sec: f64,
+    pub recent_rate_per_sec: f64,
+    pub created_by_type: HashMap<String, u64>,
+}
+
+struct LoadRun {
+    id: u64,
+    config: LoadGeneratorConfig,
+    mix: WeightedIndex<u32>,
+    rng: StdRng,
+    started_at: DateTime<Utc>,
+    // Arrivals are scheduled on absolute times so a late tick catches up
+    next_arrival: DateTime<Utc>,
+    next_burst: Option<DateTime<Utc>>,
+    created: u64,
+    created_by_type: HashMap<String, u64>,
+    recent: VecDeque<DateTime<Utc>>,
+}
+
+// Creates tasks on `target` following an arrival pattern, so the actor
+// system can be demoed and stressed without an external script
+pub struct LoadGeneratorActor {
+    target: Recipient<CreateTask>,
+    clock: SharedClock,
+    run: Option<LoadRun>,
+    last_stats: LoadStats,
+    next_run_id: u64,
+}
+
+impl LoadGeneratorActor {
+    pub fn new(target: Recipient<CreateTask>) -> Self {
+        Self {
+            target,
+            clock: Arc::new(SystemClock),
+            run: None,
+            last_stats: LoadStats::default(),
+            next_run_id: 0,
+        }
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn stats(&self) -> LoadStats {
+        let Some(run) = &self.run else {
+            return self.last_stats.clone();
+        };
+
+        let now = self.clock.now();
+        let elapsed = (now - run.started_at).to_std().unwrap_or_default();
+        let window_start = now - chrono::Duration::from_std(THROUGHPUT_WINDOW).unwrap_or_default();
+        let recent = run.recent.iter().filter(|at| **at > window_start).count();
+        let window_secs = elapsed.min(THROUGHPUT_WINDOW).as_secs_f64();
+
+        LoadStats {
+            running: true,
+            pattern: Some(run.config.pattern.clone()),
+            started_at: Some(run.started_at),
+            stopped_at: None,
+            tasks_created: run.created,
+            elapsed_secs: elapsed.as_secs_f64(),
+            target_rate_per_sec: run.config.pattern.rate_at(elapsed),
+            achieved_rate_per_sec: rate(run.created as usize, elapsed.as_secs_f64()),
+            recent_rate_per_sec: rate(recent, window_secs),
+            created_by_type: run.created_by_type.clone(),
+        }
+    }
+
+    fn stop(&mut self) -> LoadStats {
+        let mut stats = self.stats();
+        if let Some(run) = self.run.take() {
+            stats.running = false;
+            stats.stopped_at = Some(self.clock.now());
+            tracing::info!(
+                run = run.id,
+                created = run.created,
+                "load generator stopped"
+            );
+        }
+        self.last_stats = stats.clone();
+        stats
+    }
+
+    fn schedule_next(&self, ctx: &mut Context<Self>) {
+        let Some(run) = &self.run else {
+            return;
+        };
+
+        let delay = (run.next_arrival - self.clock.now())
+            .to_std()
+            .unwrap_or_default();
+        let sleep = self.clock.sleep(delay);
+        let addr = ctx.address();
+        let run_id = run.id;
+        actix::spawn(async move {
+            sleep.await;
+            addr.do_send(GenerateLoad { run_id });
+        });
+    }
+
+    fn create_task(&mut self) {
+        let Some(run) = &mut self.run else {
+            return;
+        };
+
+        let entry = &run.config.mix[run.mix.sample(&mut run.rng)];
+        let mut task = entry.task.clone();
+        task.tags.push(LOAD_TAG.to_string());
+
+        *run.created_by_type
+            .entry(task.type_name().to_string())
+            .or_default() += 1;
+        run.created += 1;
+        run.recent.push_back(run.next_arrival);
+        self.target.do_send(task);
+    }
+
+    fn finished(&self) -> bool {
+        let Some(run) = &self.run else {
+            return true;
+        };
+
+        let over_time = run.config.duration_secs.is_some_and(|secs| {
+            run.next_arrival >= run.started_at + chrono::Duration::seconds(secs as i64)
+        });
+        let over_count = run
+            .config
+            .max_tasks
+            .is_some_and(|max_tasks| run.created >= max_tasks);
+        over_time || over_count
+    }
+}
+
+fn rate(count: usize, secs: f64) -> f64 {
+    if secs > 0.0 {
+        count as f64 / secs
+    } else {
+        0.0
+    }
+}
+
+impl Actor for LoadGeneratorActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _ctx: &mut Self::Context) {
+        tracing::info!("load generator started");
+    }
+}
+
+// Starts a run, replacing any run already in progress
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Result<LoadStats, ApiError>")]
+pub struct StartLoad {
+    pub config: LoadGeneratorConfig,
+}
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "LoadStats")]
+pub struct StopLoad;
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "LoadStats")]
+pub struct GetLoadStats;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct GenerateLoad {
+    run_id: u64,
+}
+
+impl Handler<StartLoad> for LoadGeneratorActor {
+    type Result = Result<LoadStats, ApiError>;
+
+    fn handle(&mut self, msg: StartLoad, ctx: &mut Self::Context) -> Self::Result {
+        msg.config.validate()?;
+        let weights: Vec<u32> = msg.config.mix.iter().map(|entry| entry.weight).collect();
+        let mix = WeightedIndex::new(weights).map_err(|e| {
+            ApiError::validation_error(
+                format!("Invalid task mix: {}", e),
+                Some(serde_json::json!({ "field": "mix" })),
+            )
+        })?;
+
+        if self.run.is_some() {
+            self.stop();
+        }
+
+        let mut rng = match msg.config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let started_at = self.clock.now();
+        let first_gap = msg.config.pattern.next_gap(Duration::ZERO, &mut rng);
+        let next_burst =
+            msg.config.pattern.burst().map(|(every, _)| {
+                started_at + chrono::Duration::from_std(every).unwrap_or_default()
+            });
+
+        self.next_run_id += 1;
+        tracing::info!(
+            run = self.next_run_id,
+            pattern = ?msg.config.pattern,
+            "load generator run starting"
+        );
+        self.run = Some(LoadRun {
+            id: self.next_run_id,
+            config: msg.config,
+            mix,
+            rng,
+            started_at,
+            next_arrival: started_at + chrono::Duration::from_std(first_gap).unwrap_or_default(),
+            next_burst,
+            created: 0,
+            created_by_type: HashMap::new(),
+            recent: VecDeque::new(),
+        });
+
+        self.schedule_next(ctx);
+        Ok(self.stats())
+    }
+}
+
+impl Handler<StopLoad> for LoadGeneratorActor {
+    type Result = MessageResult<StopLoad>;
+
+    fn handle(&mut self, _msg: StopLoad, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.stop())
+    }
+}
+
+impl Handler<GetLoadStats> for LoadGeneratorActor {
+    type Result = MessageResult<GetLoadStats>;
+
+    fn handle(&mut self, _msg: GetLoadStats, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.stats())
+    }
+}
+
+impl Handler<GenerateLoad> for LoadGeneratorActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: GenerateLoad, ctx: &mut Self::Context) -> Self::Result {
+        // Ticks of a stopped or replaced run are ignored
+        if self.run.as_ref().map(|run| run.id) != Some(msg.run_id) {
+            return;
+        }
+
+        self.create_task();
+
+        // Bursts that fell due since the last arrival
+        let burst = self.run.as_ref().and_then(|run| run.config.pattern.burst());
+        if let Some((every, size)) = burst {
+            loop {
+                let due = self.run.as_ref().and_then(|run| {
+                    run.next_burst
+                        .filter(|next_burst| *next_burst <= run.next_arrival)
+                });
+                if due.is_none() {
+                    break;
+                }
+                for _ in 0..size {
+                    if self.finished() {
+                        break;
+                    }
+                    self.create_task();
+                }
+                if let Some(run) = &mut self.run {
+                    run.next_burst = run
+                        .next_burst
+                        .map(|at| at + chrono::Duration::from_std(every).unwrap_or_default());
+                }
+            }
+        }
+
+        if let Some(run) = &mut self.run {
+            let window_start = run.next_arrival
+                - chrono::Duration::from_std(THROUGHPUT_WINDOW).unwrap_or_default();
+            while run.recent.front().is_some_and(|at| *at <= window_start) {
+                run.recent.pop_front();
+            }
+
+            let elapsed = (run.next_arrival - run.started_at)
+                .to_std()
+                .unwrap_or_default();
+            let gap = run.config.pattern.next_gap(elapsed, &mut run.rng);
+            run.next_arrival += chrono::Duration::from_std(gap).unwrap_or_default();
+        }
+
+        if self.finished() {
+            self.stop();
+        } else {
+            self.schedule_next(ctx);
+        }
+    }
+}
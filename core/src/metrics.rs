@@ -0,0 +1,322 @@
+use crate::{TaskManagerActor, TaskMetadata, TaskStatus, WsMessageDirection};
+use actix::{Handler, Message};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Every metric name starts with this
+pub const METRICS_PREFIX: &str = "task_overlord";
+
+// Upper bounds in seconds of the task duration histogram's buckets
+pub const DURATION_BUCKETS: [f64; 12] = [
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0,
+];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    // Per bucket of DURATION_BUCKETS, not cumulative
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = DURATION_BUCKETS.iter().position(|le| seconds <= *le) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    // (event, task type) -> tasks
+    task_events: BTreeMap<(&'static str, String), u64>,
+    durations: BTreeMap<String, Histogram>,
+    // (status, task type) -> tasks in that status now
+    active: BTreeMap<(&'static str, String), i64>,
+    // (direction, message type) -> (messages, bytes)
+    websocket: BTreeMap<(&'static str, String), (u64, u64)>,
+    liveview_sessions: i64,
+}
+
+// Counters and gauges served on /metrics in the Prometheus text format.
+// Clones share the same registry, the web server hands one to every
+// manager shard, the WebSocket monitor and the LiveView sessions.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn registry(&self) -> std::sync::MutexGuard<'_, Registry> {
+        // Counters stay usable after a panic elsewhere
+        self.registry
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Counts what happened to a task between two snapshots, `old` is None
+    // for a task the previous snapshot did not have yet
+    pub(crate) fn record_task(&self, old: Option<&TaskMetadata>, new: &TaskMetadata) {
+        let mut registry = self.registry();
+        if old.is_none() {
+            *registry
+                .task_events
+                .entry(("created", new.task_type.clone()))
+                .or_default() += 1;
+        }
+        if let Some(old) = old {
+            if let Some(status) = active_status(&old.status) {
+                *registry
+                    .active
+                    .entry((status, old.task_type.clone()))
+                    .or_default() -= 1;
+            }
+        }
+        if let Some(status) = active_status(&new.status) {
+            *registry
+                .active
+                .entry((status, new.task_type.clone()))
+                .or_default() += 1;
+        }
+
+        let was_active = old.is_none_or(|old| old.status.is_active());
+        if !was_active || new.status.is_active() {
+            return;
+        }
+        let event = match new.status {
+            TaskStatus::Completed => "completed",
+            _ if new.was_cancelled() => "cancelled",
+            _ => "failed",
+        };
+        *registry
+            .task_events
+            .entry((event, new.task_type.clone()))
+            .or_default() += 1;
+        if let Some(ms) = new.actual_duration_ms {
+            registry
+                .durations
+                .entry(new.task_type.clone())
+                .or_default()
+                .observe(ms as f64 / 1000.0);
+        }
+    }
+
+    pub fn record_websocket_message(
+        &self,
+        direction: &WsMessageDirection,
+        message_type: &str,
+        size_bytes: usize,
+    ) {
+        let direction = match direction {
+            WsMessageDirection::Incoming => "incoming",
+            WsMessageDirection::Outgoing => "outgoing",
+        };
+        let mut registry = self.registry();
+        let (messages, bytes) = registry
+            .websocket
+            .entry((direction, message_type.to_string()))
+            .or_default();
+        *messages += 1;
+        *bytes += size_bytes as u64;
+    }
+
+    pub fn liveview_session_started(&self) {
+        self.registry().liveview_sessions += 1;
+    }
+
+    pub fn liveview_session_stopped(&self) {
+        self.registry().liveview_sessions -= 1;
+    }
+
+    // The text exposition format, `mailbox_latency` is what
+    // MeasureMailboxLatency returned
+    pub fn render(&self, mailbox_latency: &[(usize, Duration)]) -> String {
+        let registry = self.registry();
+        let mut out = String::new();
+
+        for (event, help) in [
+            ("created", "Tasks created"),
+            ("completed", "Tasks that completed"),
+            ("failed", "Tasks that failed or timed out"),
+            ("cancelled", "Tasks cancelled"),
+        ] {
+            let name = format!("{}_tasks_{}_total", METRICS_PREFIX, event);
+            header(
+                &mut out,
+                &name,
+                &format!("{} by task type.", help),
+                "counter",
+            );
+            for ((_, task_type), count) in registry
+                .task_events
+                .iter()
+                .filter(|((counted, _), _)| *counted == event)
+            {
+                sample(&mut out, &name, &[("task_type", task_type)], *count);
+            }
+        }
+
+        for (status, help) in [
+            ("in_progress", "Tasks running now"),
+            ("queued", "Tasks waiting for a slot or a worker"),
+        ] {
+            let name = format!("{}_tasks_{}", METRICS_PREFIX, status);
+            header(&mut out, &name, &format!("{} by task type.", help), "gauge");
+            for ((_, task_type), count) in registry
+                .active
+                .iter()
+                .filter(|((counted, _), _)| *counted == status)
+            {
+                sample(&mut out, &name, &[("task_type", task_type)], count);
+            }
+        }
+
+        let name = format!("{}_task_duration_seconds", METRICS_PREFIX);
+        header(
+            &mut out,
+            &name,
+            "Actual duration of finished tasks by task type.",
+            "histogram",
+        );
+        for (task_type, histogram) in &registry.durations {
+            let mut cumulative = 0;
+            for (le, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                let le = le.to_string();
+                sample(
+                    &mut out,
+                    &format!("{}_bucket", name),
+                    &[("task_type", task_type), ("le", &le)],
+                    cumulative,
+                );
+            }
+            sample(
+                &mut out,
+                &format!("{}_bucket", name),
+                &[("task_type", task_type), ("le", "+Inf")],
+                histogram.count,
+            );
+            sample(
+                &mut out,
+                &format!("{}_sum", name),
+                &[("task_type", task_type)],
+                histogram.sum,
+            );
+            sample(
+                &mut out,
+                &format!("{}_count", name),
+                &[("task_type", task_type)],
+                histogram.count,
+            );
+        }
+
+        let name = format!("{}_liveview_sessions", METRICS_PREFIX);
+        header(&mut out, &name, "Connected LiveView sessions.", "gauge");
+        sample(&mut out, &name, &[], registry.liveview_sessions);
+
+        let messages = format!("{}_websocket_messages_total", METRICS_PREFIX);
+        header(
+            &mut out,
+            &messages,
+            "LiveView WebSocket messages by direction and type.",
+            "counter",
+        );
+        for ((direction, message_type), (count, _)) in &registry.websocket {
+            sample(
+                &mut out,
+                &messages,
+                &[("direction", direction), ("message_type", message_type)],
+                count,
+            );
+        }
+        let bytes = format!("{}_websocket_bytes_total", METRICS_PREFIX);
+        header(
+            &mut out,
+            &bytes,
+            "LiveView WebSocket message bytes by direction and type.",
+            "counter",
+        );
+        for ((direction, message_type), (_, size)) in &registry.websocket {
+            sample(
+                &mut out,
+                &bytes,
+                &[("direction", direction), ("message_type", message_type)],
+                size,
+            );
+        }
+
+        let name = format!("{}_manager_mailbox_latency_seconds", METRICS_PREFIX);
+        header(
+            &mut out,
+            &name,
+            "Round trip of a message through each task manager shard's mailbox.",
+            "gauge",
+        );
+        for (shard, latency) in mailbox_latency {
+            let shard = shard.to_string();
+            sample(&mut out, &name, &[("shard", &shard)], latency.as_secs_f64());
+        }
+
+        out
+    }
+}
+
+// Statuses with a gauge, the others only have counters
+fn active_status(status: &TaskStatus) -> Option<&'static str> {
+    match status {
+        TaskStatus::InProgress => Some("in_progress"),
+        TaskStatus::Queued => Some("queued"),
+        TaskStatus::Completed | TaskStatus::Error => None,
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(out, " {}", value);
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// How long each task manager shard takes to get to a message, by shard
+// index. Shards that did not answer are left out. Handled by
+// TaskManagerRouter.
+#[derive(Message, Debug, Clone, Default)]
+#[rtype(result = "Vec<(usize, Duration)>")]
+pub struct MeasureMailboxLatency;
+
+// Does nothing, so the round trip is all mailbox
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "()")]
+pub(crate) struct Ping;
+
+impl Handler<Ping> for TaskManagerActor {
+    type Result = ();
+
+    fn handle(&mut self, _msg: Ping, _ctx: &mut Self::Context) -> Self::Result {}
+}
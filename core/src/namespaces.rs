@@ -0,0 +1,406 @@
+use crate::quotas::{Quota, QuotaLedger, QuotaUsage};
+use crate::{ApiError, CreateTask, TaskManagerActor, TaskMetadata, TaskStatus};
+use actix::{Context, Handler, Message, MessageResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use uuid::Uuid;
+
+// Namespace of tasks created without one
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+const MAX_NAMESPACE_LENGTH: usize = 63;
+const MAX_CONCURRENT_LIMIT: u32 = 10_000;
+const MAX_WEIGHT: u32 = 1000;
+
+// Shared namespaces show up in the unscoped task list and the default
+// LiveView, private ones only when asked for by name
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    #[default]
+    Shared,
+    Private,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NamespaceConfig {
+    // Tasks of the namespace allowed to run at once, the rest wait Queued
+    #[serde(default)]
+    pub max_concurrent: Option<u32>,
+    // Finished tasks are archived, or dropped without an archive, after
+    // this long instead of the manager's retention
+    #[serde(default)]
+    pub retention_secs: Option<u64>,
+    #[serde(default)]
+    pub visibility: Visibility,
+    #[serde(default)]
+    pub quota: Quota,
+    // Share of the remote worker pool relative to other namespaces with
+    // queued tasks, see FairScheduler
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl Default for NamespaceConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: None,
+            retention_secs: None,
+            visibility: Visibility::default(),
+            quota: Quota::default(),
+            weight: default_weight(),
+        }
+    }
+}
+
+impl NamespaceConfig {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if let Some(max_concurrent) = self.max_concurrent {
+            if !(1..=MAX_CONCURRENT_LIMIT).contains(&max_concurrent) {
+                return Err(ApiError::validation_error(
+                    format!(
+                        "max_concurrent must be between 1 and {}",
+                        MAX_CONCURRENT_LIMIT
+                    ),
+                    Some(serde_json::json!({
+                        "field": "max_concurrent",
+                        "provided_value": max_concurrent
+                    })),
+                ));
+            }
+        }
+        if !(1..=MAX_WEIGHT).contains(&self.weight) {
+            return Err(ApiError::validation_error(
+                format!("weight must be between 1 and {}", MAX_WEIGHT),
+                Some(serde_json::json!({
+                    "field": "weight",
+                    "provided_value": self.weight
+                })),
+            ));
+        }
+        self.quota.validate()
+    }
+
+    fn retention(&self) -> Option<Duration> {
+        self.retention_secs.map(Duration::from_secs)
+    }
+}
+
+// Lowercase letters, digits, '-' and '_', so names are safe in URLs
+pub fn validate_namespace(name: &str) -> Result<(), ApiError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAMESPACE_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ApiError::validation_error(
+            format!(
+                "Namespace must be 1-{} lowercase letters, digits, '-' or '_'",
+                MAX_NAMESPACE_LENGTH
+            ),
+            Some(serde_json::json!({
+                "field": "namespace",
+                "provided_value": name
+            })),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NamespaceInfo {
+    pub name: String,
+    #[serde(flatten)]
+    pub config: NamespaceConfig,
+    pub created_at: DateTime<Utc>,
+    // Admitted tasks that have not finished, including remote ones waiting
+    // for a worker and retries waiting out their backoff
+    pub running: usize,
+    // Tasks held back by max_concurrent
+    pub waiting: usize,
+    pub total: usize,
+    pub usage: QuotaUsage,
+}
+
+#[derive(Debug)]
+pub(crate) struct Namespace {
+    pub(crate) config: NamespaceConfig,
+    created_at: DateTime<Utc>,
+    // Tasks waiting for a concurrency slot, oldest first
+    waiting: VecDeque<Uuid>,
+    pub(crate) ledger: QuotaLedger,
+}
+
+pub(crate) type Namespaces = HashMap<String, Namespace>;
+
+// The default namespace always exists, others appear on first use
+pub(crate) fn initial_namespaces(now: DateTime<Utc>) -> Namespaces {
+    HashMap::from([(DEFAULT_NAMESPACE.to_string(), Namespace::new(now))])
+}
+
+impl Namespace {
+    fn new(created_at: DateTime<Utc>) -> Self {
+        Self {
+            config: NamespaceConfig::default(),
+            created_at,
+            waiting: VecDeque::new(),
+            ledger: QuotaLedger::default(),
+        }
+    }
+}
+
+impl CreateTask {
+    pub fn namespace(&self) -> &str {
+        self.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE)
+    }
+}
+
+// Creates the namespace or replaces its configuration. Raising the
+// concurrency limit admits waiting tasks right away.
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Result<NamespaceInfo, ApiError>")]
+pub struct ConfigureNamespace {
+    pub name: String,
+    pub config: NamespaceConfig,
+}
+
+// Shared namespaces only, private ones are looked up by name
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Vec<NamespaceInfo>")]
+pub struct ListNamespaces;
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Option<NamespaceInfo>")]
+pub struct GetNamespace {
+    pub name: String,
+}
+
+// Tasks of one namespace, or of every shared namespace when None
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Vec<TaskMetadata>")]
+pub struct GetNamespaceTasks {
+    pub namespace: Option<String>,
+}
+
+// A task if it is visible in the namespace, or in the shared view when None
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Option<TaskMetadata>")]
+pub struct GetNamespaceTask {
+    pub id: Uuid,
+    pub namespace: Option<String>,
+}
+
+impl TaskManagerActor {
+    pub(crate) fn namespace_mut(&mut self, name: &str) -> &mut Namespace {
+        let now = self.clock.now();
+        self.namespaces
+            .entry(name.to_string())
+            .or_insert_with(|| Namespace::new(now))
+    }
+
+    pub(crate) fn namespace_retention(&self, name: &str) -> Option<Duration> {
+        self.namespaces
+            .get(name)
+            .and_then(|namespace| namespace.config.retention())
+    }
+
+    pub(crate) fn is_visible(&self, task: &TaskMetadata, scope: Option<&str>) -> bool {
+        match scope {
+            Some(name) => task.namespace == name,
+            None => self
+                .namespaces
+                .get(&task.namespace)
+                .is_none_or(|namespace| namespace.config.visibility == Visibility::Shared),
+        }
+    }
+
+    fn running_in(&self, name: &str) -> usize {
+        let waiting = self
+            .namespaces
+            .get(name)
+            .map_or(0, |namespace| namespace.waiting.len());
+        self.active_count(name).saturating_sub(waiting)
+    }
+
+    pub(crate) fn has_free_slot(&self, name: &str) -> bool {
+        match self
+            .namespaces
+            .get(name)
+            .and_then(|namespace| namespace.config.max_concurrent)
+        {
+            Some(limit) => self.running_in(name) < limit as usize,
+            None => true,
+        }
+    }
+
+    // Parks a task until its namespace has a free slot
+    pub(crate) fn hold_task(&mut self, task_id: Uuid, spec: CreateTask, seed: u64) {
+        let now = self.clock.now();
+        let name = spec.namespace().to_string();
+        let limit = self
+            .namespaces
+            .get(&name)
+            .and_then(|namespace| namespace.config.max_concurrent)
+            .unwrap_or_default();
+        let metadata = TaskMetadata {
+            id: task_id,
+            name: if spec.name.is_empty() {
+                spec.task_type.get_name()
+            } else {
+                spec.name.clone()
+            },
+            message: spec.message.clone(),
+            task_type: spec.type_name().to_string(),
+            status: TaskStatus::Queued,
+            started_at: now,
+            timeout_ms: spec.task_type.get_timeout().as_millis() as u64,
+            tags: spec.tags.clone(),
+            seed: Some(seed),
+            queued_at: Some(now),
+            queue_reason: Some(format!(
+                "Namespace '{}' is at its concurrency limit of {}",
+                name, limit
+            )),
+            attempt: 1,
+            max_attempts: spec.retry.as_ref().map_or(1, |retry| retry.max_attempts),
+            namespace: name.clone(),
+            ..Default::default()
+        };
+        tracing::info!(
+            parent: &self.span_of(&task_id),
+            limit,
+            "task waiting for a namespace slot"
+        );
+
+        self.task_metadata.insert(task_id, metadata);
+        self.specs.insert(task_id, spec);
+        self.namespace_mut(&name).waiting.push_back(task_id);
+    }
+
+    // Forgets a waiting task that was cancelled
+    pub(crate) fn release_waiting(&mut self, task_id: Uuid) {
+        for namespace in self.namespaces.values_mut() {
+            namespace.waiting.retain(|id| *id != task_id);
+        }
+    }
+
+    // Starts waiting tasks, oldest first, in every namespace with free slots
+    pub(crate) fn admit_waiting(&mut self, ctx: &mut Context<Self>) {
+        let names: Vec<String> = self
+            .namespaces
+            .iter()
+            .filter(|(_, namespace)| !namespace.waiting.is_empty())
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        for name in names {
+            while self.has_free_slot(&name) {
+                let Some(task_id) = self.namespace_mut(&name).waiting.pop_front() else {
+                    break;
+                };
+                let Some(spec) = self.specs.get(&task_id).cloned() else {
+                    continue;
+                };
+                let seed = self
+                    .task_metadata
+                    .get(&task_id)
+                    .and_then(|metadata| metadata.seed)
+                    .unwrap_or_default();
+                if spec.retry.is_none() {
+                    self.specs.remove(&task_id);
+                }
+                self.launch_task(task_id, &spec, seed, 1, ctx);
+            }
+        }
+    }
+
+    fn namespace_info(&self, name: &str) -> Option<NamespaceInfo> {
+        let namespace = self.namespaces.get(name)?;
+        Some(NamespaceInfo {
+            name: name.to_string(),
+            config: namespace.config.clone(),
+            created_at: namespace.created_at,
+            running: self.running_in(name),
+            waiting: namespace.waiting.len(),
+            total: self
+                .task_metadata
+                .namespace(name)
+                .map_or(0, |tasks| tasks.total),
+            usage: self.quota_usage(name),
+        })
+    }
+}
+
+impl Handler<ConfigureNamespace> for TaskManagerActor {
+    type Result = Result<NamespaceInfo, ApiError>;
+
+    fn handle(&mut self, msg: ConfigureNamespace, ctx: &mut Self::Context) -> Self::Result {
+        validate_namespace(&msg.name)?;
+        msg.config.validate()?;
+
+        tracing::info!(namespace = %msg.name, config = ?msg.config, "namespace configured");
+        self.namespace_mut(&msg.name).config = msg.config;
+        self.admit_waiting(ctx);
+
+        self.namespace_info(&msg.name)
+            .ok_or_else(|| ApiError::internal_error("Namespace was not stored".to_string()))
+    }
+}
+
+impl Handler<ListNamespaces> for TaskManagerActor {
+    type Result = MessageResult<ListNamespaces>;
+
+    fn handle(&mut self, _msg: ListNamespaces, _ctx: &mut Self::Context) -> Self::Result {
+        let mut names: Vec<&String> = self.namespaces.keys().collect();
+        names.sort();
+        MessageResult(
+            names
+                .into_iter()
+                .filter_map(|name| self.namespace_info(name))
+                .filter(|info| info.config.visibility == Visibility::Shared)
+                .collect(),
+        )
+    }
+}
+
+impl Handler<GetNamespace> for TaskManagerActor {
+    type Result = Option<NamespaceInfo>;
+
+    fn handle(&mut self, msg: GetNamespace, _ctx: &mut Self::Context) -> Self::Result {
+        self.namespace_info(&msg.name)
+    }
+}
+
+impl Handler<GetNamespaceTasks> for TaskManagerActor {
+    type Result = MessageResult<GetNamespaceTasks>;
+
+    fn handle(&mut self, msg: GetNamespaceTasks, _ctx: &mut Self::Context) -> Self::Result {
+        let scope = msg.namespace.as_deref();
+        MessageResult(
+            self.task_metadata
+                .values()
+                .filter(|task| self.is_visible(task, scope))
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+impl Handler<GetNamespaceTask> for TaskManagerActor {
+    type Result = Option<TaskMetadata>;
+
+    fn handle(&mut self, msg: GetNamespaceTask, _ctx: &mut Self::Context) -> Self::Result {
+        self.task_metadata
+            .get(&msg.id)
+            .filter(|task| self.is_visible(task, msg.namespace.as_deref()))
+            .cloned()
+    }
+}
@@ -0,0 +1,134 @@
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{Protocol, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use std::collections::HashMap;
+use tracing::Span;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+// Service spans are exported as when OTEL_SERVICE_NAME is unset
+pub const DEFAULT_SERVICE_NAME: &str = "task-overlord";
+
+// The HTTP header and LiveView message field carrying W3C trace context
+pub const TRACEPARENT: &str = "traceparent";
+
+// How spans are encoded for the collector's OTLP/HTTP receiver
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OtlpProtocol {
+    #[default]
+    Protobuf,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpConfig {
+    // Base URL of the collector, spans are posted to /v1/traces under it
+    pub endpoint: String,
+    pub protocol: OtlpProtocol,
+    pub service_name: String,
+}
+
+impl OtlpConfig {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            protocol: OtlpProtocol::default(),
+            service_name: DEFAULT_SERVICE_NAME.to_string(),
+        }
+    }
+
+    pub fn with_protocol(mut self, protocol: OtlpProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    pub fn with_service_name(mut self, service_name: impl Into<String>) -> Self {
+        self.service_name = service_name.into();
+        self
+    }
+
+    // From the standard OTEL_EXPORTER_OTLP_ENDPOINT, OTEL_EXPORTER_OTLP_PROTOCOL
+    // (http/protobuf or http/json) and OTEL_SERVICE_NAME variables, None
+    // when no endpoint is set
+    pub fn from_env() -> Option<Self> {
+        let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .ok()
+            .filter(|endpoint| !endpoint.is_empty())?;
+        let mut config = Self::new(endpoint);
+        if std::env::var("OTEL_EXPORTER_OTLP_PROTOCOL").as_deref() == Ok("http/json") {
+            config = config.with_protocol(OtlpProtocol::Json);
+        }
+        if let Ok(service_name) = std::env::var("OTEL_SERVICE_NAME") {
+            config = config.with_service_name(service_name);
+        }
+        Some(config)
+    }
+
+    fn traces_url(&self) -> String {
+        format!("{}/v1/traces", self.endpoint.trim_end_matches('/'))
+    }
+}
+
+// Sends finished spans to an OTLP collector in batches, from a thread of
+// its own. Clones share the batch, shut it down before exiting so the last
+// spans go out.
+#[derive(Debug, Clone)]
+pub struct OtlpExporter {
+    provider: SdkTracerProvider,
+}
+
+impl OtlpExporter {
+    pub fn start(config: &OtlpConfig) -> Result<Self, String> {
+        let protocol = match config.protocol {
+            OtlpProtocol::Protobuf => Protocol::HttpBinary,
+            OtlpProtocol::Json => Protocol::HttpJson,
+        };
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_protocol(protocol)
+            .with_endpoint(config.traces_url())
+            .build()
+            .map_err(|e| format!("Failed to build the OTLP exporter: {}", e))?;
+        let resource = Resource::builder()
+            .with_service_name(config.service_name.clone())
+            .build();
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(resource)
+            .build();
+        Ok(Self { provider })
+    }
+
+    // Exports the spans of the subscriber it is added to
+    pub fn layer<S>(&self) -> OpenTelemetryLayer<S, SdkTracer>
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(self.provider.tracer(DEFAULT_SERVICE_NAME))
+    }
+
+    // Sends the spans that ended so far without waiting for the batch
+    pub fn flush(&self) -> Result<(), String> {
+        self.provider
+            .force_flush()
+            .map_err(|e| format!("Failed to flush spans: {}", e))
+    }
+
+    pub fn shutdown(&self) -> Result<(), String> {
+        self.provider
+            .shutdown()
+            .map_err(|e| format!("Failed to shut down the OTLP exporter: {}", e))
+    }
+}
+
+// Makes `span` a child of the remote span a W3C traceparent names. A value
+// that does not parse leaves it a root span, as does a subscriber without
+// an OtlpExporter layer.
+pub fn continue_trace(span: &Span, traceparent: &str) {
+    let carrier = HashMap::from([(TRACEPARENT.to_string(), traceparent.to_string())]);
+    let context = TraceContextPropagator::new().extract(&carrier);
+    let _ = span.set_parent(context);
+}
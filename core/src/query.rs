@@ -0,0 +1,337 @@
+use crate::index::TaskFilter;
+use crate::snapshot::TaskReader;
+use crate::{ApiError, TaskMetadata, TaskStatus};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    // started_at
+    #[default]
+    CreatedAt,
+    Name,
+    Status,
+    Duration,
+}
+
+impl SortField {
+    pub const NAMES: [&'static str; 4] = ["created_at", "name", "status", "duration"];
+
+    fn name(&self) -> &'static str {
+        match self {
+            SortField::CreatedAt => "created_at",
+            SortField::Name => "name",
+            SortField::Status => "status",
+            SortField::Duration => "duration",
+        }
+    }
+
+    // Strings that order the way tasks sort by this field
+    fn value(&self, task: &TaskMetadata) -> String {
+        match self {
+            SortField::CreatedAt => task.started_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            SortField::Name => task.name.to_lowercase(),
+            SortField::Status => match task.status {
+                TaskStatus::Queued => "0",
+                TaskStatus::InProgress => "1",
+                TaskStatus::Completed => "2",
+                TaskStatus::Error => "3",
+            }
+            .to_string(),
+            // Zero padded, unfinished tasks after every finished one
+            SortField::Duration => task
+                .actual_duration_ms
+                .map_or_else(|| "~".to_string(), |ms| format!("{:020}", ms)),
+        }
+    }
+}
+
+impl FromStr for SortField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "created_at" => Ok(SortField::CreatedAt),
+            "name" => Ok(SortField::Name),
+            "status" => Ok(SortField::Status),
+            "duration" => Ok(SortField::Duration),
+            _ => Err(format!("Invalid sort field: '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(format!("Invalid sort order: '{}'", s)),
+        }
+    }
+}
+
+// Where the previous page ended. Handed out hex encoded so clients treat
+// it as opaque, and only valid for the sort it was made for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskCursor {
+    sort: SortField,
+    order: SortOrder,
+    value: String,
+    id: Uuid,
+}
+
+impl TaskCursor {
+    fn after(task: &TaskMetadata, sort: SortField, order: SortOrder) -> Self {
+        Self {
+            sort,
+            order,
+            value: sort.value(task),
+            id: task.id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let order = match self.order {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        };
+        format!("{}|{}|{}|{}", self.sort.name(), order, self.id, self.value)
+            .bytes()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, ApiError> {
+        let invalid = || {
+            ApiError::validation_error(
+                "Invalid cursor, pass next_cursor from the previous page".to_string(),
+                Some(serde_json::json!({
+                    "field": "cursor",
+                    "provided_value": cursor
+                })),
+            )
+        };
+
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|at| {
+                cursor
+                    .get(at..at + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+        let text = String::from_utf8(bytes).map_err(|_| invalid())?;
+        let mut parts = text.splitn(4, '|');
+        let (Some(sort), Some(order), Some(id), Some(value)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+
+        Ok(Self {
+            sort: sort.parse().map_err(|_| invalid())?,
+            order: order.parse().map_err(|_| invalid())?,
+            id: id.parse().map_err(|_| invalid())?,
+            value: value.to_string(),
+        })
+    }
+
+    fn position(&self) -> (&str, Uuid) {
+        (&self.value, self.id)
+    }
+}
+
+// A filtered, sorted and paginated task listing
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskQuery {
+    pub filter: TaskFilter,
+    // Case-insensitive substring of the task name, or a pattern over the
+    // whole name when it has `*` wildcards
+    pub name: Option<String>,
+    // Inclusive bounds on actual_duration_ms, unfinished tasks never match
+    pub min_duration_ms: Option<u64>,
+    pub max_duration_ms: Option<u64>,
+    pub sort: SortField,
+    pub order: SortOrder,
+    pub cursor: Option<TaskCursor>,
+    // Every match when unset
+    pub limit: Option<usize>,
+}
+
+impl TaskQuery {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if let Some(limit) = self.limit {
+            if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+                return Err(ApiError::validation_error(
+                    format!("limit must be between 1 and {}", MAX_PAGE_SIZE),
+                    Some(serde_json::json!({
+                        "field": "limit",
+                        "provided_value": limit,
+                        "max_value": MAX_PAGE_SIZE
+                    })),
+                ));
+            }
+        }
+        if let Some(cursor) = &self.cursor {
+            if cursor.sort != self.sort || cursor.order != self.order {
+                return Err(ApiError::validation_error(
+                    "The cursor belongs to a listing with a different sort".to_string(),
+                    Some(serde_json::json!({
+                        "field": "cursor",
+                        "expected_sort": cursor.sort,
+                        "expected_order": cursor.order
+                    })),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Every condition, including the ones the indexes cannot answer
+    pub fn matches(&self, task: &TaskMetadata) -> bool {
+        self.filter.matches(task) && self.matches_unindexed(task)
+    }
+
+    fn matches_unindexed(&self, task: &TaskMetadata) -> bool {
+        let within = |bound: Option<u64>, check: fn(u64, u64) -> bool| {
+            bound.is_none_or(|bound| {
+                task.actual_duration_ms
+                    .is_some_and(|duration| check(duration, bound))
+            })
+        };
+        self.name
+            .as_ref()
+            .is_none_or(|pattern| name_matches(pattern, &task.name))
+            && within(self.min_duration_ms, |duration, min| duration >= min)
+            && within(self.max_duration_ms, |duration, max| duration <= max)
+    }
+
+    // The index filter narrowed to the tasks past the cursor where the sort
+    // allows it, the cursor check itself still has to run
+    fn page_filter(&self) -> TaskFilter {
+        let mut filter = self.filter.clone();
+        let Some(cursor) = self
+            .cursor
+            .as_ref()
+            .filter(|_| self.sort == SortField::CreatedAt)
+        else {
+            return filter;
+        };
+        let Ok(at) = DateTime::parse_from_rfc3339(&cursor.value) else {
+            return filter;
+        };
+        let at = at.with_timezone(&Utc);
+        match self.order {
+            SortOrder::Asc => {
+                filter.started_after = Some(filter.started_after.map_or(at, |after| after.max(at)));
+            }
+            SortOrder::Desc => {
+                let end = at + chrono::Duration::nanoseconds(1);
+                filter.started_before =
+                    Some(filter.started_before.map_or(end, |before| before.min(end)));
+            }
+        }
+        filter
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TaskPage {
+    pub tasks: Vec<TaskMetadata>,
+    // Matches over all pages
+    pub total: usize,
+    // Set while more pages follow
+    pub next_cursor: Option<String>,
+}
+
+fn name_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let name = name.to_lowercase();
+    if !pattern.contains('*') {
+        return name.contains(&pattern);
+    }
+
+    // The parts between wildcards in order, the first and last anchored
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if name.len() < first.len() + last.len() || !name.starts_with(first) || !name.ends_with(last) {
+        return false;
+    }
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+impl TaskReader {
+    pub fn query(&self, query: &TaskQuery, scope: Option<&str>) -> TaskPage {
+        let snapshots = self.snapshots();
+
+        let total = snapshots
+            .iter()
+            .map(|snapshot| {
+                snapshot
+                    .select(&query.filter, scope)
+                    .filter(|task| query.matches_unindexed(task))
+                    .count()
+            })
+            .sum();
+
+        let page_filter = query.page_filter();
+        let mut page: Vec<(String, &TaskMetadata)> = snapshots
+            .iter()
+            .flat_map(|snapshot| snapshot.select(&page_filter, scope))
+            .filter(|task| query.matches_unindexed(task))
+            .map(|task| (query.sort.value(task), task))
+            .filter(|(value, task)| {
+                query.cursor.as_ref().is_none_or(|cursor| {
+                    let position = (value.as_str(), task.id);
+                    match query.order {
+                        SortOrder::Asc => position > cursor.position(),
+                        SortOrder::Desc => position < cursor.position(),
+                    }
+                })
+            })
+            .collect();
+        page.sort_by(|(a, a_task), (b, b_task)| (a, a_task.id).cmp(&(b, b_task.id)));
+        if query.order == SortOrder::Desc {
+            page.reverse();
+        }
+
+        let mut next_cursor = None;
+        if let Some(limit) = query.limit {
+            if page.len() > limit {
+                page.truncate(limit);
+                next_cursor = page
+                    .last()
+                    .map(|(_, task)| TaskCursor::after(task, query.sort, query.order).encode());
+            }
+        }
+
+        TaskPage {
+            tasks: page.into_iter().map(|(_, task)| task.clone()).collect(),
+            total,
+            next_cursor,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::query::TaskQuery;
+use crate::{ApiError, TaskStatus, TaskTypeRegistry};
+use chrono::{DateTime, Utc};
+
+// Search syntax shared by `GET /api/tasks?q=` and the LiveView search box.
+// Terms are separated by spaces and all have to match:
+//
+//   status:error type:custom tag:nightly   exact matches
+//   name:"etl*"                            name pattern, `*` matches anything
+//   duration>5s duration<=2m               finished tasks by how long they ran
+//   since:1h                               started in the last hour
+//   backup                                 bare words search the name
+//
+// Values with spaces go in double quotes. Durations take ms, s, m, h or d,
+// a bare number is milliseconds.
+pub const QUERY_FIELDS: [&str; 6] = ["status", "type", "name", "tag", "duration", "since"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equals,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+#[derive(Debug)]
+struct Term {
+    // None for bare words
+    field: Option<String>,
+    operator: Operator,
+    value: String,
+}
+
+impl TaskQuery {
+    // Reads the filter of a search, `now` anchors relative terms like since
+    // and `type:` takes the names registered in `task_types`
+    pub fn parse(
+        input: &str,
+        now: DateTime<Utc>,
+        task_types: &TaskTypeRegistry,
+    ) -> Result<Self, ApiError> {
+        let invalid = |message: String| {
+            ApiError::validation_error(
+                message,
+                Some(serde_json::json!({
+                    "field": "q",
+                    "provided_value": input,
+                    "valid_fields": QUERY_FIELDS
+                })),
+            )
+        };
+
+        let mut query = TaskQuery::default();
+        let mut words = Vec::new();
+        let mut seen = Vec::new();
+        for term in terms(input).map_err(invalid)? {
+            let Some(field) = term.field else {
+                words.push(term.value);
+                continue;
+            };
+            if field != "duration" {
+                if seen.contains(&field) {
+                    return Err(invalid(format!("'{}' is given more than once", field)));
+                }
+                seen.push(field.clone());
+                if term.operator != Operator::Equals {
+                    return Err(invalid(format!(
+                        "'{}' only supports ':', comparisons are for duration",
+                        field
+                    )));
+                }
+            }
+
+            let value = term.value;
+            match field.as_str() {
+                "status" => {
+                    query.filter.status = Some(value.parse::<TaskStatus>().map_err(invalid)?);
+                }
+                "type" => {
+                    let name = task_types.canonical_name(&value).map_err(|_| {
+                        invalid(format!(
+                            "Invalid task type: '{}', expected one of {}",
+                            value,
+                            task_types.names().join(", ")
+                        ))
+                    })?;
+                    query.filter.task_type = Some(name);
+                }
+                "name" => query.name = Some(value),
+                "tag" => query.filter.tag = Some(value),
+                "since" => {
+                    let ago = parse_duration_ms(&value).map_err(invalid)?;
+                    query.filter.started_after =
+                        Some(now - chrono::Duration::milliseconds(ago as i64));
+                }
+                "duration" => {
+                    let ms = parse_duration_ms(&value).map_err(invalid)?;
+                    let (min, max) = match term.operator {
+                        Operator::Equals => (Some(ms), Some(ms)),
+                        Operator::Greater => (Some(ms.saturating_add(1)), None),
+                        Operator::GreaterOrEqual => (Some(ms), None),
+                        Operator::Less => {
+                            if ms == 0 {
+                                return Err(invalid("Nothing runs for less than 0ms".to_string()));
+                            }
+                            (None, Some(ms - 1))
+                        }
+                        Operator::LessOrEqual => (None, Some(ms)),
+                    };
+                    if let Some(min) = min {
+                        query.min_duration_ms = Some(
+                            query
+                                .min_duration_ms
+                                .map_or(min, |current| current.max(min)),
+                        );
+                    }
+                    if let Some(max) = max {
+                        query.max_duration_ms = Some(
+                            query
+                                .max_duration_ms
+                                .map_or(max, |current| current.min(max)),
+                        );
+                    }
+                }
+                other => {
+                    return Err(invalid(format!(
+                        "Unknown field '{}', expected one of {}",
+                        other,
+                        QUERY_FIELDS.join(", ")
+                    )));
+                }
+            }
+        }
+
+        if !words.is_empty() {
+            if query.name.is_some() {
+                return Err(invalid(
+                    "Bare words search the name, they cannot be combined with name:".to_string(),
+                ));
+            }
+            query.name = Some(words.join(" "));
+        }
+        Ok(query)
+    }
+}
+
+fn terms(input: &str) -> Result<Vec<Term>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut terms = Vec::new();
+    let mut at = 0;
+    loop {
+        while at < chars.len() && chars[at].is_whitespace() {
+            at += 1;
+        }
+        if at == chars.len() {
+            return Ok(terms);
+        }
+
+        let start = at;
+        while at < chars.len() && (chars[at].is_alphanumeric() || chars[at] == '_') {
+            at += 1;
+        }
+        let operator = match (chars.get(at), chars.get(at + 1)) {
+            _ if at == start => None,
+            (Some('>'), Some('=')) => Some((Operator::GreaterOrEqual, 2)),
+            (Some('<'), Some('=')) => Some((Operator::LessOrEqual, 2)),
+            (Some('>'), _) => Some((Operator::Greater, 1)),
+            (Some('<'), _) => Some((Operator::Less, 1)),
+            (Some(':'), _) | (Some('='), _) => Some((Operator::Equals, 1)),
+            _ => None,
+        };
+
+        let (field, operator) = match operator {
+            Some((operator, width)) => {
+                let field: String = chars[start..at].iter().collect();
+                at += width;
+                (Some(field.to_lowercase()), operator)
+            }
+            None => {
+                at = start;
+                (None, Operator::Equals)
+            }
+        };
+        let value = value(&chars, &mut at)?;
+        if value.is_empty() {
+            if let Some(field) = field {
+                return Err(format!("'{}' is missing a value", field));
+            }
+        }
+        terms.push(Term {
+            field,
+            operator,
+            value,
+        });
+    }
+}
+
+// A quoted string or everything up to the next space
+fn value(chars: &[char], at: &mut usize) -> Result<String, String> {
+    let mut value = String::new();
+    if chars.get(*at) != Some(&'"') {
+        while *at < chars.len() && !chars[*at].is_whitespace() {
+            value.push(chars[*at]);
+            *at += 1;
+        }
+        return Ok(value);
+    }
+
+    *at += 1;
+    loop {
+        match chars.get(*at) {
+            Some('"') => {
+                *at += 1;
+                return Ok(value);
+            }
+            Some('\\') if matches!(chars.get(*at + 1), Some('"') | Some('\\')) => {
+                value.push(chars[*at + 1]);
+                *at += 2;
+            }
+            Some(c) => {
+                value.push(*c);
+                *at += 1;
+            }
+            None => return Err("Unterminated quote".to_string()),
+        }
+    }
+}
+
+fn parse_duration_ms(value: &str) -> Result<u64, String> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let scale = match unit.to_lowercase().as_str() {
+        "" | "ms" => 1.0,
+        "s" => 1_000.0,
+        "m" => 60_000.0,
+        "h" => 3_600_000.0,
+        "d" => 86_400_000.0,
+        _ => {
+            return Err(format!(
+                "Invalid duration '{}', use ms, s, m, h or d",
+                value
+            ))
+        }
+    };
+    match number.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok((number * scale).round() as u64),
+        _ => Err(format!("Invalid duration '{}'", value)),
+    }
+}
@@ -0,0 +1,349 @@
+use crate::{ApiError, ApiErrorType, CreateTask, TaskManagerActor, TaskMetadata, TaskStatus};
+use actix::{Handler, Message};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use uuid::Uuid;
+
+// Tasks per hour and runtime are counted over this trailing window
+pub const QUOTA_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+// Retry-After when no active task has a deadline to wait for
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+// Limits on what a namespace may submit. Unlike max_concurrent, which holds
+// extra tasks back, a submission over a quota is rejected.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Quota {
+    // Queued and running tasks, including those held back by max_concurrent
+    #[serde(default)]
+    pub max_active: Option<u32>,
+    #[serde(default)]
+    pub max_tasks_per_hour: Option<u32>,
+    // Runtime of tasks that finished in the last hour plus the time running
+    // tasks have used so far
+    #[serde(default)]
+    pub max_runtime_secs_per_hour: Option<u64>,
+}
+
+impl Quota {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        let limits = [
+            ("quota.max_active", self.max_active.map(u64::from)),
+            (
+                "quota.max_tasks_per_hour",
+                self.max_tasks_per_hour.map(u64::from),
+            ),
+            (
+                "quota.max_runtime_secs_per_hour",
+                self.max_runtime_secs_per_hour,
+            ),
+        ];
+        for (field, limit) in limits {
+            if limit == Some(0) {
+                return Err(ApiError::validation_error(
+                    format!("'{}' must be at least 1", field),
+                    Some(serde_json::json!({
+                        "field": field,
+                        "provided_value": 0
+                    })),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct QuotaUsage {
+    pub active: usize,
+    pub tasks_last_hour: usize,
+    pub runtime_secs_last_hour: u64,
+}
+
+// What a namespace used in the quota window that the task list no longer
+// shows, either because it is not a task or the task was archived
+#[derive(Debug, Default)]
+pub(crate) struct QuotaLedger {
+    admissions: VecDeque<DateTime<Utc>>,
+    // Finish time and runtime of tasks removed from memory
+    archived_runtime: VecDeque<(DateTime<Utc>, Duration)>,
+}
+
+impl QuotaLedger {
+    fn trim(&mut self, window_start: DateTime<Utc>) {
+        while self
+            .admissions
+            .front()
+            .is_some_and(|at| *at <= window_start)
+        {
+            self.admissions.pop_front();
+        }
+        while self
+            .archived_runtime
+            .front()
+            .is_some_and(|(at, _)| *at <= window_start)
+        {
+            self.archived_runtime.pop_front();
+        }
+    }
+}
+
+impl ApiError {
+    pub fn quota_exceeded(
+        namespace: &str,
+        quota: &str,
+        limit: u64,
+        used: u64,
+        retry_after: Duration,
+    ) -> Self {
+        let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        Self {
+            error_type: ApiErrorType::ConcurrencyError,
+            message: format!(
+                "Namespace '{}' is over its {} quota of {}",
+                namespace, quota, limit
+            ),
+            details: Some(serde_json::json!({
+                "namespace": namespace,
+                "quota": quota,
+                "limit": limit,
+                "used": used,
+                "retry_after_secs": retry_after_secs
+            })),
+            error_code: "QUOTA_EXCEEDED".to_string(),
+        }
+    }
+
+    // Seconds a client should wait before submitting again
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        self.details.as_ref()?.get("retry_after_secs")?.as_u64()
+    }
+}
+
+// A CreateTask that is rejected, instead of created, when its namespace is
+// over a quota. This is what API clients send, internal producers like the
+// load generator send CreateTask and are only counted.
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Result<Uuid, ApiError>")]
+pub struct SubmitTask {
+    pub task: CreateTask,
+}
+
+fn since(now: DateTime<Utc>, at: DateTime<Utc>) -> Duration {
+    now.signed_duration_since(at).to_std().unwrap_or_default()
+}
+
+impl TaskManagerActor {
+    fn window_start(&self) -> DateTime<Utc> {
+        let window = chrono::Duration::from_std(QUOTA_WINDOW).unwrap_or_default();
+        self.clock.now() - window
+    }
+
+    pub(crate) fn record_admission(&mut self, name: &str) {
+        let now = self.clock.now();
+        let window_start = self.window_start();
+        let ledger = &mut self.namespace_mut(name).ledger;
+        ledger.trim(window_start);
+        ledger.admissions.push_back(now);
+    }
+
+    // Keeps the runtime of tasks leaving memory counted until the window passes
+    pub(crate) fn record_archived_runtime(&mut self, tasks: &[TaskMetadata]) {
+        let window_start = self.window_start();
+        for task in tasks {
+            let (Some(finished_at), Some(duration_ms)) =
+                (task.finished_at, task.actual_duration_ms)
+            else {
+                continue;
+            };
+            if finished_at <= window_start {
+                continue;
+            }
+            let ledger = &mut self.namespace_mut(&task.namespace).ledger;
+            ledger
+                .archived_runtime
+                .push_back((finished_at, Duration::from_millis(duration_ms)));
+            ledger.trim(window_start);
+        }
+    }
+
+    // Queued and running tasks of the namespace
+    pub(crate) fn active_tasks<'a>(
+        &'a self,
+        name: &str,
+    ) -> impl Iterator<Item = &'a TaskMetadata> + 'a {
+        self.task_metadata
+            .namespace(name)
+            .into_iter()
+            .flat_map(|tasks| tasks.active.iter())
+            .filter_map(|id| self.task_metadata.get(id))
+    }
+
+    pub(crate) fn active_count(&self, name: &str) -> usize {
+        self.task_metadata
+            .namespace(name)
+            .map_or(0, |tasks| tasks.active.len())
+    }
+
+    // Finish time and runtime of every run that ended inside the window
+    fn finished_runs(&self, name: &str) -> Vec<(DateTime<Utc>, Duration)> {
+        let window_start = self.window_start();
+        let mut runs: Vec<(DateTime<Utc>, Duration)> = self
+            .task_metadata
+            .namespace(name)
+            .into_iter()
+            .flat_map(|tasks| tasks.finished.iter().rev())
+            .take_while(|((finished_at, _), _)| *finished_at > window_start)
+            .map(|((finished_at, _), runtime)| (*finished_at, *runtime))
+            .collect();
+        if let Some(namespace) = self.namespaces.get(name) {
+            runs.extend(
+                namespace
+                    .ledger
+                    .archived_runtime
+                    .iter()
+                    .filter(|(finished_at, _)| *finished_at > window_start),
+            );
+        }
+        runs.sort_by_key(|(finished_at, _)| *finished_at);
+        runs
+    }
+
+    fn running_time(&self, name: &str) -> Duration {
+        let now = self.clock.now();
+        self.active_tasks(name)
+            .filter(|task| task.status == TaskStatus::InProgress)
+            .map(|task| since(now, task.started_at))
+            .sum()
+    }
+
+    fn admissions_in_window(&self, name: &str) -> Vec<DateTime<Utc>> {
+        let window_start = self.window_start();
+        self.namespaces
+            .get(name)
+            .map_or_else(Vec::new, |namespace| {
+                namespace
+                    .ledger
+                    .admissions
+                    .iter()
+                    .filter(|at| **at > window_start)
+                    .copied()
+                    .collect()
+            })
+    }
+
+    pub(crate) fn quota_usage(&self, name: &str) -> QuotaUsage {
+        let finished: Duration = self
+            .finished_runs(name)
+            .iter()
+            .map(|(_, runtime)| *runtime)
+            .sum();
+        QuotaUsage {
+            active: self.active_count(name),
+            tasks_last_hour: self.admissions_in_window(name).len(),
+            runtime_secs_last_hour: (finished + self.running_time(name)).as_secs(),
+        }
+    }
+
+    // Time until something that happened at `at` leaves the window
+    fn until_expiry(&self, at: DateTime<Utc>) -> Duration {
+        QUOTA_WINDOW.saturating_sub(since(self.clock.now(), at))
+    }
+
+    fn check_quota(&self, name: &str) -> Result<(), ApiError> {
+        let Some(quota) = self
+            .namespaces
+            .get(name)
+            .map(|namespace| &namespace.config.quota)
+        else {
+            return Ok(());
+        };
+        let now = self.clock.now();
+
+        if let Some(limit) = quota.max_active {
+            let active = self.active_count(name);
+            if active >= limit as usize {
+                // The first active task to hit its timeout frees a slot at the latest
+                let retry_after = self
+                    .active_tasks(name)
+                    .map(|task| {
+                        Duration::from_millis(task.timeout_ms)
+                            .saturating_sub(since(now, task.started_at))
+                    })
+                    .filter(|remaining| !remaining.is_zero())
+                    .min()
+                    .unwrap_or(DEFAULT_RETRY_AFTER);
+                return Err(ApiError::quota_exceeded(
+                    name,
+                    "max_active",
+                    limit.into(),
+                    active as u64,
+                    retry_after,
+                ));
+            }
+        }
+
+        if let Some(limit) = quota.max_tasks_per_hour {
+            let admissions = self.admissions_in_window(name);
+            if admissions.len() >= limit as usize {
+                // Once enough admissions age out one more fits
+                let oldest_blocking = admissions[admissions.len() - limit as usize];
+                return Err(ApiError::quota_exceeded(
+                    name,
+                    "max_tasks_per_hour",
+                    limit.into(),
+                    admissions.len() as u64,
+                    self.until_expiry(oldest_blocking),
+                ));
+            }
+        }
+
+        if let Some(limit_secs) = quota.max_runtime_secs_per_hour {
+            let limit = Duration::from_secs(limit_secs);
+            let runs = self.finished_runs(name);
+            let running = self.running_time(name);
+            let used = runs.iter().map(|(_, runtime)| *runtime).sum::<Duration>() + running;
+            if used >= limit {
+                // Wait until enough finished runtime ages out, or a whole
+                // window when running tasks alone use up the quota
+                let mut remaining = used;
+                let retry_after = runs
+                    .iter()
+                    .find_map(|(finished_at, runtime)| {
+                        remaining = remaining.saturating_sub(*runtime);
+                        (remaining < limit).then(|| self.until_expiry(*finished_at))
+                    })
+                    .unwrap_or(QUOTA_WINDOW);
+                return Err(ApiError::quota_exceeded(
+                    name,
+                    "max_runtime_secs_per_hour",
+                    limit_secs,
+                    used.as_secs(),
+                    retry_after,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Handler<SubmitTask> for TaskManagerActor {
+    type Result = Result<Uuid, ApiError>;
+
+    fn handle(&mut self, msg: SubmitTask, ctx: &mut Self::Context) -> Self::Result {
+        let name = msg.task.namespace().to_string();
+        if let Err(error) = self.check_quota(&name) {
+            tracing::warn!(
+                task.name = %msg.task.name,
+                task.namespace = %name,
+                error = %error.message,
+                "task rejected by namespace quota"
+            );
+            return Err(error);
+        }
+        Ok(self.create_task(msg.task, ctx))
+    }
+}
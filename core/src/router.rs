@@ -0,0 +1,414 @@
+use crate::metrics::Ping;
+use crate::{
+    validate_namespace, ApiError, ArchiveExpiredTasks, CancelTaskById, ChaosConfig,
+    ConfigureNamespace, CreateTask, Executor, GetAllTasks, GetChaosConfig, GetNamespace,
+    GetNamespaceTask, GetNamespaceTasks, GetSchedulerReport, GetTask, GetWorkers, ListNamespaces,
+    MeasureMailboxLatency, NamespaceConfig, NamespaceInfo, Quota, RegisterWorker, SchedulerReport,
+    SetFaultProfile, SnapshotCell, SubmitTask, TaskManagerActor, TaskMetadata, TaskReader,
+    WorkerDisconnected, WorkerInfo, WorkerReport,
+};
+use actix::dev::{Request, ToEnvelope};
+use actix::{Actor, Addr, Arbiter, ArbiterHandle, Context, Handler, Message, ResponseFuture};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+// Shard that owns a task, by its id
+pub fn shard_for(id: &Uuid, shards: usize) -> usize {
+    (id.as_u128() % shards.max(1) as u128) as usize
+}
+
+// Fronts several TaskManagerActors, each on its own arbiter, with the same
+// message API as a single one. Tasks are owned by the shard their id hashes
+// to, lookups and cancels go straight there and listings ask every shard.
+//
+// State that spans tasks stays on one shard so it behaves exactly as with
+// a single manager: remote workers and remote tasks live on shard 0, and a
+// namespace with a concurrency limit or quota has all its tasks on a home
+// shard. The router picks task ids that hash to the right shard.
+//
+// Tasks are never moved between shards. Those a namespace already had
+// before it got a limit or quota stay where they were and are not counted
+// by its home shard, so until they finish the namespace can run and hold
+// more than its max_concurrent and max_active, and its runtime quota
+// misses their work. Configure namespaces before submitting to them.
+//
+// Sharding spreads the managers' work, not the routing: the router is one
+// actor and every request still passes through its mailbox, which only
+// places or forwards it. A limited or quota namespace, and remote tasks,
+// run on a single shard however many there are, so they gain nothing from
+// more shards. Reads that can go through a TaskReader should, they never
+// reach the router.
+pub struct TaskManagerRouter {
+    shards: Vec<Addr<TaskManagerActor>>,
+    arbiters: Vec<ArbiterHandle>,
+    configs: HashMap<String, NamespaceConfig>,
+    snapshots: Vec<SnapshotCell>,
+}
+
+// Shard that runs remote tasks and owns the worker pool
+const WORKER_SHARD: usize = 0;
+
+impl TaskManagerRouter {
+    // Starts `shards` managers built by `make`, which gets the shard index
+    pub fn start_shards<F>(shards: usize, make: F) -> Self
+    where
+        F: Fn(usize) -> TaskManagerActor + Send + Sync + 'static,
+    {
+        let make = Arc::new(make);
+        let mut addrs = Vec::new();
+        let mut arbiters = Vec::new();
+        let mut snapshots = Vec::new();
+        for index in 0..shards.max(1) {
+            let arbiter = Arbiter::new().handle();
+            let make = make.clone();
+            let snapshot = SnapshotCell::default();
+            let cell = snapshot.clone();
+            addrs.push(TaskManagerActor::start_in_arbiter(&arbiter, move |_| {
+                make(index).with_snapshot_cell(cell)
+            }));
+            arbiters.push(arbiter);
+            snapshots.push(snapshot);
+        }
+        tracing::info!(shards = addrs.len(), "task manager sharded");
+
+        Self {
+            shards: addrs,
+            arbiters,
+            configs: HashMap::new(),
+            snapshots,
+        }
+    }
+
+    // Reads every shard's snapshots
+    pub fn reader(&self) -> TaskReader {
+        TaskReader::new(self.snapshots.clone())
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    fn shard(&self, id: &Uuid) -> Addr<TaskManagerActor> {
+        self.shards[shard_for(id, self.shards.len())].clone()
+    }
+
+    // Shard every task of the namespace must go to, if any. Only tasks
+    // placed after the namespace was configured go there.
+    fn home_shard(&self, task: &CreateTask) -> Option<usize> {
+        if task.executor == Executor::Remote {
+            return Some(WORKER_SHARD);
+        }
+        let name = task.namespace();
+        let config = self.configs.get(name)?;
+        if config.max_concurrent.is_none() && config.quota == Quota::default() {
+            return None;
+        }
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        Some((hasher.finish() % self.shards.len() as u64) as usize)
+    }
+
+    // Gives the task an id owned by its home shard, or a random one
+    fn place(&self, mut task: CreateTask) -> (Addr<TaskManagerActor>, CreateTask) {
+        let id = match self.home_shard(&task) {
+            Some(home) => id_on_shard(home, self.shards.len()),
+            None => Uuid::new_v4(),
+        };
+        task.id = Some(id);
+        (self.shard(&id), task)
+    }
+
+    fn broadcast<M>(&self, msg: M) -> Vec<Request<TaskManagerActor, M>>
+    where
+        M: Message + Clone + Send + 'static,
+        M::Result: Send,
+        TaskManagerActor: Handler<M>,
+        <TaskManagerActor as Actor>::Context: ToEnvelope<TaskManagerActor, M>,
+    {
+        // Every send is queued before any reply is awaited, so the shards
+        // work on it in parallel
+        self.shards
+            .iter()
+            .map(|shard| shard.send(msg.clone()))
+            .collect()
+    }
+}
+
+// A random v4 id that shard_for maps to `shard`. Only the low 56 bits are
+// moved, they are random in a v4 id and clear of the version and variant.
+fn id_on_shard(shard: usize, shards: usize) -> Uuid {
+    const RANDOM_BITS: u128 = (1 << 56) - 1;
+    let shards = shards.max(1) as u128;
+    let id = Uuid::new_v4().as_u128();
+    let offset = (shard as u128 + shards - id % shards) % shards;
+    let id = if (id & RANDOM_BITS) + offset <= RANDOM_BITS {
+        id + offset
+    } else {
+        id - (shards - offset)
+    };
+    Uuid::from_u128(id)
+}
+
+// A shard only goes away when the system shuts down
+fn unavailable(error: actix::MailboxError) -> ApiError {
+    ApiError::internal_error(format!("Task manager shard unavailable: {}", error))
+}
+
+async fn gather<M>(requests: Vec<Request<TaskManagerActor, M>>) -> Vec<M::Result>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+    TaskManagerActor: Handler<M>,
+{
+    let mut results = Vec::with_capacity(requests.len());
+    for request in requests {
+        match request.await {
+            Ok(result) => results.push(result),
+            Err(error) => tracing::error!("{}", unavailable(error).message),
+        }
+    }
+    results
+}
+
+// Sums the counts of one namespace as seen by each shard
+fn merge_namespaces(infos: impl IntoIterator<Item = NamespaceInfo>) -> Vec<NamespaceInfo> {
+    let mut merged: BTreeMap<String, NamespaceInfo> = BTreeMap::new();
+    for info in infos {
+        match merged.get_mut(&info.name) {
+            Some(existing) => {
+                existing.created_at = existing.created_at.min(info.created_at);
+                existing.running += info.running;
+                existing.waiting += info.waiting;
+                existing.total += info.total;
+                existing.usage.active += info.usage.active;
+                existing.usage.tasks_last_hour += info.usage.tasks_last_hour;
+                existing.usage.runtime_secs_last_hour += info.usage.runtime_secs_last_hour;
+            }
+            None => {
+                merged.insert(info.name.clone(), info);
+            }
+        }
+    }
+    merged.into_values().collect()
+}
+
+impl Actor for TaskManagerRouter {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        for arbiter in &self.arbiters {
+            arbiter.stop();
+        }
+    }
+}
+
+impl Handler<CreateTask> for TaskManagerRouter {
+    type Result = ResponseFuture<Uuid>;
+
+    fn handle(&mut self, msg: CreateTask, _ctx: &mut Self::Context) -> Self::Result {
+        let (shard, task) = self.place(msg);
+        let id = task.id.unwrap_or_default();
+        let request = shard.send(task);
+        Box::pin(async move {
+            if let Err(error) = request.await {
+                tracing::error!("{}", unavailable(error).message);
+            }
+            id
+        })
+    }
+}
+
+impl Handler<SubmitTask> for TaskManagerRouter {
+    type Result = ResponseFuture<Result<Uuid, ApiError>>;
+
+    fn handle(&mut self, msg: SubmitTask, _ctx: &mut Self::Context) -> Self::Result {
+        let (shard, task) = self.place(msg.task);
+        let request = shard.send(SubmitTask { task });
+        Box::pin(async move { request.await.map_err(unavailable)? })
+    }
+}
+
+impl Handler<GetTask> for TaskManagerRouter {
+    type Result = ResponseFuture<Option<TaskMetadata>>;
+
+    fn handle(&mut self, msg: GetTask, _ctx: &mut Self::Context) -> Self::Result {
+        let request = self.shard(&msg.id).send(msg);
+        Box::pin(async move { request.await.ok().flatten() })
+    }
+}
+
+impl Handler<GetNamespaceTask> for TaskManagerRouter {
+    type Result = ResponseFuture<Option<TaskMetadata>>;
+
+    fn handle(&mut self, msg: GetNamespaceTask, _ctx: &mut Self::Context) -> Self::Result {
+        let request = self.shard(&msg.id).send(msg);
+        Box::pin(async move { request.await.ok().flatten() })
+    }
+}
+
+impl Handler<CancelTaskById> for TaskManagerRouter {
+    type Result = ResponseFuture<bool>;
+
+    fn handle(&mut self, msg: CancelTaskById, _ctx: &mut Self::Context) -> Self::Result {
+        let request = self.shard(&msg.id).send(msg);
+        Box::pin(async move { request.await.unwrap_or(false) })
+    }
+}
+
+impl Handler<GetAllTasks> for TaskManagerRouter {
+    type Result = ResponseFuture<Vec<TaskMetadata>>;
+
+    fn handle(&mut self, msg: GetAllTasks, _ctx: &mut Self::Context) -> Self::Result {
+        let requests = self.broadcast(msg);
+        Box::pin(async move { gather(requests).await.into_iter().flatten().collect() })
+    }
+}
+
+impl Handler<GetNamespaceTasks> for TaskManagerRouter {
+    type Result = ResponseFuture<Vec<TaskMetadata>>;
+
+    fn handle(&mut self, msg: GetNamespaceTasks, _ctx: &mut Self::Context) -> Self::Result {
+        let requests = self.broadcast(msg);
+        Box::pin(async move { gather(requests).await.into_iter().flatten().collect() })
+    }
+}
+
+impl Handler<ArchiveExpiredTasks> for TaskManagerRouter {
+    type Result = ResponseFuture<usize>;
+
+    fn handle(&mut self, msg: ArchiveExpiredTasks, _ctx: &mut Self::Context) -> Self::Result {
+        let requests = self.broadcast(msg);
+        Box::pin(async move { gather(requests).await.into_iter().sum() })
+    }
+}
+
+impl Handler<SetFaultProfile> for TaskManagerRouter {
+    type Result = ResponseFuture<()>;
+
+    fn handle(&mut self, msg: SetFaultProfile, _ctx: &mut Self::Context) -> Self::Result {
+        let requests = self.broadcast(msg);
+        Box::pin(async move {
+            gather(requests).await;
+        })
+    }
+}
+
+impl Handler<GetChaosConfig> for TaskManagerRouter {
+    type Result = ResponseFuture<ChaosConfig>;
+
+    fn handle(&mut self, msg: GetChaosConfig, _ctx: &mut Self::Context) -> Self::Result {
+        let request = self.shards[WORKER_SHARD].send(msg);
+        Box::pin(async move { request.await.unwrap_or_default() })
+    }
+}
+
+// Every shard gets the configuration so each enforces it on its own tasks,
+// and the router learns which namespaces need a home shard
+impl Handler<ConfigureNamespace> for TaskManagerRouter {
+    type Result = ResponseFuture<Result<NamespaceInfo, ApiError>>;
+
+    fn handle(&mut self, msg: ConfigureNamespace, _ctx: &mut Self::Context) -> Self::Result {
+        if let Err(error) = validate_namespace(&msg.name).and_then(|_| msg.config.validate()) {
+            return Box::pin(async move { Err(error) });
+        }
+        self.configs.insert(msg.name.clone(), msg.config.clone());
+
+        let requests = self.broadcast(msg);
+        Box::pin(async move {
+            let mut infos = Vec::new();
+            for request in requests {
+                infos.push(request.await.map_err(unavailable)??);
+            }
+            merge_namespaces(infos)
+                .pop()
+                .ok_or_else(|| ApiError::internal_error("Namespace was not stored".to_string()))
+        })
+    }
+}
+
+impl Handler<ListNamespaces> for TaskManagerRouter {
+    type Result = ResponseFuture<Vec<NamespaceInfo>>;
+
+    fn handle(&mut self, msg: ListNamespaces, _ctx: &mut Self::Context) -> Self::Result {
+        let requests = self.broadcast(msg);
+        Box::pin(async move { merge_namespaces(gather(requests).await.into_iter().flatten()) })
+    }
+}
+
+impl Handler<GetNamespace> for TaskManagerRouter {
+    type Result = ResponseFuture<Option<NamespaceInfo>>;
+
+    fn handle(&mut self, msg: GetNamespace, _ctx: &mut Self::Context) -> Self::Result {
+        let requests = self.broadcast(msg);
+        Box::pin(
+            async move { merge_namespaces(gather(requests).await.into_iter().flatten()).pop() },
+        )
+    }
+}
+
+impl Handler<RegisterWorker> for TaskManagerRouter {
+    type Result = ResponseFuture<Result<Uuid, ApiError>>;
+
+    fn handle(&mut self, msg: RegisterWorker, _ctx: &mut Self::Context) -> Self::Result {
+        let request = self.shards[WORKER_SHARD].send(msg);
+        Box::pin(async move { request.await.map_err(unavailable)? })
+    }
+}
+
+impl Handler<WorkerReport> for TaskManagerRouter {
+    type Result = ResponseFuture<Result<(), ApiError>>;
+
+    fn handle(&mut self, msg: WorkerReport, _ctx: &mut Self::Context) -> Self::Result {
+        let request = self.shards[WORKER_SHARD].send(msg);
+        Box::pin(async move { request.await.map_err(unavailable)? })
+    }
+}
+
+impl Handler<WorkerDisconnected> for TaskManagerRouter {
+    type Result = ();
+
+    fn handle(&mut self, msg: WorkerDisconnected, _ctx: &mut Self::Context) -> Self::Result {
+        self.shards[WORKER_SHARD].do_send(msg);
+    }
+}
+
+impl Handler<GetWorkers> for TaskManagerRouter {
+    type Result = ResponseFuture<Vec<WorkerInfo>>;
+
+    fn handle(&mut self, msg: GetWorkers, _ctx: &mut Self::Context) -> Self::Result {
+        let request = self.shards[WORKER_SHARD].send(msg);
+        Box::pin(async move { request.await.unwrap_or_default() })
+    }
+}
+
+impl Handler<GetSchedulerReport> for TaskManagerRouter {
+    type Result = ResponseFuture<SchedulerReport>;
+
+    fn handle(&mut self, msg: GetSchedulerReport, _ctx: &mut Self::Context) -> Self::Result {
+        let request = self.shards[WORKER_SHARD].send(msg);
+        Box::pin(async move { request.await.unwrap_or_default() })
+    }
+}
+
+// One shard at a time, so each round trip is that shard's queue alone
+impl Handler<MeasureMailboxLatency> for TaskManagerRouter {
+    type Result = ResponseFuture<Vec<(usize, Duration)>>;
+
+    fn handle(&mut self, _msg: MeasureMailboxLatency, _ctx: &mut Self::Context) -> Self::Result {
+        let shards = self.shards.clone();
+        Box::pin(async move {
+            let mut latencies = Vec::with_capacity(shards.len());
+            for (index, shard) in shards.iter().enumerate() {
+                let start = Instant::now();
+                match shard.send(Ping).await {
+                    Ok(()) => latencies.push((index, start.elapsed())),
+                    Err(error) => tracing::error!("{}", unavailable(error).message),
+                }
+            }
+            latencies
+        })
+    }
+}
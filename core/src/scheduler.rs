@@ -0,0 +1,167 @@
+use crate::{TaskManagerActor, DEFAULT_NAMESPACE};
+use actix::{Handler, Message, MessageResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Duration;
+
+// Weighted fair queuing over the namespaces with queued remote tasks.
+// Every task handed to a worker moves its namespace's virtual time on by
+// 1/weight and the namespace furthest behind goes next, so under contention
+// namespaces get worker slots in proportion to their weights however much
+// each of them submits.
+#[derive(Debug, Default)]
+pub(crate) struct FairScheduler {
+    // Virtual time of every backlogged namespace
+    passes: HashMap<String, f64>,
+    // Virtual time of the last dispatch. Namespaces joining start here, so
+    // being idle earns no credit to burst with later.
+    virtual_time: f64,
+    stats: HashMap<String, QueueStats>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct QueueStats {
+    dispatched: u64,
+    total_wait: Duration,
+    max_wait: Duration,
+}
+
+impl FairScheduler {
+    // Forgets namespaces whose queue emptied and starts the newly backlogged
+    // ones at the current virtual time
+    pub(crate) fn sync(&mut self, backlogged: &BTreeSet<String>) {
+        self.passes.retain(|name, _| backlogged.contains(name));
+        for name in backlogged {
+            self.passes.entry(name.clone()).or_insert(self.virtual_time);
+        }
+    }
+
+    // The namespace to take the next task from among `candidates`. Those
+    // left out, for instance because no worker can take their tasks right
+    // now, keep their place.
+    pub(crate) fn next<'a>(&self, candidates: &'a BTreeSet<String>) -> Option<&'a String> {
+        candidates.iter().min_by(|a, b| {
+            let pass = |name: &String| self.passes.get(name).copied().unwrap_or(self.virtual_time);
+            pass(a).total_cmp(&pass(b))
+        })
+    }
+
+    pub(crate) fn record_dispatch(&mut self, name: &str, weight: u32, wait: Duration) {
+        let pass = self
+            .passes
+            .entry(name.to_string())
+            .or_insert(self.virtual_time);
+        self.virtual_time = self.virtual_time.max(*pass);
+        *pass += 1.0 / weight.max(1) as f64;
+
+        let stats = self.stats.entry(name.to_string()).or_default();
+        stats.dispatched += 1;
+        stats.total_wait += wait;
+        stats.max_wait = stats.max_wait.max(wait);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueueReport {
+    pub namespace: String,
+    pub weight: u32,
+    // Remote tasks waiting for a worker
+    pub queued: usize,
+    pub dispatched: u64,
+    // Fraction of all dispatches that went to this namespace
+    pub share: f64,
+    // Fraction its weight entitles it to among the namespaces listed
+    pub target_share: f64,
+    pub avg_wait_ms: u64,
+    pub max_wait_ms: u64,
+    // How long the oldest queued task has been waiting
+    pub oldest_wait_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SchedulerReport {
+    pub queues: Vec<QueueReport>,
+}
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "SchedulerReport")]
+pub struct GetSchedulerReport;
+
+impl TaskManagerActor {
+    pub(crate) fn namespace_of(&self, task_id: &uuid::Uuid) -> String {
+        self.task_metadata.get(task_id).map_or_else(
+            || DEFAULT_NAMESPACE.to_string(),
+            |task| task.namespace.clone(),
+        )
+    }
+
+    pub(crate) fn namespace_weight(&self, name: &str) -> u32 {
+        self.namespaces
+            .get(name)
+            .map_or(1, |namespace| namespace.config.weight)
+    }
+
+    fn scheduler_report(&self) -> SchedulerReport {
+        let now = self.clock.now();
+        // Queue length and oldest wait by namespace
+        let mut queued: BTreeMap<String, (usize, Duration)> = BTreeMap::new();
+        for task_id in self.queued_task_ids() {
+            let Some(task) = self.task_metadata.get(&task_id) else {
+                continue;
+            };
+            let waited = task
+                .queued_at
+                .and_then(|queued_at| now.signed_duration_since(queued_at).to_std().ok())
+                .unwrap_or_default();
+            let entry = queued.entry(task.namespace.clone()).or_default();
+            entry.0 += 1;
+            entry.1 = entry.1.max(waited);
+        }
+
+        let names: BTreeSet<&String> = queued.keys().chain(self.scheduler.stats.keys()).collect();
+        let total_dispatched: u64 = self
+            .scheduler
+            .stats
+            .values()
+            .map(|stats| stats.dispatched)
+            .sum();
+        let total_weight: u32 = names.iter().map(|name| self.namespace_weight(name)).sum();
+
+        let queues = names
+            .into_iter()
+            .map(|name| {
+                let stats = self.scheduler.stats.get(name).cloned().unwrap_or_default();
+                let (queued, oldest_wait) = queued.get(name).copied().unwrap_or_default();
+                let weight = self.namespace_weight(name);
+                QueueReport {
+                    namespace: name.clone(),
+                    weight,
+                    queued,
+                    dispatched: stats.dispatched,
+                    share: if total_dispatched > 0 {
+                        stats.dispatched as f64 / total_dispatched as f64
+                    } else {
+                        0.0
+                    },
+                    target_share: weight as f64 / total_weight.max(1) as f64,
+                    avg_wait_ms: stats
+                        .total_wait
+                        .checked_div(stats.dispatched.max(1) as u32)
+                        .unwrap_or_default()
+                        .as_millis() as u64,
+                    max_wait_ms: stats.max_wait.as_millis() as u64,
+                    oldest_wait_ms: oldest_wait.as_millis() as u64,
+                }
+            })
+            .collect();
+        SchedulerReport { queues }
+    }
+}
+
+impl Handler<GetSchedulerReport> for TaskManagerActor {
+    type Result = MessageResult<GetSchedulerReport>;
+
+    fn handle(&mut self, _msg: GetSchedulerReport, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.scheduler_report())
+    }
+}
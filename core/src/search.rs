@@ -0,0 +1,355 @@
+use crate::query::TaskQuery;
+use crate::snapshot::TaskReader;
+use crate::{task_key, TaskMetadata};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use uuid::Uuid;
+
+// BM25 parameters, the usual defaults
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+// Longest highlight in bytes, longer fields are cut around the first match
+const SNIPPET_BYTES: usize = 160;
+const SNIPPET_LEAD_BYTES: usize = 40;
+
+// Characters that join words into one token, so `db-3.internal` is found as
+// a whole as well as by its parts
+const CONNECTORS: [char; 5] = ['.', '-', '_', ':', '/'];
+
+// Text fields searched and how much a match in each counts. Tasks have no
+// log of their own, attempt_errors is the closest thing to one.
+const FIELDS: [(&str, u32); 5] = [
+    ("name", 3),
+    ("message", 1),
+    ("result", 1),
+    ("error", 2),
+    ("attempt_errors", 1),
+];
+
+fn field_texts(task: &TaskMetadata) -> Vec<(&'static str, u32, String)> {
+    let text = [
+        task.name.clone(),
+        task.message.clone(),
+        task.result.clone().unwrap_or_default(),
+        task.error.clone().unwrap_or_default(),
+        task.attempt_errors.join("\n"),
+    ];
+    FIELDS
+        .iter()
+        .zip(text)
+        .map(|((name, weight), text)| (*name, *weight, text))
+        .collect()
+}
+
+// Lowercased terms of `text` with where they were found
+fn tokens(text: &str) -> Vec<(String, Range<usize>)> {
+    let mut tokens = Vec::new();
+    let mut word: Option<usize> = None;
+    for (at, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        let joins = c.is_alphanumeric() || CONNECTORS.contains(&c);
+        match (word, joins) {
+            (None, true) => word = Some(at),
+            (Some(start), false) => {
+                push_word(text, start..at, &mut tokens);
+                word = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+// A run of word and connector characters becomes the whole run, its pieces
+// between `:` and `/` (host names in `host:port`, path segments) and its
+// alphanumeric parts, so any of them can be searched for
+fn push_word(text: &str, range: Range<usize>, tokens: &mut Vec<(String, Range<usize>)>) {
+    let mut words: Vec<Range<usize>> = trimmed(text, range.clone()).into_iter().collect();
+    words.extend(split(text, range.clone(), |c| c == ':' || c == '/'));
+    let parts = split(text, range, |c| !c.is_alphanumeric());
+
+    let mut seen = Vec::new();
+    for word in words {
+        // A word that is a single part is pushed as that part
+        if parts.len() > 1 && !parts.contains(&word) && !seen.contains(&word) {
+            seen.push(word.clone());
+            tokens.push((text[word.clone()].to_lowercase(), word));
+        }
+    }
+    for part in parts {
+        tokens.push((text[part.clone()].to_lowercase(), part));
+    }
+}
+
+// The pieces of `range` between characters matching `separator`, each
+// trimmed of connectors
+fn split(text: &str, range: Range<usize>, separator: impl Fn(char) -> bool) -> Vec<Range<usize>> {
+    let mut pieces = Vec::new();
+    let mut start = range.start;
+    for (at, c) in text[range.clone()].char_indices() {
+        if separator(c) {
+            pieces.extend(trimmed(text, start..range.start + at));
+            start = range.start + at + c.len_utf8();
+        }
+    }
+    pieces.extend(trimmed(text, start..range.end));
+    pieces
+}
+
+// `range` without connectors at either end, None when nothing is left
+fn trimmed(text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let trim = |c: char| CONNECTORS.contains(&c);
+    let piece = &text[range.clone()];
+    let start = range.start + (piece.len() - piece.trim_start_matches(trim).len());
+    let end = range.end - (piece.len() - piece.trim_end_matches(trim).len());
+    (start < end).then_some(start..end)
+}
+
+// Inverted index over the text fields of a snapshot's tasks. Persistent
+// like TaskIndex, so a snapshot only rebuilds the postings of the tasks
+// that changed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TextIndex {
+    // term -> task -> field-weighted term frequency
+    postings: im::HashMap<String, im::HashMap<Uuid, u32>>,
+    // Field-weighted number of terms per task
+    lengths: im::HashMap<Uuid, u32>,
+    total_length: u64,
+}
+
+impl TextIndex {
+    pub(crate) fn insert(&mut self, task: &TaskMetadata) {
+        let (frequencies, length) = document(task);
+        for (term, frequency) in frequencies {
+            self.postings
+                .entry(term)
+                .or_default()
+                .insert(task.id, frequency);
+        }
+        self.lengths.insert(task.id, length);
+        self.total_length += u64::from(length);
+    }
+
+    // `task` as it was indexed
+    pub(crate) fn remove(&mut self, task: &TaskMetadata) {
+        let (frequencies, _) = document(task);
+        for term in frequencies.keys() {
+            let Some(postings) = self.postings.get_mut(term) else {
+                continue;
+            };
+            postings.remove(&task.id);
+            if postings.is_empty() {
+                self.postings.remove(term);
+            }
+        }
+        if let Some(length) = self.lengths.remove(&task.id) {
+            self.total_length -= u64::from(length);
+        }
+    }
+
+    // Whether indexing `new` in place of `old` would change anything,
+    // progress and status updates leave the text alone
+    pub(crate) fn same_text(old: &TaskMetadata, new: &TaskMetadata) -> bool {
+        old.name == new.name
+            && old.message == new.message
+            && old.result == new.result
+            && old.error == new.error
+            && old.attempt_errors == new.attempt_errors
+    }
+}
+
+fn document(task: &TaskMetadata) -> (HashMap<String, u32>, u32) {
+    let mut frequencies = HashMap::new();
+    let mut length = 0;
+    for (_, weight, text) in field_texts(task) {
+        for (term, _) in tokens(&text) {
+            *frequencies.entry(term).or_default() += weight;
+            length += weight;
+        }
+    }
+    (frequencies, length)
+}
+
+// A run of a highlight, matched runs are the query's terms
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fragment {
+    pub text: String,
+    pub matched: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Highlight {
+    pub field: String,
+    pub fragments: Vec<Fragment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub task: TaskMetadata,
+    pub score: f64,
+    // One per field with a match, in field order
+    pub highlights: Vec<Highlight>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchResults {
+    // Best first
+    pub hits: Vec<SearchHit>,
+    // Every task that matched, not only the hits returned
+    pub total: usize,
+}
+
+impl TaskReader {
+    // Full-text search ranked with BM25 over every shard, keeping the tasks
+    // `filter` matches. A task matches when it has any of the terms.
+    pub fn search(
+        &self,
+        text: &str,
+        filter: &TaskQuery,
+        scope: Option<&str>,
+        limit: usize,
+    ) -> SearchResults {
+        let terms: HashSet<String> = tokens(text).into_iter().map(|(term, _)| term).collect();
+        let snapshots = self.snapshots();
+        let indexes: Vec<&TextIndex> = snapshots
+            .iter()
+            .map(|snapshot| snapshot.text_index())
+            .collect();
+
+        // Collection statistics span the shards so scores compare across them
+        let documents: usize = indexes.iter().map(|index| index.lengths.len()).sum();
+        if documents == 0 || terms.is_empty() {
+            return SearchResults::default();
+        }
+        let total_length: u64 = indexes.iter().map(|index| index.total_length).sum();
+        let average_length = (total_length as f64 / documents as f64).max(1.0);
+
+        let mut scored: Vec<(f64, &TaskMetadata)> = Vec::new();
+        for (snapshot, index) in snapshots.iter().zip(&indexes) {
+            let mut scores: HashMap<Uuid, f64> = HashMap::new();
+            for term in &terms {
+                let containing = indexes
+                    .iter()
+                    .filter_map(|index| index.postings.get(term))
+                    .map(|postings| postings.len())
+                    .sum::<usize>() as f64;
+                let Some(postings) = index.postings.get(term) else {
+                    continue;
+                };
+                let idf = (1.0 + (documents as f64 - containing + 0.5) / (containing + 0.5)).ln();
+                for (id, frequency) in postings {
+                    let frequency = f64::from(*frequency);
+                    let length = f64::from(index.lengths.get(id).copied().unwrap_or_default());
+                    *scores.entry(*id).or_default() += idf * frequency * (K1 + 1.0)
+                        / (frequency + K1 * (1.0 - B + B * length / average_length));
+                }
+            }
+            scored.extend(scores.into_iter().filter_map(|(id, score)| {
+                snapshot
+                    .get(&id)
+                    .filter(|task| snapshot.is_visible(task, scope) && filter.matches(task))
+                    .map(|task| (score, task))
+            }));
+        }
+
+        let total = scored.len();
+        scored.sort_by(|(a, a_task), (b, b_task)| {
+            // Newest first among equal scores
+            b.total_cmp(a)
+                .then_with(|| task_key(b_task).cmp(&task_key(a_task)))
+        });
+        scored.truncate(limit);
+
+        SearchResults {
+            hits: scored
+                .into_iter()
+                .map(|(score, task)| SearchHit {
+                    task: task.clone(),
+                    score,
+                    highlights: field_texts(task)
+                        .into_iter()
+                        .filter_map(|(field, _, text)| highlight(field, &text, &terms))
+                        .collect(),
+                })
+                .collect(),
+            total,
+        }
+    }
+}
+
+// The part of `text` around its first match with every match marked
+fn highlight(field: &str, text: &str, terms: &HashSet<String>) -> Option<Highlight> {
+    let mut matches: Vec<Range<usize>> = tokens(text)
+        .into_iter()
+        .filter(|(term, _)| terms.contains(term))
+        .map(|(_, range)| range)
+        .collect();
+    // Longest first where matches start together, so a whole word wins over its parts
+    matches.sort_by_key(|range| (range.start, std::cmp::Reverse(range.end)));
+    let first = matches.first()?.start;
+
+    let (mut start, mut end) = (0, text.len());
+    if text.len() > SNIPPET_BYTES {
+        start = first.saturating_sub(SNIPPET_LEAD_BYTES);
+        while !text.is_char_boundary(start) {
+            start -= 1;
+        }
+        end = (start + SNIPPET_BYTES).min(text.len());
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+    }
+
+    let mut fragments = Vec::new();
+    let mut push = |range: Range<usize>, matched: bool| {
+        if range.is_empty() {
+            return;
+        }
+        let text = &text[range];
+        match fragments.last_mut() {
+            Some(Fragment {
+                text: last,
+                matched: last_matched,
+            }) if *last_matched == matched => last.push_str(text),
+            _ => fragments.push(Fragment {
+                text: text.to_string(),
+                matched,
+            }),
+        }
+    };
+    let mut at = start;
+    for range in matches {
+        // Parts of an already marked compound token
+        let from = range.start.max(at);
+        let to = range.end.min(end);
+        if from >= to {
+            continue;
+        }
+        push(at..from, false);
+        push(from..to, true);
+        at = to;
+    }
+    push(at..end, false);
+
+    let cut = |matched: bool| Fragment {
+        text: "…".to_string(),
+        matched,
+    };
+    if start > 0 {
+        match fragments.first_mut() {
+            Some(first) if !first.matched => first.text.insert(0, '…'),
+            _ => fragments.insert(0, cut(false)),
+        }
+    }
+    if end < text.len() {
+        match fragments.last_mut() {
+            Some(last) if !last.matched => last.text.push('…'),
+            _ => fragments.push(cut(false)),
+        }
+    }
+    Some(Highlight {
+        field: field.to_string(),
+        fragments,
+    })
+}
@@ -0,0 +1,403 @@
+use crate::index::{task_key, TaskFilter, TaskIndex};
+use crate::search::TextIndex;
+use crate::{shard_for, TaskManagerActor, TaskMetadata, Visibility};
+use arc_swap::ArcSwap;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+// How often a manager publishes its tasks when they changed, so how stale
+// a snapshot can be
+pub const DEFAULT_SNAPSHOT_INTERVAL: Duration = Duration::from_millis(50);
+
+// The manager's tasks. Reads go straight to the map, writes note the id so
+// the next snapshot only copies the tasks that changed, and keep the
+// per-namespace counts up to date.
+#[derive(Debug, Default)]
+pub(crate) struct TaskTable {
+    tasks: HashMap<Uuid, TaskMetadata>,
+    changed: HashSet<Uuid>,
+    namespaces: HashMap<String, NamespaceTasks>,
+}
+
+// What namespace limits and quotas are checked against, kept as tasks
+// change instead of scanning every task
+#[derive(Debug, Default)]
+pub(crate) struct NamespaceTasks {
+    pub(crate) total: usize,
+    // Queued and in progress
+    pub(crate) active: HashSet<Uuid>,
+    // Runtime of finished runs by finish time
+    pub(crate) finished: BTreeMap<(DateTime<Utc>, Uuid), Duration>,
+}
+
+impl NamespaceTasks {
+    fn update(&mut self, task: &TaskMetadata, added: bool) {
+        if added {
+            self.total += 1;
+        } else {
+            self.total -= 1;
+        }
+        if task.status.is_active() {
+            if added {
+                self.active.insert(task.id);
+            } else {
+                self.active.remove(&task.id);
+            }
+        }
+        if let (false, Some(finished_at), Some(duration_ms)) = (
+            task.status.is_active(),
+            task.finished_at,
+            task.actual_duration_ms,
+        ) {
+            let key = (finished_at, task.id);
+            if added {
+                self.finished
+                    .insert(key, Duration::from_millis(duration_ms));
+            } else {
+                self.finished.remove(&key);
+            }
+        }
+    }
+}
+
+fn count(namespaces: &mut HashMap<String, NamespaceTasks>, task: &TaskMetadata, added: bool) {
+    namespaces
+        .entry(task.namespace.clone())
+        .or_default()
+        .update(task, added);
+}
+
+impl Deref for TaskTable {
+    type Target = HashMap<Uuid, TaskMetadata>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tasks
+    }
+}
+
+impl TaskTable {
+    // The task is counted again when the returned entry is dropped
+    pub(crate) fn get_mut(&mut self, id: &Uuid) -> Option<TaskEntry<'_>> {
+        let task = self.tasks.get(id)?;
+        count(&mut self.namespaces, task, false);
+        self.changed.insert(*id);
+        Some(TaskEntry {
+            table: self,
+            id: *id,
+        })
+    }
+
+    pub(crate) fn insert(&mut self, id: Uuid, task: TaskMetadata) -> Option<TaskMetadata> {
+        self.changed.insert(id);
+        count(&mut self.namespaces, &task, true);
+        let previous = self.tasks.insert(id, task);
+        if let Some(previous) = &previous {
+            count(&mut self.namespaces, previous, false);
+        }
+        previous
+    }
+
+    pub(crate) fn remove(&mut self, id: &Uuid) -> Option<TaskMetadata> {
+        self.changed.insert(*id);
+        let removed = self.tasks.remove(id);
+        if let Some(removed) = &removed {
+            count(&mut self.namespaces, removed, false);
+        }
+        removed
+    }
+
+    pub(crate) fn namespace(&self, name: &str) -> Option<&NamespaceTasks> {
+        self.namespaces.get(name)
+    }
+}
+
+// A task being changed in place, see TaskTable::get_mut
+pub(crate) struct TaskEntry<'a> {
+    table: &'a mut TaskTable,
+    id: Uuid,
+}
+
+impl Deref for TaskEntry<'_> {
+    type Target = TaskMetadata;
+
+    fn deref(&self) -> &TaskMetadata {
+        &self.table.tasks[&self.id]
+    }
+}
+
+impl DerefMut for TaskEntry<'_> {
+    fn deref_mut(&mut self) -> &mut TaskMetadata {
+        self.table
+            .tasks
+            .get_mut(&self.id)
+            .expect("entry of a stored task")
+    }
+}
+
+impl Drop for TaskEntry<'_> {
+    fn drop(&mut self) {
+        let TaskTable {
+            tasks, namespaces, ..
+        } = &mut *self.table;
+        if let Some(task) = tasks.get(&self.id) {
+            count(namespaces, task, true);
+        }
+    }
+}
+
+// Immutable copy of one manager's tasks as of `version`
+#[derive(Debug, Default)]
+pub struct TaskSnapshot {
+    // Goes up by one with every snapshot the manager publishes
+    pub version: u64,
+    tasks: im::HashMap<Uuid, Arc<TaskMetadata>>,
+    index: TaskIndex,
+    text: TextIndex,
+    // Namespaces whose tasks unscoped reads leave out
+    private: BTreeSet<String>,
+    // How long finished tasks stay, None when they are never archived
+    retention: Option<Duration>,
+    // Namespaces with their own retention
+    namespace_retention: BTreeMap<String, Duration>,
+}
+
+impl TaskSnapshot {
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub fn get(&self, id: &Uuid) -> Option<&TaskMetadata> {
+        self.tasks.get(id).map(Arc::as_ref)
+    }
+
+    pub fn tasks(&self) -> impl Iterator<Item = &TaskMetadata> {
+        self.tasks.values().map(Arc::as_ref)
+    }
+
+    // Tasks matching `filter` visible in `scope`, oldest first
+    pub fn select<'a>(
+        &'a self,
+        filter: &'a TaskFilter,
+        scope: Option<&'a str>,
+    ) -> impl DoubleEndedIterator<Item = &'a TaskMetadata> + 'a {
+        self.index
+            .candidates(filter)
+            .filter_map(|(_, id)| self.get(id))
+            .filter(move |task| filter.matches(task) && self.is_visible(task, scope))
+    }
+
+    pub(crate) fn text_index(&self) -> &TextIndex {
+        &self.text
+    }
+
+    // Same rule as GetNamespaceTasks
+    pub fn is_visible(&self, task: &TaskMetadata, scope: Option<&str>) -> bool {
+        match scope {
+            Some(name) => task.namespace == name,
+            None => !self.private.contains(&task.namespace),
+        }
+    }
+
+    // How long the finished tasks visible in `scope` are kept at least,
+    // None when they are kept until the manager stops
+    pub fn retention(&self, scope: Option<&str>) -> Option<Duration> {
+        match scope {
+            Some(name) => self
+                .namespace_retention
+                .get(name)
+                .copied()
+                .or(self.retention),
+            None => self
+                .namespace_retention
+                .iter()
+                .filter(|(name, _)| !self.private.contains(*name))
+                .map(|(_, retention)| *retention)
+                .chain(self.retention)
+                .min(),
+        }
+    }
+}
+
+// Where a manager publishes its snapshots. Loading one never waits on the
+// manager or on other readers.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotCell(Arc<ArcSwap<TaskSnapshot>>);
+
+impl SnapshotCell {
+    pub fn load(&self) -> Arc<TaskSnapshot> {
+        self.0.load_full()
+    }
+}
+
+// Answers task queries from the latest snapshots of a manager, or of every
+// shard behind a TaskManagerRouter, without messaging them. What it returns
+// may be up to a snapshot interval behind the managers.
+#[derive(Debug, Clone)]
+pub struct TaskReader {
+    // Indexed by shard, see shard_for
+    cells: Arc<[SnapshotCell]>,
+}
+
+impl TaskReader {
+    pub fn new(cells: Vec<SnapshotCell>) -> Self {
+        Self {
+            cells: cells.into(),
+        }
+    }
+
+    // Changes whenever any of the managers publishes
+    pub fn version(&self) -> u64 {
+        self.cells.iter().map(|cell| cell.load().version).sum()
+    }
+
+    pub fn task(&self, id: &Uuid) -> Option<TaskMetadata> {
+        self.shard_snapshot(id).get(id).cloned()
+    }
+
+    pub fn namespace_task(&self, id: &Uuid, scope: Option<&str>) -> Option<TaskMetadata> {
+        let snapshot = self.shard_snapshot(id);
+        snapshot
+            .get(id)
+            .filter(|task| snapshot.is_visible(task, scope))
+            .cloned()
+    }
+
+    pub fn all_tasks(&self) -> Vec<TaskMetadata> {
+        self.collect(|_, _| true)
+    }
+
+    pub fn namespace_tasks(&self, scope: Option<&str>) -> Vec<TaskMetadata> {
+        self.collect(|snapshot, task| snapshot.is_visible(task, scope))
+    }
+
+    // Tasks of every shard matching `filter` visible in `scope`, oldest first
+    pub fn select(&self, filter: &TaskFilter, scope: Option<&str>) -> Vec<TaskMetadata> {
+        let mut tasks: Vec<TaskMetadata> = self
+            .cells
+            .iter()
+            .flat_map(|cell| {
+                cell.load()
+                    .select(filter, scope)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        if self.cells.len() > 1 {
+            tasks.sort_by_key(task_key);
+        }
+        tasks
+    }
+
+    // Namespaces whose tasks unscoped reads leave out, for queries that
+    // are not answered from the snapshots such as the archive
+    pub fn private_namespaces(&self) -> BTreeSet<String> {
+        self.cells
+            .iter()
+            .flat_map(|cell| cell.load().private.clone())
+            .collect()
+    }
+
+    // The latest snapshot of every shard, loaded once so a query sees each
+    // shard as of a single version
+    pub(crate) fn snapshots(&self) -> Vec<Arc<TaskSnapshot>> {
+        self.cells.iter().map(SnapshotCell::load).collect()
+    }
+
+    fn shard_snapshot(&self, id: &Uuid) -> Arc<TaskSnapshot> {
+        self.cells[shard_for(id, self.cells.len())].load()
+    }
+
+    fn collect(&self, keep: impl Fn(&TaskSnapshot, &TaskMetadata) -> bool) -> Vec<TaskMetadata> {
+        let snapshots = self.snapshots();
+        let mut tasks = Vec::with_capacity(snapshots.iter().map(|snapshot| snapshot.len()).sum());
+        for snapshot in &snapshots {
+            tasks.extend(
+                snapshot
+                    .tasks()
+                    .filter(|task| keep(snapshot, task))
+                    .cloned(),
+            );
+        }
+        tasks
+    }
+}
+
+impl TaskManagerActor {
+    // Reads this manager's snapshots, take it before starting the actor
+    pub fn reader(&self) -> TaskReader {
+        TaskReader::new(vec![self.snapshot.clone()])
+    }
+
+    // Publishes the tasks changed since the last snapshot. Unchanged tasks
+    // and index entries are shared with the previous one, not copied again.
+    pub(crate) fn publish_snapshot(&mut self) {
+        let private: BTreeSet<String> = self
+            .namespaces
+            .iter()
+            .filter(|(_, namespace)| namespace.config.visibility == Visibility::Private)
+            .map(|(name, _)| name.clone())
+            .collect();
+        let retention = self.archive.is_some().then_some(self.retention);
+        let namespace_retention: BTreeMap<String, Duration> = self
+            .namespaces
+            .keys()
+            .filter_map(|name| Some((name.clone(), self.namespace_retention(name)?)))
+            .collect();
+        let current = self.snapshot.load();
+        if self.task_metadata.changed.is_empty()
+            && current.private == private
+            && current.retention == retention
+            && current.namespace_retention == namespace_retention
+        {
+            return;
+        }
+
+        let mut tasks = current.tasks.clone();
+        let mut index = current.index.clone();
+        let mut text = current.text.clone();
+        for id in std::mem::take(&mut self.task_metadata.changed) {
+            let old = tasks.get(&id).cloned();
+            let new = self.task_metadata.tasks.get(&id);
+            if let Some(old) = &old {
+                index.remove(old);
+                if new.is_none_or(|new| !TextIndex::same_text(old, new)) {
+                    text.remove(old);
+                }
+            }
+            match new {
+                Some(task) => {
+                    self.metrics.record_task(old.as_deref(), task);
+                    // The task's span closes once its outcome is published
+                    if !task.status.is_active() {
+                        self.spans.remove(&id);
+                    }
+                    index.insert(task);
+                    if old.is_none_or(|old| !TextIndex::same_text(&old, task)) {
+                        text.insert(task);
+                    }
+                    tasks.insert(id, Arc::new(task.clone()));
+                }
+                None => {
+                    tasks.remove(&id);
+                }
+            }
+        }
+        self.snapshot.0.store(Arc::new(TaskSnapshot {
+            version: current.version + 1,
+            tasks,
+            index,
+            text,
+            private,
+            retention,
+            namespace_retention,
+        }));
+    }
+}
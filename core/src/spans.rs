@@ -0,0 +1,79 @@
+use crate::{otel, CreateTask, TaskManagerActor, TaskMetadata, TaskStatus};
+use tracing::{info, info_span, warn, Span};
+use uuid::Uuid;
+
+// Every task has one span from its creation until the manager publishes
+// its final status, across all its attempts. Whatever runs the task logs
+// into it, so each line carries the task's id, type and namespace. A task
+// created with a traceparent continues that trace.
+pub(crate) fn task_span(id: Uuid, spec: &CreateTask) -> Span {
+    let name = if spec.name.is_empty() {
+        spec.task_type.get_name()
+    } else {
+        spec.name.clone()
+    };
+    let span = info_span!(
+        "task",
+        task.id = %id,
+        task.name = %name,
+        "task.type" = spec.type_name(),
+        task.namespace = spec.namespace(),
+    );
+    if let Some(traceparent) = &spec.traceparent {
+        otel::continue_trace(&span, traceparent);
+    }
+    span
+}
+
+// How a task ended, logged the same way whichever executor ran it
+pub(crate) fn record_outcome(span: &Span, task: &TaskMetadata) {
+    let duration_ms = task.actual_duration_ms;
+    match task.status {
+        TaskStatus::Completed => info!(
+            parent: span,
+            duration_ms,
+            attempt = task.attempt,
+            "task completed"
+        ),
+        TaskStatus::Error if task.was_cancelled() => {
+            info!(parent: span, duration_ms, "task cancelled")
+        }
+        TaskStatus::Error if task.was_timeout() => warn!(
+            parent: span,
+            duration_ms,
+            timeout_ms = task.timeout_ms,
+            "task timed out"
+        ),
+        TaskStatus::Error => warn!(
+            parent: span,
+            duration_ms,
+            attempt = task.attempt,
+            error = task.error.as_deref(),
+            "task failed"
+        ),
+        TaskStatus::Queued | TaskStatus::InProgress => {}
+    }
+}
+
+impl TaskManagerActor {
+    // The span of a task that has not finished yet, a disabled one for
+    // any other id
+    pub(crate) fn span_of(&self, id: &Uuid) -> Span {
+        self.spans.get(id).cloned().unwrap_or_else(Span::none)
+    }
+
+    // Opened when the task is created, retries find it still open
+    pub(crate) fn open_span(&mut self, id: Uuid, spec: &CreateTask) -> Span {
+        self.spans
+            .entry(id)
+            .or_insert_with(|| task_span(id, spec))
+            .clone()
+    }
+
+    // Logs how the task ended in its span
+    pub(crate) fn record_outcome(&self, id: &Uuid) {
+        if let Some(task) = self.task_metadata.get(id) {
+            record_outcome(&self.span_of(id), task);
+        }
+    }
+}
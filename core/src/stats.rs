@@ -0,0 +1,205 @@
+use crate::{TaskMetadata, TaskReader, TaskStatus};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+// Rolling windows reported besides all tasks, a task falls in a window when
+// it started within it. Those longer than the retention are left out.
+pub const STATS_WINDOWS: [(&str, Duration); 3] = [
+    ("1m", Duration::from_secs(60)),
+    ("5m", Duration::from_secs(5 * 60)),
+    ("15m", Duration::from_secs(15 * 60)),
+];
+
+pub const ALL_TASKS_WINDOW: &str = "all";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct StatusCounts {
+    pub queued: usize,
+    pub in_progress: usize,
+    pub completed: usize,
+    pub error: usize,
+}
+
+impl StatusCounts {
+    fn add(&mut self, status: &TaskStatus) {
+        match status {
+            TaskStatus::Queued => self.queued += 1,
+            TaskStatus::InProgress => self.in_progress += 1,
+            TaskStatus::Completed => self.completed += 1,
+            TaskStatus::Error => self.error += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.queued + self.in_progress + self.completed + self.error
+    }
+
+    pub fn finished(&self) -> usize {
+        self.completed + self.error
+    }
+}
+
+// Nearest-rank percentiles of actual_duration_ms over finished tasks
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct DurationPercentiles {
+    pub p50: Option<u64>,
+    pub p90: Option<u64>,
+    pub p99: Option<u64>,
+}
+
+impl DurationPercentiles {
+    fn of(durations: &mut [u64]) -> Self {
+        durations.sort_unstable();
+        let at = |percentile: usize| {
+            let rank = (percentile * durations.len()).div_ceil(100);
+            durations.get(rank.max(1) - 1).copied()
+        };
+        Self {
+            p50: at(50),
+            p90: at(90),
+            p99: at(99),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct StatsSummary {
+    pub total: usize,
+    pub by_status: StatusCounts,
+    // Fractions of the finished tasks, None until one finishes
+    pub success_rate: Option<f64>,
+    pub timeout_rate: Option<f64>,
+    pub duration_ms: DurationPercentiles,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct WindowStats {
+    // ALL_TASKS_WINDOW or one of STATS_WINDOWS
+    pub window: String,
+    pub overall: StatsSummary,
+    // By registered task type name
+    pub by_type: BTreeMap<String, StatsSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskStats {
+    pub generated_at: DateTime<Utc>,
+    // All tasks in memory first, then STATS_WINDOWS in order
+    pub windows: Vec<WindowStats>,
+}
+
+impl TaskStats {
+    pub fn window(&self, window: &str) -> Option<&WindowStats> {
+        self.windows.iter().find(|stats| stats.window == window)
+    }
+}
+
+// What one bucket of tasks adds up to before percentiles are taken
+#[derive(Debug, Clone, Default)]
+struct StatsBucket {
+    counts: StatusCounts,
+    timeouts: usize,
+    durations: Vec<u64>,
+}
+
+impl StatsBucket {
+    fn add(&mut self, task: &TaskMetadata) {
+        self.counts.add(&task.status);
+        if task.was_timeout() {
+            self.timeouts += 1;
+        }
+        if !task.status.is_active() {
+            self.durations.extend(task.actual_duration_ms);
+        }
+    }
+
+    fn summarize(mut self) -> StatsSummary {
+        let finished = self.counts.finished();
+        let rate = |count: usize| (finished > 0).then(|| count as f64 / finished as f64);
+        StatsSummary {
+            total: self.counts.total(),
+            by_status: self.counts,
+            success_rate: rate(self.counts.completed),
+            timeout_rate: rate(self.timeouts),
+            duration_ms: DurationPercentiles::of(&mut self.durations),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct WindowSample {
+    overall: StatsBucket,
+    by_type: BTreeMap<String, StatsBucket>,
+}
+
+// Tasks are only counted while they are in memory, so windows longer than
+// `retention` would miss the archived ones and are left out
+fn task_stats<'a>(
+    tasks: impl Iterator<Item = &'a TaskMetadata>,
+    now: DateTime<Utc>,
+    retention: Option<Duration>,
+) -> TaskStats {
+    let windows: Vec<(&str, Option<DateTime<Utc>>)> = std::iter::once((ALL_TASKS_WINDOW, None))
+        .chain(
+            STATS_WINDOWS
+                .iter()
+                .filter(|(_, window)| retention.is_none_or(|retention| *window <= retention))
+                .map(|(name, window)| {
+                    let start = chrono::Duration::from_std(*window)
+                        .ok()
+                        .map(|window| now - window);
+                    (*name, start)
+                }),
+        )
+        .collect();
+    let mut samples = vec![WindowSample::default(); windows.len()];
+    for task in tasks {
+        for (sample, (_, start)) in samples.iter_mut().zip(&windows) {
+            if start.is_some_and(|start| task.started_at < start) {
+                continue;
+            }
+            sample.overall.add(task);
+            sample
+                .by_type
+                .entry(task.task_type.clone())
+                .or_default()
+                .add(task);
+        }
+    }
+    TaskStats {
+        generated_at: now,
+        windows: windows
+            .into_iter()
+            .zip(samples)
+            .map(|((name, _), sample)| WindowStats {
+                window: name.to_string(),
+                overall: sample.overall.summarize(),
+                by_type: sample
+                    .by_type
+                    .into_iter()
+                    .map(|(task_type, bucket)| (task_type, bucket.summarize()))
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+impl TaskReader {
+    // Statistics over every shard's latest snapshot as of `now`, without
+    // messaging the managers
+    pub fn stats(&self, scope: Option<&str>, now: DateTime<Utc>) -> TaskStats {
+        let snapshots = self.snapshots();
+        let retention = snapshots
+            .iter()
+            .filter_map(|snapshot| snapshot.retention(scope))
+            .min();
+        let tasks = snapshots.iter().flat_map(|snapshot| {
+            snapshot
+                .tasks()
+                .filter(move |task| snapshot.is_visible(task, scope))
+        });
+        task_stats(tasks, now, retention)
+    }
+}
@@ -0,0 +1,387 @@
+use crate::{ApiError, DurationDistribution, ErrorType, TaskType};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+// Type-specific parameters as they arrive from the REST API, forms and LiveView
+pub type TaskParams = serde_json::Map<String, serde_json::Value>;
+
+pub type TaskParamValidator = dyn Fn(&TaskParams) -> Result<(), ApiError> + Send + Sync;
+pub type TaskTypeExecutor = dyn Fn(&TaskParams) -> TaskType + Send + Sync;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ParamKind {
+    Integer,
+    Number,
+    String,
+    Choice(Vec<String>),
+    Object,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParamSchema {
+    pub name: String,
+    pub kind: ParamKind,
+    pub required: bool,
+    pub description: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl ParamSchema {
+    pub fn new(name: &str, kind: ParamKind, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            required: false,
+            description: description.to_string(),
+            min: None,
+            max: None,
+        }
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    fn check(&self, value: Option<&serde_json::Value>) -> Result<(), ApiError> {
+        let field = format!("task_type.{}", self.name);
+
+        let value = match value {
+            Some(serde_json::Value::Null) | None if self.required => {
+                return Err(ApiError::validation_error(
+                    format!("Parameter '{}' is required", self.name),
+                    Some(serde_json::json!({ "field": field })),
+                ));
+            }
+            Some(serde_json::Value::Null) | None => return Ok(()),
+            Some(value) => value,
+        };
+
+        let number = match &self.kind {
+            ParamKind::Integer => value.as_u64().map(|n| n as f64),
+            ParamKind::Number => value.as_f64(),
+            ParamKind::String => value.as_str().map(|_| 0.0),
+            ParamKind::Object => value.as_object().map(|_| 0.0),
+            ParamKind::Choice(choices) => value
+                .as_str()
+                .filter(|choice| choices.iter().any(|c| c == choice))
+                .map(|_| 0.0),
+        };
+
+        let Some(number) = number else {
+            return Err(ApiError::validation_error(
+                format!("Parameter '{}' has an invalid value", self.name),
+                Some(serde_json::json!({
+                    "field": field,
+                    "provided_value": value,
+                    "expected": self.kind
+                })),
+            ));
+        };
+
+        let below = self.min.is_some_and(|min| number < min);
+        let above = self.max.is_some_and(|max| number > max);
+        if below || above {
+            return Err(ApiError::validation_error(
+                format!(
+                    "Parameter '{}' must be between {} and {}",
+                    self.name,
+                    self.min.unwrap_or(f64::MIN),
+                    self.max.unwrap_or(f64::MAX)
+                ),
+                Some(serde_json::json!({
+                    "field": field,
+                    "provided_value": value,
+                    "min_value": self.min,
+                    "max_value": self.max
+                })),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// A task type as registered at runtime: its parameter schema, an optional
+// cross-field validator, and the executor that turns parameters into the
+// TaskType the manager runs
+#[derive(Clone)]
+pub struct TaskTypeDefinition {
+    pub name: String,
+    pub display_name: String,
+    pub schema: Vec<ParamSchema>,
+    validator: Option<Arc<TaskParamValidator>>,
+    executor: Arc<TaskTypeExecutor>,
+}
+
+impl fmt::Debug for TaskTypeDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskTypeDefinition")
+            .field("name", &self.name)
+            .field("display_name", &self.display_name)
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl TaskTypeDefinition {
+    pub fn new(
+        name: &str,
+        display_name: &str,
+        executor: impl Fn(&TaskParams) -> TaskType + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            display_name: display_name.to_string(),
+            schema: Vec::new(),
+            validator: None,
+            executor: Arc::new(executor),
+        }
+    }
+
+    pub fn with_param(mut self, param: ParamSchema) -> Self {
+        self.schema.push(param);
+        self
+    }
+
+    pub fn with_validator(
+        mut self,
+        validator: impl Fn(&TaskParams) -> Result<(), ApiError> + Send + Sync + 'static,
+    ) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
+    pub fn has_param(&self, name: &str) -> bool {
+        self.schema.iter().any(|param| param.name == name)
+    }
+
+    pub fn validate(&self, params: &TaskParams) -> Result<(), ApiError> {
+        for param in &self.schema {
+            param.check(params.get(&param.name))?;
+        }
+
+        match &self.validator {
+            Some(validator) => validator(params),
+            None => Ok(()),
+        }
+    }
+
+    pub fn build(&self, params: &TaskParams) -> Result<TaskType, ApiError> {
+        self.validate(params)?;
+        Ok((self.executor)(params))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskTypeInfo {
+    pub name: String,
+    pub display_name: String,
+    pub schema: Vec<ParamSchema>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TaskTypeRegistry {
+    types: Vec<TaskTypeDefinition>,
+}
+
+impl TaskTypeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Registry preloaded with the quick, long, error and custom types
+    pub fn with_builtin_types() -> Self {
+        let mut registry = Self::new();
+
+        registry.register(
+            TaskTypeDefinition::new("quick", "Quick Task", |params| TaskType::Quick {
+                timeout_ms: param_u64(params, "timeout_ms"),
+                duration: param_duration(params),
+            })
+            .with_param(timeout_param())
+            .with_param(duration_param())
+            .with_validator(validate_duration),
+        );
+
+        registry.register(
+            TaskTypeDefinition::new("long", "Long Task", |params| TaskType::Long {
+                timeout_ms: param_u64(params, "timeout_ms"),
+                duration: param_duration(params),
+            })
+            .with_param(timeout_param())
+            .with_param(duration_param())
+            .with_validator(validate_duration),
+        );
+
+        registry.register(
+            TaskTypeDefinition::new("error", "Error Task", |params| TaskType::Error {
+                timeout_ms: param_u64(params, "timeout_ms"),
+                error_type: match param_str(params, "error_type") {
+                    Some("immediate") => ErrorType::Immediate,
+                    Some("timeout") => ErrorType::Timeout,
+                    Some("network") => ErrorType::NetworkError,
+                    Some("validation") => ErrorType::ValidationError,
+                    _ => ErrorType::Random,
+                },
+            })
+            .with_param(timeout_param())
+            .with_param(ParamSchema::new(
+                "error_type",
+                ParamKind::Choice(
+                    ["immediate", "timeout", "random", "network", "validation"]
+                        .iter()
+                        .map(|s| s.to_string())
+                        .collect(),
+                ),
+                "How the task fails, defaults to random",
+            )),
+        );
+
+        registry.register(
+            TaskTypeDefinition::new("custom", "Custom Task", |params| TaskType::Custom {
+                name: param_str(params, "custom_name")
+                    .filter(|name| !name.trim().is_empty())
+                    .unwrap_or("Custom Task")
+                    .to_string(),
+                timeout_ms: param_u64(params, "timeout_ms").unwrap_or(5000),
+                failure_rate: params
+                    .get("failure_rate")
+                    .and_then(|rate| rate.as_f64())
+                    .map(|rate| rate as f32),
+                duration: param_duration(params),
+            })
+            .with_param(ParamSchema::new(
+                "custom_name",
+                ParamKind::String,
+                "Name shown for the task when none is given",
+            ))
+            .with_param(timeout_param())
+            .with_param(
+                ParamSchema::new(
+                    "failure_rate",
+                    ParamKind::Number,
+                    "Probability of failure, 0.0 = never fails, 1.0 = always fails",
+                )
+                .range(0.0, 1.0),
+            )
+            .with_param(duration_param())
+            .with_validator(|params| match param_str(params, "custom_name") {
+                Some(name) if name.len() > 100 => Err(ApiError::validation_error(
+                    "Custom task name cannot exceed 100 characters".to_string(),
+                    Some(serde_json::json!({
+                        "field": "task_type.custom_name",
+                        "provided_length": name.len(),
+                        "max_length": 100
+                    })),
+                )),
+                _ => validate_duration(params),
+            }),
+        );
+
+        registry
+    }
+
+    // Registering an existing name replaces the earlier definition
+    pub fn register(&mut self, definition: TaskTypeDefinition) {
+        match self.types.iter().position(|t| t.name == definition.name) {
+            Some(index) => self.types[index] = definition,
+            None => self.types.push(definition),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TaskTypeDefinition> {
+        self.types.iter().find(|t| t.name == name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.types.iter().map(|t| t.name.clone()).collect()
+    }
+
+    pub fn describe(&self) -> Vec<TaskTypeInfo> {
+        self.types
+            .iter()
+            .map(|t| TaskTypeInfo {
+                name: t.name.clone(),
+                display_name: t.display_name.clone(),
+                schema: t.schema.clone(),
+            })
+            .collect()
+    }
+
+    pub fn definition(&self, name: &str) -> Result<&TaskTypeDefinition, ApiError> {
+        self.get(name)
+            .ok_or_else(|| ApiError::invalid_task_type(name, &self.names()))
+    }
+
+    // The registered name matching `name` regardless of case, for filters
+    // and per-type settings keyed by task type
+    pub fn canonical_name(&self, name: &str) -> Result<String, ApiError> {
+        self.types
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+            .map(|t| t.name.clone())
+            .ok_or_else(|| ApiError::invalid_task_type(name, &self.names()))
+    }
+
+    pub fn resolve(&self, name: &str, params: &TaskParams) -> Result<TaskType, ApiError> {
+        self.definition(name)?.build(params)
+    }
+}
+
+fn timeout_param() -> ParamSchema {
+    ParamSchema::new(
+        "timeout_ms",
+        ParamKind::Integer,
+        "Timeout in milliseconds, maximum 5 minutes",
+    )
+    .range(100.0, 300000.0)
+}
+
+fn duration_param() -> ParamSchema {
+    ParamSchema::new(
+        "duration",
+        ParamKind::Object,
+        "Work duration distribution: constant, uniform, normal, log_normal or pareto",
+    )
+}
+
+fn validate_duration(params: &TaskParams) -> Result<(), ApiError> {
+    let Some(value) = params.get("duration").filter(|value| !value.is_null()) else {
+        return Ok(());
+    };
+
+    match serde_json::from_value::<DurationDistribution>(value.clone()) {
+        Ok(distribution) => distribution.validate(),
+        Err(e) => Err(ApiError::validation_error(
+            format!("Invalid duration distribution: {}", e),
+            Some(serde_json::json!({
+                "field": "task_type.duration",
+                "provided_value": value
+            })),
+        )),
+    }
+}
+
+// Only called after validate_duration has accepted the value
+fn param_duration(params: &TaskParams) -> Option<DurationDistribution> {
+    params
+        .get("duration")
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+}
+
+fn param_u64(params: &TaskParams, name: &str) -> Option<u64> {
+    params.get(name).and_then(|value| value.as_u64())
+}
+
+fn param_str<'a>(params: &'a TaskParams, name: &str) -> Option<&'a str> {
+    params.get(name).and_then(|value| value.as_str())
+}
@@ -0,0 +1,392 @@
+use crate::{ApiError, CreateTask, TaskParams, TaskTypeDefinition, TaskTypeRegistry};
+use actix::{Actor, Context, Handler, Message};
+use serde::{Deserialize, Serialize};
+
+const MAX_TEMPLATE_TAGS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskTemplate {
+    // Unique key, used in URLs and LiveView messages
+    pub name: String,
+    pub display_name: String,
+    #[serde(default)]
+    pub description: String,
+    // Name of a type in the TaskTypeRegistry
+    pub task_type: String,
+    pub default_message: String,
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub failure_rate: Option<f32>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Extra type-specific parameters, e.g. error_type for error tasks
+    #[serde(default)]
+    pub params: TaskParams,
+}
+
+// Per-call values that take precedence over the template defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TemplateOverrides {
+    pub name: Option<String>,
+    pub message: Option<String>,
+    pub timeout_ms: Option<u64>,
+    pub failure_rate: Option<f32>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl TaskTemplate {
+    // The presets the LiveView used to hard-code
+    pub fn builtin() -> Vec<TaskTemplate> {
+        vec![
+            TaskTemplate {
+                name: "quick".to_string(),
+                display_name: "Quick Task".to_string(),
+                description: "A fast task that completes in approximately 2 seconds".to_string(),
+                task_type: "quick".to_string(),
+                default_message: "Quick task message (completes in ~2s)".to_string(),
+                timeout_ms: 2000,
+                failure_rate: None,
+                tags: vec!["builtin".to_string()],
+                params: TaskParams::new(),
+            },
+            TaskTemplate {
+                name: "long".to_string(),
+                display_name: "Long Task".to_string(),
+                description: "A longer task that takes approximately 10 seconds to complete"
+                    .to_string(),
+                task_type: "long".to_string(),
+                default_message: "Long task message (completes in ~10s)".to_string(),
+                timeout_ms: 10000,
+                failure_rate: None,
+                tags: vec!["builtin".to_string()],
+                params: TaskParams::new(),
+            },
+            TaskTemplate {
+                name: "error".to_string(),
+                display_name: "Error Task".to_string(),
+                description: "A task designed to fail for testing error handling".to_string(),
+                task_type: "error".to_string(),
+                default_message: "Error task message (will fail for testing)".to_string(),
+                timeout_ms: 5000,
+                failure_rate: None,
+                tags: vec!["builtin".to_string()],
+                params: serde_json::json!({ "error_type": "random" })
+                    .as_object()
+                    .cloned()
+                    .unwrap_or_default(),
+            },
+            TaskTemplate {
+                name: "custom".to_string(),
+                display_name: "Custom Task".to_string(),
+                description: "A fully customizable task with configurable timeout and failure rate"
+                    .to_string(),
+                task_type: "custom".to_string(),
+                default_message: "Custom task message".to_string(),
+                timeout_ms: 5000,
+                failure_rate: Some(0.0),
+                tags: vec!["builtin".to_string()],
+                params: TaskParams::new(),
+            },
+        ]
+    }
+
+    pub fn validate(&self, registry: &TaskTypeRegistry) -> Result<(), ApiError> {
+        if self.name.is_empty()
+            || self.name.len() > 50
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(ApiError::validation_error(
+                "Template name must be 1-50 characters of letters, digits, '-' or '_'".to_string(),
+                Some(serde_json::json!({
+                    "field": "name",
+                    "provided_value": self.name
+                })),
+            ));
+        }
+
+        if self.display_name.trim().is_empty() || self.display_name.len() > 100 {
+            return Err(ApiError::validation_error(
+                "Template display name must be 1-100 characters".to_string(),
+                Some(serde_json::json!({
+                    "field": "display_name",
+                    "provided_length": self.display_name.len(),
+                    "max_length": 100
+                })),
+            ));
+        }
+
+        let definition = registry.definition(&self.task_type)?;
+
+        if self.default_message.len() > 500 {
+            return Err(ApiError::validation_error(
+                "Template default message cannot exceed 500 characters".to_string(),
+                Some(serde_json::json!({
+                    "field": "default_message",
+                    "provided_length": self.default_message.len(),
+                    "max_length": 500
+                })),
+            ));
+        }
+
+        validate_tags("tags", &self.tags)?;
+
+        let params = self.task_params(definition, "", self.timeout_ms, self.failure_rate)?;
+        definition.validate(&params)
+    }
+
+    pub fn instantiate(
+        &self,
+        registry: &TaskTypeRegistry,
+        overrides: TemplateOverrides,
+    ) -> Result<CreateTask, ApiError> {
+        if let Some(tags) = &overrides.tags {
+            validate_tags("tags", tags)?;
+        }
+
+        let name = overrides
+            .name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_default();
+        let message = overrides
+            .message
+            .filter(|message| !message.trim().is_empty())
+            .unwrap_or_else(|| self.default_message.clone());
+        let timeout_ms = overrides.timeout_ms.unwrap_or(self.timeout_ms);
+        let failure_rate = overrides.failure_rate.or(self.failure_rate);
+
+        let definition = registry.definition(&self.task_type)?;
+        let params = self.task_params(definition, &name, timeout_ms, failure_rate)?;
+        let task_type = definition.build(&params)?;
+
+        Ok(CreateTask {
+            name,
+            message,
+            task_type,
+            type_name: Some(definition.name.clone()),
+            tags: overrides.tags.unwrap_or_else(|| self.tags.clone()),
+            ..Default::default()
+        })
+    }
+
+    // Merges the template's top-level fields into its params, only passing
+    // the ones the task type's schema actually declares
+    fn task_params(
+        &self,
+        definition: &TaskTypeDefinition,
+        name: &str,
+        timeout_ms: u64,
+        failure_rate: Option<f32>,
+    ) -> Result<TaskParams, ApiError> {
+        let mut params = self.params.clone();
+        params.insert("timeout_ms".to_string(), timeout_ms.into());
+
+        if definition.has_param("custom_name") {
+            let name = if name.is_empty() {
+                &self.display_name
+            } else {
+                name
+            };
+            params.insert("custom_name".to_string(), name.into());
+        }
+
+        match failure_rate {
+            Some(rate) if definition.has_param("failure_rate") => {
+                params.insert("failure_rate".to_string(), rate.into());
+            }
+            Some(rate) if rate != 0.0 => {
+                return Err(ApiError::validation_error(
+                    format!(
+                        "Task type '{}' does not support a failure rate",
+                        definition.name
+                    ),
+                    Some(serde_json::json!({
+                        "field": "failure_rate",
+                        "task_type": definition.name
+                    })),
+                ));
+            }
+            _ => {}
+        }
+
+        Ok(params)
+    }
+}
+
+fn validate_tags(field: &str, tags: &[String]) -> Result<(), ApiError> {
+    if tags.len() > MAX_TEMPLATE_TAGS
+        || tags
+            .iter()
+            .any(|tag| tag.trim().is_empty() || tag.len() > 50)
+    {
+        return Err(ApiError::validation_error(
+            "Tags must be at most 10 non-empty values of up to 50 characters".to_string(),
+            Some(serde_json::json!({
+                "field": field,
+                "provided_value": tags,
+                "max_tags": MAX_TEMPLATE_TAGS
+            })),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct TaskTemplateActor {
+    templates: Vec<TaskTemplate>,
+    registry: TaskTypeRegistry,
+}
+
+impl TaskTemplateActor {
+    pub fn new() -> Self {
+        Self {
+            templates: TaskTemplate::builtin(),
+            registry: TaskTypeRegistry::with_builtin_types(),
+        }
+    }
+
+    // Validate and instantiate templates against a registry with extra types
+    pub fn with_registry(mut self, registry: TaskTypeRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.templates.iter().position(|t| t.name == name)
+    }
+}
+
+impl Default for TaskTemplateActor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Actor for TaskTemplateActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _ctx: &mut Self::Context) {
+        tracing::info!(
+            templates = self.templates.len(),
+            "task template actor started"
+        );
+    }
+}
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Vec<TaskTemplate>")]
+pub struct ListTemplates;
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Option<TaskTemplate>")]
+pub struct GetTemplate {
+    pub name: String,
+}
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Result<TaskTemplate, ApiError>")]
+pub struct CreateTemplate {
+    pub template: TaskTemplate,
+}
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Result<TaskTemplate, ApiError>")]
+pub struct UpdateTemplate {
+    pub name: String,
+    pub template: TaskTemplate,
+}
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Result<TaskTemplate, ApiError>")]
+pub struct DeleteTemplate {
+    pub name: String,
+}
+
+// Resolves a template into a ready-to-send CreateTask
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Result<CreateTask, ApiError>")]
+pub struct InstantiateTemplate {
+    pub name: String,
+    pub overrides: TemplateOverrides,
+}
+
+impl Handler<ListTemplates> for TaskTemplateActor {
+    type Result = Vec<TaskTemplate>;
+
+    fn handle(&mut self, _msg: ListTemplates, _ctx: &mut Self::Context) -> Self::Result {
+        self.templates.clone()
+    }
+}
+
+impl Handler<GetTemplate> for TaskTemplateActor {
+    type Result = Option<TaskTemplate>;
+
+    fn handle(&mut self, msg: GetTemplate, _ctx: &mut Self::Context) -> Self::Result {
+        self.templates.iter().find(|t| t.name == msg.name).cloned()
+    }
+}
+
+impl Handler<CreateTemplate> for TaskTemplateActor {
+    type Result = Result<TaskTemplate, ApiError>;
+
+    fn handle(&mut self, msg: CreateTemplate, _ctx: &mut Self::Context) -> Self::Result {
+        msg.template.validate(&self.registry)?;
+
+        if self.position(&msg.template.name).is_some() {
+            return Err(ApiError::validation_error(
+                format!("Template '{}' already exists", msg.template.name),
+                Some(serde_json::json!({
+                    "field": "name",
+                    "provided_value": msg.template.name
+                })),
+            ));
+        }
+
+        self.templates.push(msg.template.clone());
+        Ok(msg.template)
+    }
+}
+
+impl Handler<UpdateTemplate> for TaskTemplateActor {
+    type Result = Result<TaskTemplate, ApiError>;
+
+    fn handle(&mut self, msg: UpdateTemplate, _ctx: &mut Self::Context) -> Self::Result {
+        let index = self
+            .position(&msg.name)
+            .ok_or_else(|| ApiError::not_found("Template", &msg.name))?;
+
+        // The URL names the template, the body can't rename it
+        let mut template = msg.template;
+        template.name = msg.name;
+        template.validate(&self.registry)?;
+
+        self.templates[index] = template.clone();
+        Ok(template)
+    }
+}
+
+impl Handler<DeleteTemplate> for TaskTemplateActor {
+    type Result = Result<TaskTemplate, ApiError>;
+
+    fn handle(&mut self, msg: DeleteTemplate, _ctx: &mut Self::Context) -> Self::Result {
+        let index = self
+            .position(&msg.name)
+            .ok_or_else(|| ApiError::not_found("Template", &msg.name))?;
+
+        Ok(self.templates.remove(index))
+    }
+}
+
+impl Handler<InstantiateTemplate> for TaskTemplateActor {
+    type Result = Result<CreateTask, ApiError>;
+
+    fn handle(&mut self, msg: InstantiateTemplate, _ctx: &mut Self::Context) -> Self::Result {
+        self.templates
+            .iter()
+            .find(|t| t.name == msg.name)
+            .ok_or_else(|| ApiError::not_found("Template", &msg.name))?
+            .instantiate(&self.registry, msg.overrides)
+    }
+}
@@ -0,0 +1,700 @@
+use crate::{ApiError, ErrorType, FaultPlan, TaskManagerActor, TaskMetadata, TaskStatus, TaskType};
+use actix::{AsyncContext, Context, Handler, Message, MessageResult, Recipient};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::time::Duration;
+use uuid::Uuid;
+
+const DEFAULT_MAX_CONCURRENT: u32 = 4;
+const MAX_CONCURRENT_LIMIT: u32 = 1000;
+
+// Messages a worker sends over the /ws/worker connection, one JSON object
+// per text frame, e.g. {"type": "lease", "max_tasks": 2}
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerRequest {
+    // Must come first. An empty capability list accepts every task type kind.
+    Register {
+        name: String,
+        #[serde(default)]
+        capabilities: Vec<String>,
+        #[serde(default = "default_max_concurrent")]
+        max_concurrent: u32,
+        // Free-form key/value labels matched against task affinity,
+        // e.g. {"region": "a", "gpu": "false"}
+        #[serde(default)]
+        labels: BTreeMap<String, String>,
+    },
+    // Asks for up to `max_tasks` more tasks, handed out as soon as matching
+    // tasks are queued and the worker has free slots
+    Lease {
+        max_tasks: u32,
+    },
+    Heartbeat,
+    Progress {
+        task_id: Uuid,
+        progress: f32,
+    },
+    Complete {
+        task_id: Uuid,
+        result: String,
+    },
+    Fail {
+        task_id: Uuid,
+        error: String,
+    },
+}
+
+fn default_max_concurrent() -> u32 {
+    DEFAULT_MAX_CONCURRENT
+}
+
+// Messages the server sends to a worker
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "()")]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerCommand {
+    Registered { worker_id: Uuid },
+    Assign { task: Box<TaskAssignment> },
+    // The task was cancelled or timed out, the worker should drop it
+    Cancel { task_id: Uuid, reason: String },
+    Error { message: String },
+}
+
+// Worker labels a remote task asks for. Every required label must match
+// exactly, preferred labels only pick between workers that are eligible.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Affinity {
+    #[serde(default)]
+    pub required: BTreeMap<String, String>,
+    #[serde(default)]
+    pub preferred: BTreeMap<String, String>,
+}
+
+impl Affinity {
+    pub fn is_empty(&self) -> bool {
+        self.required.is_empty() && self.preferred.is_empty()
+    }
+
+    pub fn validate(&self) -> Result<(), ApiError> {
+        validate_labels("affinity.required", &self.required)?;
+        validate_labels("affinity.preferred", &self.preferred)
+    }
+
+    pub fn allows(&self, labels: &BTreeMap<String, String>) -> bool {
+        self.required
+            .iter()
+            .all(|(key, value)| labels.get(key) == Some(value))
+    }
+
+    // Number of preferred labels the worker has
+    pub fn preference(&self, labels: &BTreeMap<String, String>) -> usize {
+        self.preferred
+            .iter()
+            .filter(|(key, value)| labels.get(*key) == Some(*value))
+            .count()
+    }
+}
+
+fn validate_labels(field: &str, labels: &BTreeMap<String, String>) -> Result<(), ApiError> {
+    if let Some(key) = labels
+        .keys()
+        .find(|key| key.trim().is_empty() || key.contains('='))
+    {
+        return Err(ApiError::validation_error(
+            "Label keys must be non-empty and cannot contain '='".to_string(),
+            Some(serde_json::json!({
+                "field": field,
+                "provided_value": key
+            })),
+        ));
+    }
+    Ok(())
+}
+
+// Why a task is still queued when no connected worker could ever take it
+fn no_eligible_worker(task_kind: &str, affinity: &Affinity) -> String {
+    let mut needs = vec![format!("capability '{}'", task_kind)];
+    needs.extend(
+        affinity
+            .required
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value)),
+    );
+    format!("No eligible worker: requires {}", needs.join(", "))
+}
+
+// Everything a worker needs to run a task. Duration, failure and faults
+// are rolled by the manager from the task's seed, exactly as for a local
+// TaskActor, so a remote run replays the same way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskAssignment {
+    pub task_id: Uuid,
+    pub name: String,
+    pub message: String,
+    pub task_type: TaskType,
+    // Registered type name, what worker capabilities are matched against
+    #[serde(default)]
+    pub type_name: String,
+    pub seed: u64,
+    pub timeout_ms: u64,
+    pub duration_ms: u64,
+    // When set the work should end with this error instead of completing
+    pub failure: Option<String>,
+    pub faults: FaultPlan,
+    #[serde(default)]
+    pub affinity: Affinity,
+}
+
+impl TaskAssignment {
+    pub fn new(
+        metadata: &TaskMetadata,
+        task_type: TaskType,
+        work_duration: Duration,
+        failure: Option<ErrorType>,
+        mut faults: FaultPlan,
+        affinity: Affinity,
+    ) -> Self {
+        let mut duration_ms = work_duration.as_millis() as u64;
+        match failure {
+            Some(ErrorType::Timeout) => faults.hang = true,
+            Some(ErrorType::Immediate) => duration_ms = 0,
+            _ => {}
+        }
+
+        Self {
+            task_id: metadata.id,
+            name: metadata.name.clone(),
+            message: metadata.message.clone(),
+            task_type,
+            type_name: metadata.task_type.clone(),
+            seed: metadata.seed.unwrap_or_default(),
+            timeout_ms: metadata.timeout_ms,
+            duration_ms,
+            failure: failure
+                .filter(|error_type| *error_type != ErrorType::Timeout)
+                .map(|error_type| error_type.failure_message()),
+            faults,
+            affinity,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WorkerInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub capabilities: Vec<String>,
+    pub labels: BTreeMap<String, String>,
+    pub max_concurrent: u32,
+    pub connected_at: DateTime<Utc>,
+    pub last_heartbeat: DateTime<Utc>,
+    pub active_tasks: Vec<Uuid>,
+    // Tasks requested through Lease that have not been handed out yet
+    pub leased: u32,
+    pub completed: u64,
+    pub failed: u64,
+}
+
+impl WorkerInfo {
+    pub fn accepts(&self, task_kind: &str) -> bool {
+        self.capabilities.is_empty() || self.capabilities.iter().any(|c| c == task_kind)
+    }
+
+    // Could run the task at all, regardless of free slots
+    pub fn eligible_for(&self, task_kind: &str, affinity: &Affinity) -> bool {
+        self.accepts(task_kind) && affinity.allows(&self.labels)
+    }
+
+    pub fn free_slots(&self) -> u32 {
+        self.max_concurrent
+            .saturating_sub(self.active_tasks.len() as u32)
+    }
+}
+
+#[derive(Debug)]
+struct RemoteWorker {
+    info: WorkerInfo,
+    recipient: Recipient<WorkerCommand>,
+}
+
+// Connected workers and the remote tasks waiting for or assigned to them
+#[derive(Debug, Default)]
+pub(crate) struct WorkerPool {
+    workers: HashMap<Uuid, RemoteWorker>,
+    queue: VecDeque<TaskAssignment>,
+    // Task id to the id of the worker running it
+    assignments: HashMap<Uuid, Uuid>,
+}
+
+// Sent by a worker connection once the worker has registered. The manager
+// answers on `recipient` with Registered, then with Assign and Cancel.
+#[derive(Message)]
+#[rtype(result = "Result<Uuid, ApiError>")]
+pub struct RegisterWorker {
+    pub name: String,
+    pub capabilities: Vec<String>,
+    pub labels: BTreeMap<String, String>,
+    pub max_concurrent: u32,
+    pub recipient: Recipient<WorkerCommand>,
+}
+
+// Tasks still running on the worker are retried or fail, see RetryPolicy
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "()")]
+pub struct WorkerDisconnected {
+    pub worker_id: Uuid,
+}
+
+// Any request after Register, forwarded by the worker connection
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Result<(), ApiError>")]
+pub struct WorkerReport {
+    pub worker_id: Uuid,
+    pub request: WorkerRequest,
+}
+
+#[derive(Message, Debug, Clone, Serialize, Deserialize)]
+#[rtype(result = "Vec<WorkerInfo>")]
+pub struct GetWorkers;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct RemoteTaskTimeout {
+    task_id: Uuid,
+    worker_id: Uuid,
+    attempt: u32,
+}
+
+impl TaskManagerActor {
+    pub(crate) fn enqueue_remote(
+        &mut self,
+        metadata: TaskMetadata,
+        assignment: TaskAssignment,
+        ctx: &mut Context<Self>,
+    ) {
+        tracing::info!(parent: &self.span_of(&metadata.id), "task queued for a remote worker");
+        self.task_metadata.insert(metadata.id, metadata);
+        self.workers.queue.push_back(assignment);
+        self.dispatch_remote(ctx);
+    }
+
+    // Hands queued tasks to an eligible worker with an open lease,
+    // preferring the most preferred labels, then the most free slots.
+    // Namespaces share the pool by weight, see FairScheduler, and within
+    // one namespace tasks go oldest first. Tasks left behind record why they
+    // are still waiting.
+    fn dispatch_remote(&mut self, ctx: &mut Context<Self>) {
+        let mut queues: BTreeMap<String, VecDeque<TaskAssignment>> = BTreeMap::new();
+        for assignment in std::mem::take(&mut self.workers.queue) {
+            queues
+                .entry(self.namespace_of(&assignment.task_id))
+                .or_default()
+                .push_back(assignment);
+        }
+        let mut candidates: BTreeSet<String> = queues.keys().cloned().collect();
+        self.scheduler.sync(&candidates);
+
+        let mut waiting: Vec<TaskAssignment> = Vec::new();
+        while let Some(name) = self.scheduler.next(&candidates).cloned() {
+            let queue = queues.entry(name.clone()).or_default();
+            let mut assigned = false;
+            while let Some(assignment) = queue.pop_front() {
+                match self.assign_remote(assignment, &name, ctx) {
+                    Some(assignment) => waiting.push(assignment),
+                    None => {
+                        assigned = true;
+                        break;
+                    }
+                }
+            }
+            if !assigned || queues.get(&name).is_none_or(VecDeque::is_empty) {
+                candidates.remove(&name);
+            }
+        }
+
+        self.workers.queue.extend(waiting);
+        self.workers.queue.extend(queues.into_values().flatten());
+    }
+
+    // Gives a task to the best eligible worker. When none can take it the
+    // task is handed back with its queue reason updated.
+    fn assign_remote(
+        &mut self,
+        assignment: TaskAssignment,
+        namespace: &str,
+        ctx: &mut Context<Self>,
+    ) -> Option<TaskAssignment> {
+        let span = self.span_of(&assignment.task_id);
+        let kind = assignment.type_name.as_str();
+        let affinity = &assignment.affinity;
+        let mut eligible = self
+            .workers
+            .workers
+            .values_mut()
+            .filter(|worker| worker.info.eligible_for(kind, affinity))
+            .peekable();
+        let any_eligible = eligible.peek().is_some();
+        let worker = eligible
+            .filter(|worker| worker.info.leased > 0 && worker.info.free_slots() > 0)
+            .max_by_key(|worker| {
+                (
+                    affinity.preference(&worker.info.labels),
+                    worker.info.free_slots(),
+                )
+            });
+
+        let Some(worker) = worker else {
+            let reason = if any_eligible {
+                "Waiting for a free eligible worker".to_string()
+            } else {
+                no_eligible_worker(kind, affinity)
+            };
+            if let Some(mut metadata) = self.task_metadata.get_mut(&assignment.task_id) {
+                metadata.queue_reason = Some(reason);
+            }
+            return Some(assignment);
+        };
+
+        let task_id = assignment.task_id;
+        let worker_id = worker.info.id;
+        let timeout = Duration::from_millis(assignment.timeout_ms);
+        worker.info.leased -= 1;
+        worker.info.active_tasks.push(task_id);
+        worker.recipient.do_send(WorkerCommand::Assign {
+            task: Box::new(assignment),
+        });
+        self.workers.assignments.insert(task_id, worker_id);
+
+        let now = self.clock.now();
+        let mut attempt = 1;
+        let mut waited = Duration::ZERO;
+        if let Some(mut metadata) = self.task_metadata.get_mut(&task_id) {
+            waited = metadata
+                .queued_at
+                .and_then(|queued_at| now.signed_duration_since(queued_at).to_std().ok())
+                .unwrap_or_default();
+            metadata.status = TaskStatus::InProgress;
+            metadata.started_at = now;
+            metadata.worker = Some(worker.info.name.clone());
+            metadata.queue_reason = None;
+            attempt = metadata.attempt;
+        }
+        tracing::info!(
+            parent: &span,
+            worker = %worker.info.name,
+            attempt,
+            "task assigned to a remote worker"
+        );
+        let weight = self.namespace_weight(namespace);
+        self.scheduler.record_dispatch(namespace, weight, waited);
+        self.grant_lease(task_id, attempt, ctx);
+
+        let timeout_sleep = self.clock.sleep(timeout);
+        let addr = ctx.address();
+        actix::spawn(async move {
+            timeout_sleep.await;
+            addr.do_send(RemoteTaskTimeout {
+                task_id,
+                worker_id,
+                attempt,
+            });
+        });
+        None
+    }
+
+    pub(crate) fn queued_task_ids(&self) -> impl Iterator<Item = Uuid> + '_ {
+        self.workers
+            .queue
+            .iter()
+            .map(|assignment| assignment.task_id)
+    }
+
+    // Frees the worker slot held by a task, returns the worker's id
+    fn release_remote(&mut self, task_id: Uuid) -> Option<Uuid> {
+        let worker_id = self.workers.assignments.remove(&task_id)?;
+        if let Some(worker) = self.workers.workers.get_mut(&worker_id) {
+            worker.info.active_tasks.retain(|id| *id != task_id);
+        }
+        Some(worker_id)
+    }
+
+    // Drops a task whose lease ran out while the worker kept quiet
+    pub(crate) fn reclaim_remote(&mut self, task_id: Uuid, ctx: &mut Context<Self>) {
+        let Some(worker_id) = self.release_remote(task_id) else {
+            return;
+        };
+        if let Some(worker) = self.workers.workers.get_mut(&worker_id) {
+            worker.info.failed += 1;
+            worker.recipient.do_send(WorkerCommand::Cancel {
+                task_id,
+                reason: "Lease expired".to_string(),
+            });
+        }
+        self.dispatch_remote(ctx);
+    }
+
+    fn check_assigned(&self, task_id: Uuid, worker_id: Uuid) -> Result<(), ApiError> {
+        if self.workers.assignments.get(&task_id) == Some(&worker_id) {
+            Ok(())
+        } else {
+            Err(ApiError::not_found(
+                "Task assigned to this worker",
+                &task_id.to_string(),
+            ))
+        }
+    }
+
+    // Cancels a queued or remotely running task, false if it is neither
+    pub(crate) fn cancel_remote(&mut self, task_id: Uuid, ctx: &mut Context<Self>) -> bool {
+        let now = self.clock.now();
+
+        if let Some(position) = self
+            .workers
+            .queue
+            .iter()
+            .position(|assignment| assignment.task_id == task_id)
+        {
+            self.workers.queue.remove(position);
+        } else if let Some(worker_id) = self.release_remote(task_id) {
+            if let Some(worker) = self.workers.workers.get(&worker_id) {
+                worker.recipient.do_send(WorkerCommand::Cancel {
+                    task_id,
+                    reason: "Task was cancelled".to_string(),
+                });
+            }
+            self.dispatch_remote(ctx);
+        } else {
+            return false;
+        }
+
+        if let Some(mut metadata) = self.task_metadata.get_mut(&task_id) {
+            metadata.mark_cancelled_at(now);
+        }
+        self.record_outcome(&task_id);
+        true
+    }
+}
+
+impl Handler<RegisterWorker> for TaskManagerActor {
+    type Result = Result<Uuid, ApiError>;
+
+    fn handle(&mut self, msg: RegisterWorker, ctx: &mut Self::Context) -> Self::Result {
+        if msg.name.trim().is_empty() {
+            return Err(ApiError::validation_error(
+                "Worker name is required".to_string(),
+                Some(serde_json::json!({ "field": "name" })),
+            ));
+        }
+        if !(1..=MAX_CONCURRENT_LIMIT).contains(&msg.max_concurrent) {
+            return Err(ApiError::validation_error(
+                format!(
+                    "max_concurrent must be between 1 and {}",
+                    MAX_CONCURRENT_LIMIT
+                ),
+                Some(serde_json::json!({
+                    "field": "max_concurrent",
+                    "provided_value": msg.max_concurrent
+                })),
+            ));
+        }
+        let mut capabilities = Vec::new();
+        for capability in &msg.capabilities {
+            capabilities.push(self.task_types.canonical_name(capability)?);
+        }
+        validate_labels("labels", &msg.labels)?;
+
+        let now = self.clock.now();
+        let worker_id = Uuid::new_v4();
+        tracing::info!(
+            worker = %msg.name,
+            max_concurrent = msg.max_concurrent,
+            capabilities = ?msg.capabilities,
+            labels = ?msg.labels,
+            "worker registered"
+        );
+        msg.recipient
+            .do_send(WorkerCommand::Registered { worker_id });
+        self.workers.workers.insert(
+            worker_id,
+            RemoteWorker {
+                info: WorkerInfo {
+                    id: worker_id,
+                    name: msg.name,
+                    capabilities,
+                    labels: msg.labels,
+                    max_concurrent: msg.max_concurrent,
+                    connected_at: now,
+                    last_heartbeat: now,
+                    active_tasks: Vec::new(),
+                    leased: 0,
+                    completed: 0,
+                    failed: 0,
+                },
+                recipient: msg.recipient,
+            },
+        );
+        // Queued tasks may have an eligible worker now
+        self.dispatch_remote(ctx);
+
+        Ok(worker_id)
+    }
+}
+
+impl Handler<WorkerReport> for TaskManagerActor {
+    type Result = Result<(), ApiError>;
+
+    fn handle(&mut self, msg: WorkerReport, ctx: &mut Self::Context) -> Self::Result {
+        let now = self.clock.now();
+        let worker_id = msg.worker_id;
+        let Some(worker) = self.workers.workers.get_mut(&worker_id) else {
+            return Err(ApiError::not_found("Worker", &worker_id.to_string()));
+        };
+        // Every message from a worker shows it is alive and renews the
+        // leases of all tasks it runs
+        worker.info.last_heartbeat = now;
+        for task_id in worker.info.active_tasks.clone() {
+            if let Some(attempt) = self.task_metadata.get(&task_id).map(|m| m.attempt) {
+                self.renew_lease(task_id, attempt);
+            }
+        }
+        let Some(worker) = self.workers.workers.get_mut(&worker_id) else {
+            return Err(ApiError::not_found("Worker", &worker_id.to_string()));
+        };
+
+        match msg.request {
+            WorkerRequest::Register { .. } => Err(ApiError::validation_error(
+                "Worker is already registered".to_string(),
+                Some(serde_json::json!({ "worker_id": worker_id })),
+            )),
+            WorkerRequest::Lease { max_tasks } => {
+                worker.info.leased = worker
+                    .info
+                    .leased
+                    .saturating_add(max_tasks)
+                    .min(worker.info.free_slots());
+                self.dispatch_remote(ctx);
+                Ok(())
+            }
+            WorkerRequest::Heartbeat => Ok(()),
+            WorkerRequest::Progress { task_id, progress } => {
+                self.check_assigned(task_id, worker_id)?;
+                if let Some(mut metadata) = self.task_metadata.get_mut(&task_id) {
+                    metadata.progress = progress.clamp(0.0, 1.0);
+                }
+                Ok(())
+            }
+            WorkerRequest::Complete { task_id, result } => {
+                self.check_assigned(task_id, worker_id)?;
+                self.release_remote(task_id);
+                if let Some(worker) = self.workers.workers.get_mut(&worker_id) {
+                    worker.info.completed += 1;
+                }
+                self.specs.remove(&task_id);
+                if let Some(mut metadata) = self.task_metadata.get_mut(&task_id) {
+                    metadata.lease_expires_at = None;
+                    metadata.mark_completed_at(result, now);
+                }
+                self.record_outcome(&task_id);
+                self.dispatch_remote(ctx);
+                self.admit_waiting(ctx);
+                Ok(())
+            }
+            WorkerRequest::Fail { task_id, error } => {
+                self.check_assigned(task_id, worker_id)?;
+                self.release_remote(task_id);
+                if let Some(worker) = self.workers.workers.get_mut(&worker_id) {
+                    worker.info.failed += 1;
+                }
+                self.specs.remove(&task_id);
+                if let Some(mut metadata) = self.task_metadata.get_mut(&task_id) {
+                    metadata.lease_expires_at = None;
+                    metadata.mark_error_at(error, false, now);
+                }
+                self.record_outcome(&task_id);
+                self.dispatch_remote(ctx);
+                self.admit_waiting(ctx);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Handler<WorkerDisconnected> for TaskManagerActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: WorkerDisconnected, ctx: &mut Self::Context) -> Self::Result {
+        let Some(worker) = self.workers.workers.remove(&msg.worker_id) else {
+            return;
+        };
+        tracing::warn!(
+            worker = %worker.info.name,
+            running = worker.info.active_tasks.len(),
+            "worker disconnected"
+        );
+
+        for task_id in worker.info.active_tasks {
+            self.workers.assignments.remove(&task_id);
+            self.retry_or_fail(
+                task_id,
+                format!("Worker '{}' disconnected", worker.info.name),
+                ctx,
+            );
+        }
+        self.dispatch_remote(ctx);
+        self.admit_waiting(ctx);
+    }
+}
+
+impl Handler<RemoteTaskTimeout> for TaskManagerActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: RemoteTaskTimeout, ctx: &mut Self::Context) -> Self::Result {
+        // A retry may have landed on the same worker since
+        let current_attempt = self.task_metadata.get(&msg.task_id).map(|m| m.attempt);
+        if self.check_assigned(msg.task_id, msg.worker_id).is_err()
+            || current_attempt != Some(msg.attempt)
+        {
+            return;
+        }
+
+        self.release_remote(msg.task_id);
+        if let Some(worker) = self.workers.workers.get_mut(&msg.worker_id) {
+            worker.info.failed += 1;
+            worker.recipient.do_send(WorkerCommand::Cancel {
+                task_id: msg.task_id,
+                reason: "Task timed out".to_string(),
+            });
+        }
+        self.specs.remove(&msg.task_id);
+        let now = self.clock.now();
+        if let Some(mut metadata) = self.task_metadata.get_mut(&msg.task_id) {
+            let error = format!("Task timed out after {}ms", metadata.timeout_ms);
+            metadata.lease_expires_at = None;
+            metadata.mark_error_at(error, true, now);
+        }
+        self.record_outcome(&msg.task_id);
+        self.dispatch_remote(ctx);
+        self.admit_waiting(ctx);
+    }
+}
+
+impl Handler<GetWorkers> for TaskManagerActor {
+    type Result = MessageResult<GetWorkers>;
+
+    fn handle(&mut self, _msg: GetWorkers, _ctx: &mut Self::Context) -> Self::Result {
+        let mut workers: Vec<WorkerInfo> = self
+            .workers
+            .workers
+            .values()
+            .map(|worker| worker.info.clone())
+            .collect();
+        workers.sort_by(|a, b| {
+            a.connected_at
+                .cmp(&b.connected_at)
+                .then(a.name.cmp(&b.name))
+        });
+        MessageResult(workers)
+    }
+}
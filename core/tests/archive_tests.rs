@@ -0,0 +1,229 @@
+use actix::Actor;
+use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+use std::time::Duration;
+use task_core::*;
+use uuid::Uuid;
+
+fn temp_archive() -> TaskArchive {
+    TaskArchive::new(std::env::temp_dir().join(format!("overlord-archive-{}", Uuid::new_v4())))
+}
+
+fn finished_task(
+    name: &str,
+    status: TaskStatus,
+    started_at: chrono::DateTime<Utc>,
+) -> TaskMetadata {
+    TaskMetadata {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        message: "Archived task".to_string(),
+        status,
+        started_at,
+        finished_at: Some(started_at + ChronoDuration::seconds(2)),
+        result: None,
+        error: None,
+        timeout_ms: 5000,
+        actual_duration_ms: Some(2000),
+        cancelled_at: None,
+        timeout_at: None,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_archive_partitions_by_date() {
+    let archive = temp_archive();
+    let day1 = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+    let day2 = Utc.with_ymd_and_hms(2025, 3, 2, 10, 0, 0).unwrap();
+
+    let written = archive
+        .append(&[
+            finished_task("First", TaskStatus::Completed, day1),
+            finished_task("Second", TaskStatus::Error, day2),
+        ])
+        .unwrap();
+    assert_eq!(written, 2);
+
+    assert!(archive.partition_path(day1.date_naive()).exists());
+    assert!(archive.partition_path(day2.date_naive()).exists());
+
+    let _ = std::fs::remove_dir_all(archive.root());
+}
+
+#[test]
+fn test_archive_query_filters() {
+    let archive = temp_archive();
+    let day1 = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+    let day2 = Utc.with_ymd_and_hms(2025, 3, 2, 10, 0, 0).unwrap();
+
+    // Two separate appends to the same partition produce two gzip members
+    archive
+        .append(&[finished_task("ETL nightly", TaskStatus::Completed, day1)])
+        .unwrap();
+    archive
+        .append(&[
+            finished_task("ETL retry", TaskStatus::Error, day1),
+            finished_task("Report", TaskStatus::Completed, day2),
+        ])
+        .unwrap();
+
+    let all = archive.query(&ArchiveQuery::default()).unwrap();
+    assert_eq!(all.len(), 3);
+
+    let by_date = archive
+        .query(&ArchiveQuery {
+            from: Some(day2 - ChronoDuration::hours(1)),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(by_date.len(), 1);
+    assert_eq!(by_date[0].name, "Report");
+
+    let by_status_and_name = archive
+        .query(&ArchiveQuery {
+            status: Some(TaskStatus::Completed),
+            name: Some("etl".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(by_status_and_name.len(), 1);
+    assert_eq!(by_status_and_name[0].name, "ETL nightly");
+
+    let limited = archive
+        .query(&ArchiveQuery {
+            limit: Some(2),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(limited.len(), 2);
+
+    let _ = std::fs::remove_dir_all(archive.root());
+}
+
+#[test]
+fn test_archive_query_missing_directory() {
+    let archive = temp_archive();
+    let tasks = archive.query(&ArchiveQuery::default()).unwrap();
+    assert!(tasks.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_manager_archives_expired_tasks() {
+    let archive = temp_archive();
+    let manager = TaskManagerActor::new()
+        .with_archive(archive.clone(), Duration::ZERO)
+        .start();
+
+    let task_id = manager
+        .send(CreateTask {
+            name: "Archive Me".to_string(),
+            message: "Fails immediately".to_string(),
+            task_type: TaskType::Error {
+                timeout_ms: None,
+                error_type: ErrorType::Immediate,
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    // Give the task a moment to fail and report back
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let archived = manager.send(ArchiveExpiredTasks).await.unwrap();
+    assert_eq!(archived, 1);
+
+    let task = manager.send(GetTask { id: task_id }).await.unwrap();
+    assert!(task.is_none());
+
+    let tasks = archive.query(&ArchiveQuery::default()).unwrap();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].id, task_id);
+    assert_eq!(tasks[0].status, TaskStatus::Error);
+
+    let _ = std::fs::remove_dir_all(archive.root());
+}
+
+#[actix_rt::test]
+async fn test_private_namespace_archive_needs_its_scope() {
+    let archive = temp_archive();
+    let manager = TaskManagerActor::new()
+        .with_archive(archive.clone(), Duration::ZERO)
+        .with_snapshot_interval(Duration::from_millis(10));
+    let reader = manager.reader();
+    let manager = manager.start();
+    manager
+        .send(ConfigureNamespace {
+            name: "secret".to_string(),
+            config: NamespaceConfig {
+                visibility: Visibility::Private,
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    let failing = |namespace: Option<&str>| CreateTask {
+        name: "Archive Me".to_string(),
+        message: "Fails immediately".to_string(),
+        task_type: TaskType::Error {
+            timeout_ms: None,
+            error_type: ErrorType::Immediate,
+        },
+        namespace: namespace.map(str::to_string),
+        ..Default::default()
+    };
+    let hidden = manager.send(failing(Some("secret"))).await.unwrap();
+    let shared = manager.send(failing(None)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(manager.send(ArchiveExpiredTasks).await.unwrap(), 2);
+
+    let unscoped = archive
+        .query(&ArchiveQuery {
+            private: reader.private_namespaces(),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(unscoped.len(), 1);
+    assert_eq!(unscoped[0].id, shared);
+
+    let scoped = archive
+        .query(&ArchiveQuery {
+            namespace: Some("secret".to_string()),
+            private: reader.private_namespaces(),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(scoped.len(), 1);
+    assert_eq!(scoped[0].id, hidden);
+
+    let _ = std::fs::remove_dir_all(archive.root());
+}
+
+#[actix_rt::test]
+async fn test_manager_keeps_running_tasks() {
+    let archive = temp_archive();
+    let manager = TaskManagerActor::new()
+        .with_archive(archive.clone(), Duration::ZERO)
+        .start();
+
+    let task_id = manager
+        .send(CreateTask {
+            name: "Still Running".to_string(),
+            message: "Long task".to_string(),
+            task_type: TaskType::Long {
+                timeout_ms: None,
+                duration: None,
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let archived = manager.send(ArchiveExpiredTasks).await.unwrap();
+    assert_eq!(archived, 0);
+
+    let task = manager.send(GetTask { id: task_id }).await.unwrap();
+    assert!(task.is_some());
+}
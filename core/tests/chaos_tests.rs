@@ -0,0 +1,255 @@
+use std::time::Duration;
+use task_core::*;
+
+mod common;
+use common::*;
+
+fn quick_task(faults: Option<FaultProfile>) -> CreateTask {
+    CreateTask {
+        name: "Chaos".to_string(),
+        message: "Fault injection".to_string(),
+        task_type: TaskType::Quick {
+            timeout_ms: Some(1000),
+            duration: None,
+        },
+        faults,
+        ..Default::default()
+    }
+}
+
+#[actix_rt::test]
+async fn test_error_types_fail_with_their_own_message() {
+    let (manager, clock) = manual_manager();
+
+    let id = manager
+        .send(CreateTask {
+            name: "Network".to_string(),
+            message: "Fails".to_string(),
+            task_type: TaskType::Error {
+                timeout_ms: Some(3000),
+                error_type: ErrorType::NetworkError,
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let task = advance(&manager, &clock, id, 1000).await;
+    assert_eq!(task.status, TaskStatus::Error);
+    assert!(!task.was_timeout());
+    assert_eq!(task.error, Some(ErrorType::NetworkError.failure_message()));
+}
+
+#[actix_rt::test]
+async fn test_panic_is_reported_as_task_error() {
+    let (manager, clock) = manual_manager();
+    let id = manager
+        .send(quick_task(Some(FaultProfile {
+            panic_probability: 1.0,
+            ..Default::default()
+        })))
+        .await
+        .unwrap();
+
+    let task = advance(&manager, &clock, id, 750).await;
+    assert_eq!(task.status, TaskStatus::Error);
+    assert!(task.error.unwrap().starts_with("Task panicked"));
+    assert_eq!(task.faults, vec!["panic".to_string()]);
+}
+
+#[actix_rt::test]
+async fn test_hang_runs_into_timeout() {
+    let (manager, clock) = manual_manager();
+    let id = manager
+        .send(quick_task(Some(FaultProfile {
+            hang_probability: 1.0,
+            ..Default::default()
+        })))
+        .await
+        .unwrap();
+
+    let task = advance(&manager, &clock, id, 999).await;
+    assert_eq!(task.status, TaskStatus::InProgress);
+
+    let task = advance(&manager, &clock, id, 1).await;
+    assert!(task.was_timeout());
+}
+
+#[actix_rt::test]
+async fn test_latency_spike_delays_completion() {
+    let (manager, clock) = manual_manager();
+    let id = manager
+        .send(CreateTask {
+            task_type: TaskType::Quick {
+                timeout_ms: Some(10_000),
+                duration: None,
+            },
+            ..quick_task(Some(FaultProfile {
+                latency_spike: Some(LatencySpike {
+                    probability: 1.0,
+                    extra_ms: 2000,
+                }),
+                ..Default::default()
+            }))
+        })
+        .await
+        .unwrap();
+
+    // 75% of the timeout plus the spike
+    let task = advance(&manager, &clock, id, 7500).await;
+    assert_eq!(task.status, TaskStatus::InProgress);
+    assert_eq!(task.faults, vec!["latency_spike:+2000ms".to_string()]);
+
+    let task = advance(&manager, &clock, id, 2000).await;
+    assert_eq!(task.status, TaskStatus::Completed);
+    assert_eq!(task.actual_duration_ms, Some(9500));
+}
+
+#[actix_rt::test]
+async fn test_partial_progress_stalls() {
+    let (manager, clock) = manual_manager();
+    let id = manager
+        .send(quick_task(Some(FaultProfile {
+            progress: Some(ProgressFault {
+                steps: 5,
+                slowdown: 1.0,
+                stall_at: Some(0.4),
+            }),
+            ..Default::default()
+        })))
+        .await
+        .unwrap();
+
+    // Each of the five steps takes 150ms
+    let task = advance(&manager, &clock, id, 150).await;
+    assert_eq!(task.progress, 0.2);
+
+    let task = advance(&manager, &clock, id, 600).await;
+    assert_eq!(task.progress, 0.4);
+    assert_eq!(task.status, TaskStatus::InProgress);
+
+    let task = advance(&manager, &clock, id, 250).await;
+    assert!(task.was_timeout());
+    assert_eq!(task.progress, 0.4);
+}
+
+#[actix_rt::test]
+async fn test_profiles_apply_per_type_then_global() {
+    let (manager, clock) = manual_manager();
+
+    manager
+        .send(SetFaultProfile {
+            task_type: None,
+            profile: Some(FaultProfile {
+                hang_probability: 1.0,
+                ..Default::default()
+            }),
+        })
+        .await
+        .unwrap();
+    manager
+        .send(SetFaultProfile {
+            task_type: Some("quick".to_string()),
+            profile: Some(FaultProfile {
+                intermittent_failure: Some(IntermittentFailure {
+                    probability: 1.0,
+                    error_type: ErrorType::ValidationError,
+                }),
+                ..Default::default()
+            }),
+        })
+        .await
+        .unwrap();
+
+    let config = manager.send(GetChaosConfig).await.unwrap();
+    assert!(config.global.is_some());
+    assert!(config.per_type.contains_key("quick"));
+
+    let quick = manager.send(quick_task(None)).await.unwrap();
+    let long = manager
+        .send(CreateTask {
+            task_type: TaskType::Long {
+                timeout_ms: Some(1000),
+                duration: None,
+            },
+            ..quick_task(None)
+        })
+        .await
+        .unwrap();
+    let own_profile = manager
+        .send(quick_task(Some(FaultProfile::default())))
+        .await
+        .unwrap();
+
+    settle().await;
+    clock.advance(Duration::from_millis(800));
+    settle().await;
+
+    let quick = manager.send(GetTask { id: quick }).await.unwrap().unwrap();
+    assert_eq!(
+        quick.error,
+        Some(ErrorType::ValidationError.failure_message())
+    );
+
+    let long = manager.send(GetTask { id: long }).await.unwrap().unwrap();
+    assert_eq!(long.faults, vec!["hang".to_string()]);
+    assert_eq!(long.status, TaskStatus::InProgress);
+
+    let own_profile = manager
+        .send(GetTask { id: own_profile })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(own_profile.status, TaskStatus::Completed);
+
+    manager
+        .send(SetFaultProfile {
+            task_type: None,
+            profile: None,
+        })
+        .await
+        .unwrap();
+    let config = manager.send(GetChaosConfig).await.unwrap();
+    assert!(config.global.is_none());
+}
+
+#[test]
+fn test_fault_profile_validation() {
+    assert!(FaultProfile::default().validate().is_ok());
+
+    let invalid = FaultProfile {
+        panic_probability: 1.5,
+        ..Default::default()
+    };
+    assert!(invalid.validate().is_err());
+
+    let invalid = FaultProfile {
+        progress: Some(ProgressFault {
+            steps: 0,
+            slowdown: 1.0,
+            stall_at: None,
+        }),
+        ..Default::default()
+    };
+    assert!(invalid.validate().is_err());
+}
+
+#[test]
+fn test_slow_progress_step_offsets() {
+    let plan = FaultPlan {
+        steps: 3,
+        slowdown: 2.0,
+        ..Default::default()
+    };
+
+    // Weights 1, 2 and 4 out of 7
+    let offsets = plan.step_offsets(Duration::from_millis(700));
+    assert_eq!(
+        offsets,
+        vec![
+            Duration::from_millis(100),
+            Duration::from_millis(300),
+            Duration::from_millis(700)
+        ]
+    );
+}
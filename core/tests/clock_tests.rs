@@ -0,0 +1,134 @@
+use actix::Actor;
+use chrono::{TimeZone, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use task_core::*;
+
+mod common;
+use common::*;
+
+fn start_time() -> chrono::DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap()
+}
+
+#[actix_rt::test]
+async fn test_manual_clock_wakes_sleepers_on_advance() {
+    let clock = ManualClock::new(start_time());
+    let short = clock.sleep(Duration::from_secs(1));
+    let long = clock.sleep(Duration::from_secs(5));
+    assert_eq!(clock.pending_sleepers(), 2);
+
+    let short = tokio::spawn(short);
+    let long = tokio::spawn(long);
+
+    clock.advance(Duration::from_secs(2));
+    short.await.unwrap();
+    assert_eq!(clock.pending_sleepers(), 1);
+    assert!(!long.is_finished());
+
+    clock.advance(Duration::from_secs(3));
+    long.await.unwrap();
+    assert_eq!(clock.now(), start_time() + chrono::Duration::seconds(5));
+}
+
+#[actix_rt::test]
+async fn test_manual_clock_zero_sleep_is_ready() {
+    let clock = ManualClock::new(start_time());
+    clock.sleep(Duration::ZERO).await;
+    assert_eq!(clock.pending_sleepers(), 0);
+}
+
+#[actix_rt::test]
+async fn test_task_timeout_uses_clock() {
+    let clock = ManualClock::new(start_time());
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .start();
+
+    // Timeout errors work past their timeout, so the timeout fires first
+    let task_id = manager
+        .send(CreateTask {
+            name: "Slow".to_string(),
+            message: "Times out".to_string(),
+            task_type: TaskType::Error {
+                timeout_ms: Some(60_000),
+                error_type: ErrorType::Timeout,
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    settle().await;
+
+    clock.advance(Duration::from_secs(59));
+    settle().await;
+    let task = manager
+        .send(GetTask { id: task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.status, TaskStatus::InProgress);
+    assert_eq!(task.started_at, start_time());
+
+    clock.advance(Duration::from_secs(1));
+    settle().await;
+    let task = manager
+        .send(GetTask { id: task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.status, TaskStatus::Error);
+    assert!(task.was_timeout());
+    assert_eq!(
+        task.timeout_at,
+        Some(start_time() + chrono::Duration::minutes(1))
+    );
+    assert_eq!(task.actual_duration_ms, Some(60_000));
+}
+
+#[actix_rt::test]
+async fn test_cancel_and_archive_use_clock() {
+    let clock = ManualClock::new(start_time());
+    let archive = TaskArchive::new(
+        std::env::temp_dir().join(format!("overlord-clock-{}", uuid::Uuid::new_v4())),
+    );
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_archive(archive.clone(), Duration::from_secs(600))
+        .start();
+
+    let task_id = manager
+        .send(CreateTask {
+            name: "Cancelled".to_string(),
+            message: "Long task".to_string(),
+            task_type: TaskType::Long {
+                timeout_ms: None,
+                duration: None,
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    settle().await;
+
+    clock.advance(Duration::from_secs(3));
+    assert!(manager.send(CancelTaskById { id: task_id }).await.unwrap());
+    settle().await;
+
+    let task = manager
+        .send(GetTask { id: task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        task.cancelled_at,
+        Some(start_time() + chrono::Duration::seconds(3))
+    );
+
+    // Retention is measured on the manual clock too
+    assert_eq!(manager.send(ArchiveExpiredTasks).await.unwrap(), 0);
+    clock.advance(Duration::from_secs(600));
+    assert_eq!(manager.send(ArchiveExpiredTasks).await.unwrap(), 1);
+
+    let _ = std::fs::remove_dir_all(archive.root());
+}
@@ -0,0 +1,78 @@
+// Fixtures shared by the integration tests. Each test file is its own
+// crate and uses only some of them.
+#![allow(dead_code)]
+
+use actix::{Actor, Addr};
+use std::sync::Arc;
+use std::time::Duration;
+use task_core::*;
+use uuid::Uuid;
+
+// Snapshot interval of the managers that tests read through a TaskReader
+pub const INTERVAL: Duration = Duration::from_millis(10);
+
+// Lets spawned actor futures run until they are parked on the manual clock
+pub async fn settle() {
+    for _ in 0..100 {
+        tokio::task::yield_now().await;
+    }
+}
+
+// Long enough for the managers to publish what they just did
+pub async fn published() {
+    tokio::time::sleep(INTERVAL * 5).await;
+}
+
+pub fn manual_manager() -> (Addr<TaskManagerActor>, ManualClock) {
+    manual_manager_with(|manager| manager)
+}
+
+// A manager on a manual clock, set up further by `configure`
+pub fn manual_manager_with(
+    configure: impl FnOnce(TaskManagerActor) -> TaskManagerActor,
+) -> (Addr<TaskManagerActor>, ManualClock) {
+    let clock = ManualClock::default();
+    let manager = configure(TaskManagerActor::new().with_clock(Arc::new(clock.clone())));
+    (manager.start(), clock)
+}
+
+// A manager on a manual clock publishing every INTERVAL, with its reader
+pub fn manual_manager_and_reader() -> (Addr<TaskManagerActor>, TaskReader, ManualClock) {
+    let clock = ManualClock::default();
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_snapshot_interval(INTERVAL);
+    let reader = manager.reader();
+    (manager.start(), reader, clock)
+}
+
+pub async fn advance_clock(clock: &ManualClock, ms: u64) {
+    settle().await;
+    clock.advance(Duration::from_millis(ms));
+    settle().await;
+}
+
+// Moves the clock on and returns the task as it is then
+pub async fn advance(
+    manager: &Addr<TaskManagerActor>,
+    clock: &ManualClock,
+    id: Uuid,
+    ms: u64,
+) -> TaskMetadata {
+    advance_clock(clock, ms).await;
+    manager.send(GetTask { id }).await.unwrap().unwrap()
+}
+
+// Runs until it is cancelled, its timeout is out of reach of the tests
+pub fn long_task(namespace: Option<&str>) -> CreateTask {
+    CreateTask {
+        name: "Still running".to_string(),
+        message: "Never finishes here".to_string(),
+        task_type: TaskType::Long {
+            timeout_ms: Some(3_600_000),
+            duration: None,
+        },
+        namespace: namespace.map(str::to_string),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,162 @@
+use actix::Actor;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use task_core::*;
+
+fn samples(distribution: &DurationDistribution, count: usize) -> Vec<u64> {
+    let mut rng = StdRng::seed_from_u64(99);
+    let mut samples: Vec<u64> = (0..count)
+        .map(|_| distribution.sample(&mut rng).as_millis() as u64)
+        .collect();
+    samples.sort_unstable();
+    samples
+}
+
+#[test]
+fn test_constant_and_uniform_samples() {
+    let constant = samples(&DurationDistribution::Constant { ms: 1200 }, 10);
+    assert!(constant.iter().all(|ms| *ms == 1200));
+
+    let uniform = samples(
+        &DurationDistribution::Uniform {
+            min_ms: 100,
+            max_ms: 300,
+        },
+        500,
+    );
+    assert!(uniform[0] >= 100 && uniform[499] <= 300);
+    assert!(uniform[0] < 120 && uniform[499] > 280);
+}
+
+#[test]
+fn test_log_normal_median_and_normal_clamp() {
+    let log_normal = samples(
+        &DurationDistribution::LogNormal {
+            median_ms: 1000.0,
+            sigma: 0.5,
+        },
+        2001,
+    );
+    let median = log_normal[1000];
+    assert!((900..=1100).contains(&median), "median was {}", median);
+
+    // Half of these would be negative, they clamp to zero
+    let normal = samples(
+        &DurationDistribution::Normal {
+            mean_ms: 0.0,
+            std_dev_ms: 100.0,
+        },
+        200,
+    );
+    assert_eq!(normal[0], 0);
+}
+
+#[test]
+fn test_pareto_has_heavy_tail() {
+    let pareto = samples(
+        &DurationDistribution::Pareto {
+            scale_ms: 100.0,
+            shape: 1.5,
+        },
+        2000,
+    );
+    assert!(pareto[0] >= 100);
+    assert!(pareto[1999] > 1000, "longest was {}", pareto[1999]);
+}
+
+#[test]
+fn test_distribution_validation_and_serde() {
+    assert!(DurationDistribution::Uniform {
+        min_ms: 500,
+        max_ms: 100
+    }
+    .validate()
+    .is_err());
+    assert!(DurationDistribution::Pareto {
+        scale_ms: 100.0,
+        shape: 0.0
+    }
+    .validate()
+    .is_err());
+
+    let parsed: DurationDistribution =
+        serde_json::from_value(json!({ "kind": "log_normal", "median_ms": 800.0, "sigma": 0.3 }))
+            .unwrap();
+    assert_eq!(
+        parsed,
+        DurationDistribution::LogNormal {
+            median_ms: 800.0,
+            sigma: 0.3
+        }
+    );
+}
+
+#[test]
+fn test_registry_accepts_duration_param() {
+    let registry = TaskTypeRegistry::with_builtin_types();
+    let params = |value: serde_json::Value| value.as_object().cloned().unwrap();
+
+    let long = registry
+        .resolve(
+            "long",
+            &params(json!({ "duration": { "kind": "constant", "ms": 4000 } })),
+        )
+        .unwrap();
+    assert!(matches!(
+        long,
+        TaskType::Long {
+            duration: Some(DurationDistribution::Constant { ms: 4000 }),
+            ..
+        }
+    ));
+
+    let invalid = registry.resolve(
+        "custom",
+        &params(json!({ "duration": { "kind": "uniform", "min_ms": 9, "max_ms": 1 } })),
+    );
+    assert!(invalid.is_err());
+
+    let unknown_kind = registry.resolve(
+        "quick",
+        &params(json!({ "duration": { "kind": "bimodal" } })),
+    );
+    assert!(unknown_kind.is_err());
+}
+
+#[actix_rt::test]
+async fn test_manager_uses_sampled_duration() {
+    let clock = ManualClock::default();
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .start();
+
+    let id = manager
+        .send(CreateTask {
+            name: "Sampled".to_string(),
+            message: "Constant duration".to_string(),
+            task_type: TaskType::Custom {
+                name: "Sampled".to_string(),
+                timeout_ms: 5000,
+                failure_rate: None,
+                duration: Some(DurationDistribution::Constant { ms: 300 }),
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    for _ in 0..100 {
+        tokio::task::yield_now().await;
+    }
+    clock.advance(Duration::from_millis(300));
+    for _ in 0..100 {
+        tokio::task::yield_now().await;
+    }
+
+    let task = manager.send(GetTask { id }).await.unwrap().unwrap();
+    assert_eq!(task.status, TaskStatus::Completed);
+    assert_eq!(task.actual_duration_ms, Some(300));
+}
@@ -0,0 +1,144 @@
+use std::time::Duration;
+use task_core::*;
+use uuid::Uuid;
+
+mod common;
+use common::*;
+
+fn task(task_type: TaskType, tags: &[&str]) -> CreateTask {
+    CreateTask {
+        name: "Indexed".to_string(),
+        message: "Found without a scan".to_string(),
+        task_type,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+fn quick() -> TaskType {
+    TaskType::Quick {
+        timeout_ms: Some(10_000),
+        duration: Some(DurationDistribution::Constant { ms: 1000 }),
+    }
+}
+
+fn long() -> TaskType {
+    TaskType::Long {
+        timeout_ms: Some(60_000),
+        duration: None,
+    }
+}
+
+fn ids(tasks: &[TaskMetadata]) -> Vec<Uuid> {
+    tasks.iter().map(|task| task.id).collect()
+}
+
+#[actix_rt::test]
+async fn test_filters_combine_and_keep_start_order() {
+    let (manager, reader, clock) = manual_manager_and_reader();
+    let mut created = Vec::new();
+    for (task_type, tags) in [
+        (quick(), vec!["red"]),
+        (long(), vec!["red", "blue"]),
+        (quick(), vec!["blue"]),
+        (long(), vec![]),
+    ] {
+        created.push(manager.send(task(task_type, &tags)).await.unwrap());
+        clock.advance(Duration::from_millis(10));
+    }
+    published().await;
+
+    let select = |filter: TaskFilter| ids(&reader.select(&filter, None));
+    assert_eq!(select(TaskFilter::default()), created);
+    assert_eq!(
+        select(TaskFilter {
+            task_type: Some("quick".to_string()),
+            ..Default::default()
+        }),
+        vec![created[0], created[2]]
+    );
+    assert_eq!(
+        select(TaskFilter {
+            tag: Some("red".to_string()),
+            task_type: Some("long".to_string()),
+            ..Default::default()
+        }),
+        vec![created[1]]
+    );
+    assert!(select(TaskFilter {
+        tag: Some("green".to_string()),
+        ..Default::default()
+    })
+    .is_empty());
+    assert_eq!(
+        select(TaskFilter {
+            status: Some(TaskStatus::InProgress),
+            tag: Some("blue".to_string()),
+            ..Default::default()
+        }),
+        vec![created[1], created[2]]
+    );
+}
+
+#[actix_rt::test]
+async fn test_start_time_range() {
+    let (manager, reader, clock) = manual_manager_and_reader();
+    let start = clock.now();
+    let mut created = Vec::new();
+    for _ in 0..5 {
+        created.push(manager.send(task(long(), &[])).await.unwrap());
+        clock.advance(Duration::from_secs(1));
+    }
+    published().await;
+
+    let at = |secs: i64| start + chrono::Duration::seconds(secs);
+    let filter = TaskFilter {
+        started_after: Some(at(1)),
+        started_before: Some(at(3)),
+        ..Default::default()
+    };
+    assert_eq!(ids(&reader.select(&filter, None)), created[1..3].to_vec());
+
+    let filter = TaskFilter {
+        started_after: Some(at(3)),
+        started_before: Some(at(1)),
+        ..Default::default()
+    };
+    assert!(reader.select(&filter, None).is_empty());
+}
+
+#[actix_rt::test]
+async fn test_index_follows_status_changes() {
+    let (manager, reader, clock) = manual_manager_and_reader();
+    let done = manager.send(task(quick(), &["a"])).await.unwrap();
+    let cancelled = manager.send(task(long(), &["a"])).await.unwrap();
+    let running = manager.send(task(long(), &["a"])).await.unwrap();
+
+    for _ in 0..100 {
+        tokio::task::yield_now().await;
+    }
+    clock.advance(Duration::from_millis(1500));
+    manager
+        .send(CancelTaskById { id: cancelled })
+        .await
+        .unwrap();
+    for _ in 0..100 {
+        tokio::task::yield_now().await;
+    }
+    published().await;
+
+    let with_status = |status: TaskStatus| {
+        ids(&reader.select(
+            &TaskFilter {
+                status: Some(status),
+                tag: Some("a".to_string()),
+                ..Default::default()
+            },
+            None,
+        ))
+    };
+    assert_eq!(with_status(TaskStatus::Completed), vec![done]);
+    assert_eq!(with_status(TaskStatus::Error), vec![cancelled]);
+    assert_eq!(with_status(TaskStatus::InProgress), vec![running]);
+    assert_eq!(reader.task(&done).unwrap().task_type, "quick");
+}
@@ -0,0 +1,255 @@
+use actix::{Actor, Addr, Context, Handler, MessageResult};
+use std::collections::BTreeMap;
+use task_core::*;
+use uuid::Uuid;
+
+mod common;
+use common::*;
+
+fn frozen_task(retry: Option<RetryPolicy>) -> CreateTask {
+    CreateTask {
+        name: "Frozen".to_string(),
+        message: "Loses its heartbeat".to_string(),
+        task_type: TaskType::Quick {
+            timeout_ms: Some(1000),
+            duration: None,
+        },
+        faults: Some(FaultProfile {
+            heartbeat_loss_probability: 1.0,
+            ..Default::default()
+        }),
+        retry,
+        ..Default::default()
+    }
+}
+
+#[derive(Default)]
+struct FakeWorker {
+    commands: Vec<WorkerCommand>,
+}
+
+impl Actor for FakeWorker {
+    type Context = Context<Self>;
+}
+
+impl Handler<WorkerCommand> for FakeWorker {
+    type Result = ();
+
+    fn handle(&mut self, msg: WorkerCommand, _ctx: &mut Self::Context) -> Self::Result {
+        self.commands.push(msg);
+    }
+}
+
+#[derive(actix::Message)]
+#[rtype(result = "Vec<WorkerCommand>")]
+struct Commands;
+
+impl Handler<Commands> for FakeWorker {
+    type Result = MessageResult<Commands>;
+
+    fn handle(&mut self, _msg: Commands, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.commands.clone())
+    }
+}
+
+async fn leasing_worker(manager: &Addr<TaskManagerActor>, name: &str) -> (Uuid, Addr<FakeWorker>) {
+    let worker = FakeWorker::default().start();
+    let worker_id = manager
+        .send(RegisterWorker {
+            name: name.to_string(),
+            capabilities: Vec::new(),
+            labels: BTreeMap::new(),
+            max_concurrent: 1,
+            recipient: worker.clone().recipient(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    manager
+        .send(WorkerReport {
+            worker_id,
+            request: WorkerRequest::Lease { max_tasks: 1 },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    (worker_id, worker)
+}
+
+fn remote_long_task(retry: Option<RetryPolicy>) -> CreateTask {
+    CreateTask {
+        name: "Remote".to_string(),
+        message: "Runs on a worker".to_string(),
+        task_type: TaskType::Long {
+            timeout_ms: Some(30_000),
+            duration: None,
+        },
+        executor: Executor::Remote,
+        retry,
+        ..Default::default()
+    }
+}
+
+#[actix_rt::test]
+async fn test_lost_heartbeat_expires_the_lease() {
+    let (manager, clock) = manual_manager();
+    let id = manager.send(frozen_task(None)).await.unwrap();
+
+    // A frozen executor does not even time itself out
+    let task = advance(&manager, &clock, id, 1000).await;
+    assert_eq!(task.status, TaskStatus::InProgress);
+    assert_eq!(task.faults, vec!["heartbeat_loss".to_string()]);
+    assert!(task.lease_expires_at.is_some());
+
+    let task = advance(&manager, &clock, id, 4000).await;
+    assert_eq!(task.status, TaskStatus::Error);
+    assert!(!task.was_timeout());
+    assert_eq!(
+        task.error.as_deref(),
+        Some("Lease expired after 5000ms without a heartbeat")
+    );
+    assert_eq!(task.attempt, 1);
+    assert!(task.lease_expires_at.is_none());
+}
+
+#[actix_rt::test]
+async fn test_expired_lease_is_retried_until_attempts_run_out() {
+    let (manager, clock) = manual_manager();
+    let id = manager
+        .send(frozen_task(Some(RetryPolicy {
+            max_attempts: 2,
+            backoff_ms: 1000,
+        })))
+        .await
+        .unwrap();
+
+    let task = advance(&manager, &clock, id, 5000).await;
+    assert_eq!(task.status, TaskStatus::Queued);
+    assert_eq!(task.attempt, 1);
+    assert_eq!(
+        task.attempt_errors,
+        vec!["attempt 1: Lease expired after 5000ms without a heartbeat".to_string()]
+    );
+
+    // The second attempt starts after the backoff under the same id
+    let task = advance(&manager, &clock, id, 1000).await;
+    assert_eq!(task.status, TaskStatus::InProgress);
+    assert_eq!(task.attempt, 2);
+    assert_eq!(task.max_attempts, 2);
+
+    let task = advance(&manager, &clock, id, 5000).await;
+    assert_eq!(task.status, TaskStatus::Error);
+    assert_eq!(task.attempt, 2);
+    assert_eq!(task.attempt_errors.len(), 1);
+    assert_eq!(manager.send(GetAllTasks).await.unwrap().len(), 1);
+}
+
+#[actix_rt::test]
+async fn test_healthy_task_keeps_renewing_its_lease() {
+    let (manager, clock) = manual_manager();
+    let id = manager
+        .send(CreateTask {
+            name: "Slow".to_string(),
+            message: "Longer than a lease".to_string(),
+            task_type: TaskType::Long {
+                timeout_ms: Some(20_000),
+                duration: None,
+            },
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let mut task = advance(&manager, &clock, id, 0).await;
+    for _ in 0..30 {
+        if task.status != TaskStatus::InProgress {
+            break;
+        }
+        task = advance(&manager, &clock, id, 1000).await;
+    }
+    assert_eq!(task.status, TaskStatus::Completed);
+    assert!(task.attempt_errors.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_remote_lease_needs_worker_heartbeats() {
+    let (manager, clock) = manual_manager();
+    let (worker_id, worker) = leasing_worker(&manager, "chatty").await;
+    let id = manager.send(remote_long_task(None)).await.unwrap();
+
+    // Heartbeats keep the task well past a single lease
+    for _ in 0..4 {
+        let task = advance(&manager, &clock, id, 2000).await;
+        assert_eq!(task.status, TaskStatus::InProgress);
+        manager
+            .send(WorkerReport {
+                worker_id,
+                request: WorkerRequest::Heartbeat,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    // Then the worker goes quiet
+    let task = advance(&manager, &clock, id, 5000).await;
+    assert_eq!(task.status, TaskStatus::Error);
+    assert_eq!(
+        task.error.as_deref(),
+        Some("Lease expired after 5000ms without a heartbeat")
+    );
+    let commands = worker.send(Commands).await.unwrap();
+    assert!(commands.iter().any(|command| matches!(
+        command,
+        WorkerCommand::Cancel { task_id, reason } if *task_id == id && reason == "Lease expired"
+    )));
+    let workers = manager.send(GetWorkers).await.unwrap();
+    assert!(workers[0].active_tasks.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_disconnected_worker_task_is_retried_elsewhere() {
+    let (manager, clock) = manual_manager();
+    let (first_id, _first) = leasing_worker(&manager, "first").await;
+    let id = manager
+        .send(remote_long_task(Some(RetryPolicy {
+            max_attempts: 3,
+            backoff_ms: 0,
+        })))
+        .await
+        .unwrap();
+
+    manager
+        .send(WorkerDisconnected {
+            worker_id: first_id,
+        })
+        .await
+        .unwrap();
+    let task = advance(&manager, &clock, id, 0).await;
+    assert_eq!(task.status, TaskStatus::Queued);
+    assert_eq!(task.attempt, 2);
+    assert_eq!(
+        task.attempt_errors,
+        vec!["attempt 1: Worker 'first' disconnected".to_string()]
+    );
+
+    let (second_id, _second) = leasing_worker(&manager, "second").await;
+    let task = advance(&manager, &clock, id, 0).await;
+    assert_eq!(task.worker.as_deref(), Some("second"));
+    manager
+        .send(WorkerReport {
+            worker_id: second_id,
+            request: WorkerRequest::Complete {
+                task_id: id,
+                result: "done on retry".to_string(),
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    let task = manager.send(GetTask { id }).await.unwrap().unwrap();
+    assert_eq!(task.status, TaskStatus::Completed);
+    assert_eq!(task.attempt, 2);
+    assert_eq!(task.attempt_errors.len(), 1);
+}
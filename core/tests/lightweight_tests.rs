@@ -0,0 +1,203 @@
+use actix::Addr;
+use task_core::*;
+
+mod common;
+use common::*;
+
+fn lightweight_manager(mode: ExecutionMode) -> (Addr<TaskManagerActor>, ManualClock) {
+    manual_manager_with(|manager| manager.with_seed(7).with_execution_mode(mode))
+}
+
+// Runs the task in 100ms steps until it is done
+async fn run_to_end(mode: ExecutionMode, spec: CreateTask) -> TaskMetadata {
+    let (manager, clock) = lightweight_manager(mode);
+    let id = manager.send(spec).await.unwrap();
+    for _ in 0..200 {
+        let task = advance(&manager, &clock, id, 100).await;
+        if !task.status.is_active() {
+            return task;
+        }
+    }
+    panic!("task {} never finished", id);
+}
+
+fn task(task_type: TaskType, faults: Option<FaultProfile>) -> CreateTask {
+    CreateTask {
+        name: "Light".to_string(),
+        message: "Runs without an actor".to_string(),
+        task_type,
+        faults,
+        ..Default::default()
+    }
+}
+
+fn quick(duration_ms: u64) -> TaskType {
+    TaskType::Quick {
+        timeout_ms: Some(1000),
+        duration: Some(DurationDistribution::Constant { ms: duration_ms }),
+    }
+}
+
+#[actix_rt::test]
+async fn test_outcomes_match_actor_mode() {
+    let specs = vec![
+        task(
+            quick(450),
+            Some(FaultProfile {
+                progress: Some(ProgressFault {
+                    steps: 3,
+                    slowdown: 2.0,
+                    stall_at: None,
+                }),
+                ..Default::default()
+            }),
+        ),
+        task(
+            TaskType::Error {
+                timeout_ms: Some(1000),
+                error_type: ErrorType::Immediate,
+            },
+            None,
+        ),
+        task(
+            TaskType::Error {
+                timeout_ms: Some(1000),
+                error_type: ErrorType::NetworkError,
+            },
+            None,
+        ),
+        task(
+            TaskType::Error {
+                timeout_ms: Some(1000),
+                error_type: ErrorType::Timeout,
+            },
+            None,
+        ),
+        task(
+            quick(450),
+            Some(FaultProfile {
+                panic_probability: 1.0,
+                ..Default::default()
+            }),
+        ),
+        task(
+            quick(450),
+            Some(FaultProfile {
+                progress: Some(ProgressFault {
+                    steps: 4,
+                    slowdown: 1.0,
+                    stall_at: Some(0.5),
+                }),
+                ..Default::default()
+            }),
+        ),
+        CreateTask {
+            retry: Some(RetryPolicy {
+                max_attempts: 2,
+                backoff_ms: 200,
+            }),
+            ..task(
+                quick(450),
+                Some(FaultProfile {
+                    heartbeat_loss_probability: 1.0,
+                    ..Default::default()
+                }),
+            )
+        },
+    ];
+
+    for spec in specs {
+        let actor = run_to_end(ExecutionMode::Actor, spec.clone()).await;
+        let light = run_to_end(ExecutionMode::Lightweight, spec).await;
+        let outcome = |task: &TaskMetadata| {
+            (
+                task.status.clone(),
+                task.result.clone(),
+                task.error.clone(),
+                task.progress,
+                task.actual_duration_ms,
+                task.was_timeout(),
+                task.faults.clone(),
+                task.attempt,
+                task.attempt_errors.clone(),
+                task.lease_expires_at,
+            )
+        };
+        assert_eq!(outcome(&light), outcome(&actor));
+    }
+}
+
+#[actix_rt::test]
+async fn test_heartbeats_keep_a_long_task_leased() {
+    let (manager, clock) = lightweight_manager(ExecutionMode::Lightweight);
+    let id = manager
+        .send(task(
+            TaskType::Long {
+                timeout_ms: Some(30_000),
+                duration: Some(DurationDistribution::Constant { ms: 20_000 }),
+            },
+            None,
+        ))
+        .await
+        .unwrap();
+
+    let mut task = advance(&manager, &clock, id, 0).await;
+    for _ in 0..15 {
+        task = advance(&manager, &clock, id, 1000).await;
+        assert_eq!(task.status, TaskStatus::InProgress);
+    }
+    assert!(task.lease_expires_at.unwrap() > clock.now());
+
+    for _ in 0..5 {
+        task = advance(&manager, &clock, id, 1000).await;
+    }
+    assert_eq!(task.status, TaskStatus::Completed);
+    assert_eq!(task.actual_duration_ms, Some(20_000));
+    assert!(task.lease_expires_at.is_none());
+}
+
+#[actix_rt::test]
+async fn test_cancelled_task_stays_cancelled() {
+    let (manager, clock) = lightweight_manager(ExecutionMode::Lightweight);
+    let id = manager.send(task(quick(450), None)).await.unwrap();
+    advance(&manager, &clock, id, 200).await;
+
+    assert!(manager.send(CancelTaskById { id }).await.unwrap());
+    let task = advance(&manager, &clock, id, 2000).await;
+    assert!(task.was_cancelled());
+    assert!(!task.was_timeout());
+    assert_eq!(task.actual_duration_ms, Some(200));
+    assert!(task.lease_expires_at.is_none());
+}
+
+async fn run_many_tasks(count: usize) {
+    let (manager, clock) = lightweight_manager(ExecutionMode::Lightweight);
+    let mut ids = Vec::with_capacity(count);
+    for _ in 0..count {
+        ids.push(manager.send(task(quick(450), None)).await.unwrap());
+    }
+    settle().await;
+
+    // One sleep per work future plus the wheel's, no per-task timers
+    assert_eq!(clock.pending_sleepers(), count + 1);
+
+    advance(&manager, &clock, ids[0], 500).await;
+    let tasks = manager.send(GetAllTasks).await.unwrap();
+    assert_eq!(tasks.len(), count);
+    assert!(tasks
+        .iter()
+        .all(|task| task.status == TaskStatus::Completed));
+}
+
+#[actix_rt::test]
+async fn test_many_tasks_share_one_timer() {
+    run_many_tasks(10_000).await;
+}
+
+// The mode's target, about a minute in a debug build, run it with
+// `cargo test --release -- --ignored`
+#[actix_rt::test]
+#[ignore]
+async fn test_hundred_thousand_tasks_share_one_timer() {
+    run_many_tasks(100_000).await;
+}
@@ -0,0 +1,237 @@
+use actix::{Actor, Context, Handler, MessageResult};
+use std::sync::Arc;
+use std::time::Duration;
+use task_core::*;
+use uuid::Uuid;
+
+mod common;
+use common::*;
+
+// Stands in for the task manager so thousands of arrivals stay cheap
+#[derive(Default)]
+struct TaskSink {
+    received: Vec<CreateTask>,
+}
+
+impl Actor for TaskSink {
+    type Context = Context<Self>;
+}
+
+impl Handler<CreateTask> for TaskSink {
+    type Result = MessageResult<CreateTask>;
+
+    fn handle(&mut self, msg: CreateTask, _ctx: &mut Self::Context) -> Self::Result {
+        self.received.push(msg);
+        MessageResult(Uuid::new_v4())
+    }
+}
+
+#[derive(actix::Message)]
+#[rtype(result = "Vec<CreateTask>")]
+struct Received;
+
+impl Handler<Received> for TaskSink {
+    type Result = MessageResult<Received>;
+
+    fn handle(&mut self, _msg: Received, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.received.clone())
+    }
+}
+
+fn quick_task() -> CreateTask {
+    CreateTask {
+        name: "load quick".to_string(),
+        message: "generated".to_string(),
+        task_type: TaskType::Quick {
+            timeout_ms: Some(1000),
+            duration: None,
+        },
+        ..Default::default()
+    }
+}
+
+fn config(pattern: ArrivalPattern) -> LoadGeneratorConfig {
+    LoadGeneratorConfig {
+        pattern,
+        mix: vec![LoadMixEntry {
+            weight: 1,
+            task: quick_task(),
+        }],
+        duration_secs: None,
+        max_tasks: None,
+        seed: Some(5),
+    }
+}
+
+fn start(clock: &ManualClock) -> (actix::Addr<TaskSink>, actix::Addr<LoadGeneratorActor>) {
+    let sink = TaskSink::default().start();
+    let generator = LoadGeneratorActor::new(sink.clone().recipient())
+        .with_clock(Arc::new(clock.clone()))
+        .start();
+    (sink, generator)
+}
+
+async fn run_for(clock: &ManualClock, secs: u64) {
+    for _ in 0..secs * 10 {
+        clock.advance(Duration::from_millis(100));
+        settle().await;
+    }
+}
+
+#[actix_rt::test]
+async fn test_constant_rate_reports_throughput() {
+    let clock = ManualClock::default();
+    let (sink, generator) = start(&clock);
+
+    generator
+        .send(StartLoad {
+            config: config(ArrivalPattern::Constant { rate_per_sec: 10.0 }),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    run_for(&clock, 2).await;
+
+    let stats = generator.send(GetLoadStats).await.unwrap();
+    assert!(stats.running);
+    assert_eq!(stats.tasks_created, 20);
+    assert!((stats.achieved_rate_per_sec - 10.0).abs() < 0.01);
+    assert!((stats.recent_rate_per_sec - 10.0).abs() < 0.01);
+    assert_eq!(stats.created_by_type.get("quick"), Some(&20));
+
+    let received = sink.send(Received).await.unwrap();
+    assert_eq!(received.len(), 20);
+    assert!(received
+        .iter()
+        .all(|task| task.tags.contains(&"load-generator".to_string())));
+
+    let stopped = generator.send(StopLoad).await.unwrap();
+    assert!(!stopped.running);
+    assert!(stopped.stopped_at.is_some());
+
+    // Nothing arrives after the stop
+    run_for(&clock, 1).await;
+    assert_eq!(sink.send(Received).await.unwrap().len(), 20);
+    assert_eq!(
+        generator.send(GetLoadStats).await.unwrap().tasks_created,
+        20
+    );
+}
+
+#[actix_rt::test]
+async fn test_poisson_rate_and_mix_weights() {
+    let clock = ManualClock::default();
+    let (sink, generator) = start(&clock);
+
+    let mut config = config(ArrivalPattern::Poisson { rate_per_sec: 20.0 });
+    config.mix = vec![
+        LoadMixEntry {
+            weight: 3,
+            task: quick_task(),
+        },
+        LoadMixEntry {
+            weight: 1,
+            task: long_task(None),
+        },
+    ];
+    config.duration_secs = Some(30);
+    generator.send(StartLoad { config }).await.unwrap().unwrap();
+    run_for(&clock, 31).await;
+
+    let stats = generator.send(GetLoadStats).await.unwrap();
+    assert!(!stats.running);
+    // 600 expected, well within three standard deviations
+    assert!(
+        (525..=675).contains(&stats.tasks_created),
+        "created {}",
+        stats.tasks_created
+    );
+    let quick = stats.created_by_type["quick"] as f64;
+    let long = stats.created_by_type["long"] as f64;
+    let share = quick / (quick + long);
+    assert!((0.68..=0.82).contains(&share), "quick share was {}", share);
+    assert_eq!(
+        sink.send(Received).await.unwrap().len() as u64,
+        stats.tasks_created
+    );
+}
+
+#[actix_rt::test]
+async fn test_bursty_ramp_adds_bursts_and_stops_at_max_tasks() {
+    let clock = ManualClock::default();
+    let (_sink, generator) = start(&clock);
+
+    let mut config = config(ArrivalPattern::BurstyRamp {
+        start_rate_per_sec: 1.0,
+        peak_rate_per_sec: 10.0,
+        ramp_secs: 10,
+        burst_every_secs: 5,
+        burst_size: 20,
+    });
+    config.max_tasks = Some(200);
+    generator.send(StartLoad { config }).await.unwrap().unwrap();
+
+    // The first burst lands together with the arrival that passes 5s
+    run_for(&clock, 4).await;
+    let before_burst = generator.send(GetLoadStats).await.unwrap().tasks_created;
+    run_for(&clock, 2).await;
+    let after_burst = generator.send(GetLoadStats).await.unwrap().tasks_created;
+    assert!(after_burst >= before_burst + 20);
+
+    let stats = generator.send(GetLoadStats).await.unwrap();
+    assert!(stats.target_rate_per_sec > 5.0 && stats.target_rate_per_sec < 10.0);
+
+    run_for(&clock, 30).await;
+    let stats = generator.send(GetLoadStats).await.unwrap();
+    assert!(!stats.running);
+    assert_eq!(stats.tasks_created, 200);
+}
+
+#[actix_rt::test]
+async fn test_invalid_configs_are_rejected() {
+    let clock = ManualClock::default();
+    let (_sink, generator) = start(&clock);
+
+    let result = generator
+        .send(StartLoad {
+            config: config(ArrivalPattern::Constant { rate_per_sec: 0.0 }),
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        result.unwrap_err().error_type,
+        ApiErrorType::ValidationError
+    );
+
+    let mut empty_mix = config(ArrivalPattern::Poisson { rate_per_sec: 1.0 });
+    empty_mix.mix.clear();
+    let result = generator
+        .send(StartLoad { config: empty_mix })
+        .await
+        .unwrap();
+    assert!(result.is_err());
+
+    assert!(!generator.send(GetLoadStats).await.unwrap().running);
+}
+
+#[actix_rt::test]
+async fn test_load_tasks_reach_the_manager() {
+    let clock = ManualClock::default();
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .start();
+    let generator = LoadGeneratorActor::new(manager.clone().recipient())
+        .with_clock(Arc::new(clock.clone()))
+        .start();
+
+    let mut config = config(ArrivalPattern::Constant { rate_per_sec: 5.0 });
+    config.max_tasks = Some(3);
+    generator.send(StartLoad { config }).await.unwrap().unwrap();
+    run_for(&clock, 1).await;
+
+    let tasks = manager.send(GetAllTasks).await.unwrap();
+    assert_eq!(tasks.len(), 3);
+    assert!(tasks
+        .iter()
+        .all(|task| task.tags.contains(&"load-generator".to_string())));
+}
@@ -74,6 +74,7 @@ async fn test_start_task_message() {
     // Send start message with very short duration
     addr.send(StartTask {
         duration: Duration::from_millis(50),
+        ..Default::default()
     })
     .await
     .unwrap();
@@ -7,7 +7,11 @@ fn test_create_task_message_serialization() {
     let msg = CreateTask {
         name: "Test Task".to_string(),
         message: "Test message".to_string(),
-        task_type: TaskType::Quick { timeout_ms: None },
+        task_type: TaskType::Quick {
+            timeout_ms: None,
+            duration: None,
+        },
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&msg).unwrap();
@@ -20,8 +24,14 @@ fn test_create_task_message_serialization() {
 #[test]
 fn test_task_type_serialization() {
     let types = vec![
-        TaskType::Quick { timeout_ms: None },
-        TaskType::Long { timeout_ms: None },
+        TaskType::Quick {
+            timeout_ms: None,
+            duration: None,
+        },
+        TaskType::Long {
+            timeout_ms: None,
+            duration: None,
+        },
         TaskType::Error {
             timeout_ms: None,
             error_type: ErrorType::Immediate,
@@ -42,6 +52,7 @@ fn test_task_type_serialization() {
 fn test_start_task_duration_serialization() {
     let msg = StartTask {
         duration: Duration::from_secs(5),
+        ..Default::default()
     };
 
     let json = serde_json::to_string(&msg).unwrap();
@@ -84,13 +95,16 @@ fn test_api_response_serialization() {
     assert!(deserialized.error.is_none());
 
     // Test error response
-    let error_response: ApiResponse<String> = ApiResponse::error("test error".to_string());
+    let error_response: ApiResponse<String> =
+        ApiResponse::error(ApiError::internal_error("test error".to_string()));
     let json = serde_json::to_string(&error_response).unwrap();
     let deserialized: ApiResponse<String> = serde_json::from_str(&json).unwrap();
 
     assert!(!deserialized.success);
     assert!(deserialized.data.is_none());
-    assert_eq!(deserialized.error, Some("test error".to_string()));
+    let error = deserialized.error.unwrap();
+    assert_eq!(error.error_type, ApiErrorType::InternalError);
+    assert_eq!(error.message, "test error");
 }
 
 #[test]
@@ -125,11 +139,13 @@ fn test_task_list_response_serialization() {
         actual_duration_ms: Some(1000),
         cancelled_at: None,
         timeout_at: None,
+        ..Default::default()
     };
 
     let response = TaskListResponse {
         tasks: vec![metadata],
         total: 1,
+        next_cursor: None,
     };
 
     let json = serde_json::to_string(&response).unwrap();
@@ -0,0 +1,187 @@
+use actix::Actor;
+use std::sync::Arc;
+use std::time::Duration;
+use task_core::*;
+use uuid::Uuid;
+
+mod common;
+use common::*;
+
+fn task(name: &str, task_type: TaskType) -> CreateTask {
+    CreateTask {
+        name: name.to_string(),
+        message: "Counted on /metrics".to_string(),
+        task_type,
+        ..Default::default()
+    }
+}
+
+// The value of the sample with exactly these labels
+fn value(text: &str, series: &str) -> Option<f64> {
+    text.lines()
+        .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+        .map(|value| value.parse().unwrap())
+}
+
+#[actix_rt::test]
+async fn test_task_events_and_durations_are_counted_by_type() {
+    let clock = ManualClock::default();
+    let metrics = Metrics::new();
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_snapshot_interval(INTERVAL)
+        .with_metrics(metrics.clone())
+        .start();
+
+    for name in ["First", "Second"] {
+        let quick = TaskType::Quick {
+            timeout_ms: Some(60_000),
+            duration: Some(DurationDistribution::Constant { ms: 1_000 }),
+        };
+        manager.send(task(name, quick)).await.unwrap();
+    }
+    let timing_out = TaskType::Error {
+        timeout_ms: Some(2_000),
+        error_type: ErrorType::Timeout,
+    };
+    manager.send(task("Slow", timing_out)).await.unwrap();
+    let long = || TaskType::Long {
+        timeout_ms: Some(3_600_000),
+        duration: None,
+    };
+    let cancelled = manager.send(task("Cancelled", long())).await.unwrap();
+    manager.send(task("Running", long())).await.unwrap();
+    settle().await;
+    published().await;
+
+    let text = metrics.render(&[]);
+    assert_eq!(
+        value(
+            &text,
+            "task_overlord_tasks_created_total{task_type=\"quick\"}"
+        ),
+        Some(2.0)
+    );
+    assert_eq!(
+        value(&text, "task_overlord_tasks_in_progress{task_type=\"long\"}"),
+        Some(2.0)
+    );
+
+    manager
+        .send(CancelTaskById { id: cancelled })
+        .await
+        .unwrap();
+    for _ in 0..2 {
+        clock.advance(Duration::from_secs(1));
+        settle().await;
+    }
+    published().await;
+
+    let text = metrics.render(&[]);
+    for (series, expected) in [
+        (
+            "task_overlord_tasks_completed_total{task_type=\"quick\"}",
+            2.0,
+        ),
+        ("task_overlord_tasks_failed_total{task_type=\"error\"}", 1.0),
+        (
+            "task_overlord_tasks_cancelled_total{task_type=\"long\"}",
+            1.0,
+        ),
+        ("task_overlord_tasks_in_progress{task_type=\"long\"}", 1.0),
+        ("task_overlord_tasks_in_progress{task_type=\"quick\"}", 0.0),
+        (
+            "task_overlord_task_duration_seconds_bucket{task_type=\"quick\",le=\"0.5\"}",
+            0.0,
+        ),
+        (
+            "task_overlord_task_duration_seconds_bucket{task_type=\"quick\",le=\"1\"}",
+            2.0,
+        ),
+        (
+            "task_overlord_task_duration_seconds_bucket{task_type=\"error\",le=\"+Inf\"}",
+            1.0,
+        ),
+        (
+            "task_overlord_task_duration_seconds_sum{task_type=\"quick\"}",
+            2.0,
+        ),
+        (
+            "task_overlord_task_duration_seconds_count{task_type=\"error\"}",
+            1.0,
+        ),
+    ] {
+        assert_eq!(value(&text, series), Some(expected), "{}", series);
+    }
+    // Finishing does not count the tasks as created again
+    assert_eq!(
+        value(
+            &text,
+            "task_overlord_tasks_created_total{task_type=\"quick\"}"
+        ),
+        Some(2.0)
+    );
+    assert!(text.contains("# TYPE task_overlord_task_duration_seconds histogram\n"));
+}
+
+#[actix_rt::test]
+async fn test_websocket_sessions_and_mailboxes_are_measured() {
+    let metrics = Metrics::new();
+    let monitor = WebSocketMonitorActor::new()
+        .with_metrics(metrics.clone())
+        .start();
+    for (message_type, size_bytes) in [("task_grid_update", 100), ("task_grid_update", 50)] {
+        monitor
+            .send(LogWebSocketMessage {
+                session_id: Uuid::new_v4(),
+                direction: WsMessageDirection::Outgoing,
+                message_type: message_type.to_string(),
+                content: String::new(),
+                size_bytes,
+            })
+            .await
+            .unwrap();
+    }
+    metrics.record_websocket_message(&WsMessageDirection::Incoming, "say \"hi\"", 7);
+    metrics.liveview_session_started();
+    metrics.liveview_session_started();
+    metrics.liveview_session_stopped();
+
+    let router = TaskManagerRouter::start_shards(4, |_| TaskManagerActor::new()).start();
+    let latency = router.send(MeasureMailboxLatency).await.unwrap();
+    assert_eq!(
+        latency.iter().map(|(shard, _)| *shard).collect::<Vec<_>>(),
+        vec![0, 1, 2, 3]
+    );
+
+    let text = metrics.render(&latency);
+    let grid = "{direction=\"outgoing\",message_type=\"task_grid_update\"}";
+    assert_eq!(
+        value(
+            &text,
+            &format!("task_overlord_websocket_messages_total{}", grid)
+        ),
+        Some(2.0)
+    );
+    assert_eq!(
+        value(
+            &text,
+            &format!("task_overlord_websocket_bytes_total{}", grid)
+        ),
+        Some(150.0)
+    );
+    assert_eq!(
+        value(
+            &text,
+            "task_overlord_websocket_bytes_total{direction=\"incoming\",message_type=\"say \\\"hi\\\"\"}"
+        ),
+        Some(7.0)
+    );
+    assert_eq!(value(&text, "task_overlord_liveview_sessions"), Some(1.0));
+    let probe = value(
+        &text,
+        "task_overlord_manager_mailbox_latency_seconds{shard=\"3\"}",
+    )
+    .unwrap();
+    assert!(probe > 0.0 && probe < 1.0);
+}
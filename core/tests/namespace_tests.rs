@@ -0,0 +1,232 @@
+use actix::Addr;
+use std::time::Duration;
+use task_core::*;
+use uuid::Uuid;
+
+mod common;
+use common::*;
+
+fn task_in(namespace: &str, name: &str) -> CreateTask {
+    CreateTask {
+        name: name.to_string(),
+        message: "Namespaced".to_string(),
+        task_type: TaskType::Quick {
+            timeout_ms: Some(1000),
+            duration: None,
+        },
+        namespace: Some(namespace.to_string()),
+        ..Default::default()
+    }
+}
+
+async fn status(manager: &Addr<TaskManagerActor>, id: Uuid) -> TaskMetadata {
+    manager.send(GetTask { id }).await.unwrap().unwrap()
+}
+
+async fn configure(
+    manager: &Addr<TaskManagerActor>,
+    name: &str,
+    config: NamespaceConfig,
+) -> Result<NamespaceInfo, ApiError> {
+    manager
+        .send(ConfigureNamespace {
+            name: name.to_string(),
+            config,
+        })
+        .await
+        .unwrap()
+}
+
+#[actix_rt::test]
+async fn test_concurrency_limit_holds_tasks_until_a_slot_frees() {
+    let (manager, clock) = manual_manager();
+    configure(
+        &manager,
+        "team-a",
+        NamespaceConfig {
+            max_concurrent: Some(1),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let first = manager.send(task_in("team-a", "first")).await.unwrap();
+    let second = manager.send(task_in("team-a", "second")).await.unwrap();
+    let third = manager.send(task_in("team-a", "third")).await.unwrap();
+    // Other namespaces are not limited by team-a
+    let other = manager.send(task_in("team-b", "other")).await.unwrap();
+
+    assert_eq!(status(&manager, first).await.status, TaskStatus::InProgress);
+    assert_eq!(status(&manager, other).await.status, TaskStatus::InProgress);
+    let waiting = status(&manager, second).await;
+    assert_eq!(waiting.status, TaskStatus::Queued);
+    assert_eq!(waiting.namespace, "team-a");
+    assert_eq!(
+        waiting.queue_reason.as_deref(),
+        Some("Namespace 'team-a' is at its concurrency limit of 1")
+    );
+
+    let info = manager
+        .send(GetNamespace {
+            name: "team-a".to_string(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((info.running, info.waiting, info.total), (1, 2, 3));
+
+    // Cancelling a waiting task skips it, finishing the first admits the next
+    assert!(manager.send(CancelTaskById { id: second }).await.unwrap());
+    settle().await;
+    clock.advance(Duration::from_millis(1000));
+    settle().await;
+
+    assert_eq!(status(&manager, first).await.status, TaskStatus::Completed);
+    assert!(status(&manager, second).await.was_cancelled());
+    let admitted = status(&manager, third).await;
+    assert_eq!(admitted.status, TaskStatus::InProgress);
+    assert!(admitted.queue_reason.is_none());
+
+    settle().await;
+    clock.advance(Duration::from_millis(1000));
+    settle().await;
+    assert_eq!(status(&manager, third).await.status, TaskStatus::Completed);
+}
+
+#[actix_rt::test]
+async fn test_private_namespaces_are_hidden_from_the_shared_view() {
+    let (manager, _clock) = manual_manager();
+    configure(
+        &manager,
+        "secret",
+        NamespaceConfig {
+            visibility: Visibility::Private,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let hidden = manager.send(task_in("secret", "hidden")).await.unwrap();
+    let shared = manager
+        .send(CreateTask {
+            namespace: None,
+            ..task_in("", "shared")
+        })
+        .await
+        .unwrap();
+
+    let visible = manager
+        .send(GetNamespaceTasks { namespace: None })
+        .await
+        .unwrap();
+    assert_eq!(visible.len(), 1);
+    assert_eq!(visible[0].id, shared);
+    assert_eq!(visible[0].namespace, DEFAULT_NAMESPACE);
+
+    let scoped = manager
+        .send(GetNamespaceTasks {
+            namespace: Some("secret".to_string()),
+        })
+        .await
+        .unwrap();
+    assert_eq!(scoped.len(), 1);
+    assert_eq!(scoped[0].id, hidden);
+
+    let lookup = |id, namespace: Option<&str>| GetNamespaceTask {
+        id,
+        namespace: namespace.map(str::to_string),
+    };
+    assert!(manager.send(lookup(hidden, None)).await.unwrap().is_none());
+    assert!(manager
+        .send(lookup(hidden, Some("secret")))
+        .await
+        .unwrap()
+        .is_some());
+    assert!(manager
+        .send(lookup(shared, Some("secret")))
+        .await
+        .unwrap()
+        .is_none());
+
+    let names: Vec<String> = manager
+        .send(ListNamespaces)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|namespace| namespace.name)
+        .collect();
+    assert_eq!(names, vec!["default".to_string()]);
+    // Still there when asked for by name
+    assert!(manager
+        .send(GetNamespace {
+            name: "secret".to_string()
+        })
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[actix_rt::test]
+async fn test_namespace_retention_drops_finished_tasks() {
+    let (manager, clock) = manual_manager();
+    configure(
+        &manager,
+        "short-lived",
+        NamespaceConfig {
+            retention_secs: Some(60),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let expiring = manager
+        .send(task_in("short-lived", "expiring"))
+        .await
+        .unwrap();
+    let kept = manager.send(task_in("default", "kept")).await.unwrap();
+    settle().await;
+    clock.advance(Duration::from_millis(1000));
+    settle().await;
+    assert_eq!(
+        status(&manager, expiring).await.status,
+        TaskStatus::Completed
+    );
+
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(manager.send(ArchiveExpiredTasks).await.unwrap(), 1);
+    assert!(manager
+        .send(GetTask { id: expiring })
+        .await
+        .unwrap()
+        .is_none());
+    // Without an archive the default namespace keeps everything
+    assert_eq!(status(&manager, kept).await.status, TaskStatus::Completed);
+}
+
+#[actix_rt::test]
+async fn test_namespace_configuration_is_validated() {
+    let (manager, _clock) = manual_manager();
+
+    let bad_name = configure(&manager, "Team A", NamespaceConfig::default()).await;
+    assert_eq!(
+        bad_name.unwrap_err().error_type,
+        ApiErrorType::ValidationError
+    );
+
+    let bad_limit = configure(
+        &manager,
+        "team-a",
+        NamespaceConfig {
+            max_concurrent: Some(0),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(
+        bad_limit.unwrap_err().error_type,
+        ApiErrorType::ValidationError
+    );
+}
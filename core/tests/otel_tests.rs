@@ -0,0 +1,197 @@
+use actix::Actor;
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+use task_core::*;
+use tracing_subscriber::layer::SubscriberExt;
+
+mod common;
+use common::*;
+
+// What a browser would send for its "create task" click
+const BROWSER_TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const BROWSER_SPAN_ID: &str = "00f067aa0ba902b7";
+
+// Stands in for an OpenTelemetry collector's OTLP/HTTP receiver, every
+// request body it gets is sent on the channel
+fn start_collector() -> (String, mpsc::Receiver<(String, Value)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let path = request_line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or_default()
+                .to_string();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            let _ = sender.send((path, serde_json::from_slice(&body).unwrap()));
+        }
+    });
+    (endpoint, receiver)
+}
+
+// Every span in the export requests received so far
+fn exported_spans(receiver: &mpsc::Receiver<(String, Value)>) -> Vec<Value> {
+    let mut spans = Vec::new();
+    while let Ok((path, body)) = receiver.recv_timeout(Duration::from_secs(2)) {
+        assert_eq!(path, "/v1/traces");
+        for resource in body["resourceSpans"].as_array().unwrap() {
+            assert!(attributes(&resource["resource"])
+                .contains(&("service.name".to_string(), Value::from("otel-tests"))));
+            for scope in resource["scopeSpans"].as_array().unwrap() {
+                spans.extend(scope["spans"].as_array().unwrap().iter().cloned());
+            }
+        }
+    }
+    spans
+}
+
+fn attributes(value: &Value) -> Vec<(String, Value)> {
+    value["attributes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|attribute| {
+            let value = attribute["value"]
+                .as_object()
+                .and_then(|value| value.values().next())
+                .cloned()
+                .unwrap_or_default();
+            (attribute["key"].as_str().unwrap().to_string(), value)
+        })
+        .collect()
+}
+
+fn attribute(span: &Value, key: &str) -> Option<Value> {
+    attributes(span)
+        .into_iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value)
+}
+
+fn quick(name: &str, traceparent: Option<&str>) -> CreateTask {
+    CreateTask {
+        name: name.to_string(),
+        message: "Exported".to_string(),
+        task_type: TaskType::Quick {
+            timeout_ms: Some(60_000),
+            duration: Some(DurationDistribution::Constant { ms: 1_000 }),
+        },
+        traceparent: traceparent.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[actix_rt::test]
+async fn test_task_spans_continue_the_browser_trace() {
+    let (endpoint, receiver) = start_collector();
+    let exporter = OtlpExporter::start(
+        &OtlpConfig::new(endpoint)
+            .with_protocol(OtlpProtocol::Json)
+            .with_service_name("otel-tests"),
+    )
+    .unwrap();
+    let subscriber = tracing_subscriber::registry().with(exporter.layer());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let clock = ManualClock::default();
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_snapshot_interval(INTERVAL)
+        .start();
+    let traceparent = format!("00-{}-{}-01", BROWSER_TRACE_ID, BROWSER_SPAN_ID);
+    let clicked = manager
+        .send(quick("Clicked", Some(&traceparent)))
+        .await
+        .unwrap();
+    let untraced = manager.send(quick("Untraced", None)).await.unwrap();
+    let garbled = manager
+        .send(quick("Garbled", Some("00-not-a-trace-01")))
+        .await
+        .unwrap();
+    settle().await;
+    clock.advance(Duration::from_secs(1));
+    settle().await;
+    // Task spans end once the manager publishes the final status
+    published().await;
+    exporter.flush().unwrap();
+
+    let spans = exported_spans(&receiver);
+    let span_of = |id: uuid::Uuid| {
+        spans
+            .iter()
+            .find(|span| attribute(span, "task.id") == Some(Value::from(id.to_string())))
+            .unwrap_or_else(|| panic!("no span for task {}", id))
+    };
+
+    let span = span_of(clicked);
+    assert_eq!(span["name"], "task");
+    assert_eq!(span["traceId"], BROWSER_TRACE_ID);
+    assert_eq!(span["parentSpanId"], BROWSER_SPAN_ID);
+    assert_eq!(attribute(span, "task.type"), Some(Value::from("quick")));
+    assert_eq!(
+        attribute(span, "task.namespace"),
+        Some(Value::from(DEFAULT_NAMESPACE))
+    );
+    let events: Vec<&str> = span["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["name"].as_str().unwrap())
+        .collect();
+    assert!(events.contains(&"task created"));
+    assert!(events.contains(&"task completed"));
+
+    // Without a usable traceparent a task starts a trace of its own
+    for id in [untraced, garbled] {
+        let span = span_of(id);
+        assert_ne!(span["traceId"], BROWSER_TRACE_ID);
+        assert_eq!(span["parentSpanId"].as_str().unwrap_or_default(), "");
+    }
+    exporter.shutdown().unwrap();
+}
+
+#[test]
+fn test_config_from_env() {
+    // Only this test touches these variables
+    std::env::remove_var("OTEL_EXPORTER_OTLP_ENDPOINT");
+    assert_eq!(OtlpConfig::from_env(), None);
+
+    std::env::set_var("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4318");
+    std::env::set_var("OTEL_EXPORTER_OTLP_PROTOCOL", "http/json");
+    std::env::set_var("OTEL_SERVICE_NAME", "dashboard");
+    assert_eq!(
+        OtlpConfig::from_env(),
+        Some(
+            OtlpConfig::new("http://collector:4318")
+                .with_protocol(OtlpProtocol::Json)
+                .with_service_name("dashboard")
+        )
+    );
+}
@@ -0,0 +1,132 @@
+use actix::Actor;
+use chrono::{TimeZone, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use task_core::*;
+
+mod common;
+use common::*;
+
+fn quick(name: &str, ms: u64) -> CreateTask {
+    CreateTask {
+        name: name.to_string(),
+        message: "Found by a search".to_string(),
+        task_type: TaskType::Quick {
+            timeout_ms: Some(60_000),
+            duration: Some(DurationDistribution::Constant { ms }),
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_terms_fill_the_query() {
+    let now = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+    let task_types = TaskTypeRegistry::with_builtin_types();
+    let query = TaskQuery::parse(
+        r#"status:error TYPE:Custom tag:nightly name:"etl*" duration>5s duration<=2m since:1h"#,
+        now,
+        &task_types,
+    )
+    .unwrap();
+
+    assert_eq!(query.filter.status, Some(TaskStatus::Error));
+    assert_eq!(query.filter.task_type.as_deref(), Some("custom"));
+    assert_eq!(query.filter.tag.as_deref(), Some("nightly"));
+    assert_eq!(query.name.as_deref(), Some("etl*"));
+    assert_eq!(query.min_duration_ms, Some(5_001));
+    assert_eq!(query.max_duration_ms, Some(120_000));
+    assert_eq!(
+        query.filter.started_after,
+        Some(Utc.with_ymd_and_hms(2026, 1, 1, 11, 0, 0).unwrap())
+    );
+
+    let query = TaskQuery::parse(r#"nightly "db backup" duration:1.5s"#, now, &task_types).unwrap();
+    assert_eq!(query.name.as_deref(), Some("nightly db backup"));
+    assert_eq!(query.min_duration_ms, Some(1_500));
+    assert_eq!(query.max_duration_ms, Some(1_500));
+
+    assert_eq!(
+        TaskQuery::parse("  ", now, &task_types).unwrap(),
+        TaskQuery::default()
+    );
+
+    // Types registered at runtime can be searched for like the built-in ones
+    let mut task_types = task_types;
+    task_types.register(TaskTypeDefinition::new("batch", "Batch", |_| {
+        TaskType::default()
+    }));
+    let query = TaskQuery::parse("type:Batch", now, &task_types).unwrap();
+    assert_eq!(query.filter.task_type.as_deref(), Some("batch"));
+}
+
+#[test]
+fn test_invalid_searches_are_rejected() {
+    let now = Utc::now();
+    let task_types = TaskTypeRegistry::with_builtin_types();
+    for q in [
+        "status:sleeping",
+        "type:batch",
+        "owner:me",
+        "status:error status:completed",
+        "status>error",
+        "duration>5y",
+        "duration<0",
+        "name:\"unterminated",
+        "name:",
+        "name:etl backup",
+    ] {
+        let error = TaskQuery::parse(q, now, &task_types).unwrap_err();
+        assert_eq!(error.error_type, ApiErrorType::ValidationError, "{}", q);
+    }
+}
+
+#[actix_rt::test]
+async fn test_name_patterns_and_durations_match_finished_tasks() {
+    let clock = ManualClock::default();
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_snapshot_interval(INTERVAL);
+    let reader = manager.reader();
+    let manager = manager.start();
+
+    let short = manager.send(quick("ETL daily load", 1_000)).await.unwrap();
+    let long = manager.send(quick("etl weekly load", 8_000)).await.unwrap();
+    let other = manager.send(quick("Report load", 8_000)).await.unwrap();
+    for _ in 0..100 {
+        tokio::task::yield_now().await;
+    }
+    clock.advance(Duration::from_millis(1_500));
+    for _ in 0..100 {
+        tokio::task::yield_now().await;
+    }
+    published().await;
+
+    let task_types = TaskTypeRegistry::with_builtin_types();
+    let found = |q: &str| {
+        let mut ids: Vec<_> = reader
+            .query(&TaskQuery::parse(q, Utc::now(), &task_types).unwrap(), None)
+            .tasks
+            .iter()
+            .map(|task| task.id)
+            .collect();
+        ids.sort();
+        ids
+    };
+    let sorted = |mut ids: Vec<uuid::Uuid>| {
+        ids.sort();
+        ids
+    };
+
+    assert_eq!(found(r#"name:"etl*load""#), sorted(vec![short, long]));
+    assert_eq!(found("name:etl*daily*"), vec![short]);
+    assert_eq!(found("name:*load"), sorted(vec![short, long, other]));
+    assert!(found("name:load*").is_empty());
+    // Only the short one has finished
+    assert_eq!(found("duration<5s"), vec![short]);
+    assert_eq!(found("duration>=1s status:completed"), vec![short]);
+    // Measured on the clock, which jumped straight to 1.5s
+    assert_eq!(found("duration:1500"), vec![short]);
+    assert!(found("duration>1.5s").is_empty());
+    assert_eq!(found("weekly"), vec![long]);
+}
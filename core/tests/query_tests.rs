@@ -0,0 +1,164 @@
+use actix::Addr;
+use std::time::Duration;
+use task_core::*;
+use uuid::Uuid;
+
+mod common;
+use common::*;
+
+// One task a second, so start times are distinct
+async fn create_all(
+    manager: &Addr<TaskManagerActor>,
+    clock: &ManualClock,
+    names: &[&str],
+) -> Vec<Uuid> {
+    let mut created = Vec::new();
+    for name in names {
+        created.push(
+            manager
+                .send(CreateTask {
+                    name: name.to_string(),
+                    ..long_task(None)
+                })
+                .await
+                .unwrap(),
+        );
+        clock.advance(Duration::from_secs(1));
+    }
+    published().await;
+    created
+}
+
+// Follows next_cursor until the last page
+fn all_pages(reader: &TaskReader, query: TaskQuery) -> (Vec<Uuid>, usize) {
+    let mut query = query;
+    let mut ids = Vec::new();
+    let mut pages = 0;
+    loop {
+        let page = reader.query(&query, None);
+        pages += 1;
+        ids.extend(page.tasks.iter().map(|task| task.id));
+        match page.next_cursor {
+            Some(cursor) => query.cursor = Some(TaskCursor::decode(&cursor).unwrap()),
+            None => return (ids, pages),
+        }
+    }
+}
+
+#[actix_rt::test]
+async fn test_filters_and_total_cover_every_page() {
+    let (manager, reader, clock) = manual_manager_and_reader();
+    let created = create_all(
+        &manager,
+        &clock,
+        &[
+            "Nightly backup",
+            "Resize images",
+            "Weekly BACKUP",
+            "Backup logs",
+        ],
+    )
+    .await;
+    manager
+        .send(CancelTaskById { id: created[3] })
+        .await
+        .unwrap();
+    published().await;
+
+    let query = TaskQuery {
+        name: Some("backup".to_string()),
+        limit: Some(2),
+        ..Default::default()
+    };
+    let page = reader.query(&query, None);
+    assert_eq!(page.total, 3);
+    assert_eq!(
+        page.tasks.iter().map(|task| task.id).collect::<Vec<_>>(),
+        vec![created[0], created[2]]
+    );
+    assert!(page.next_cursor.is_some());
+
+    let query = TaskQuery {
+        name: Some("backup".to_string()),
+        filter: TaskFilter {
+            status: Some(TaskStatus::InProgress),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let page = reader.query(&query, None);
+    assert_eq!(page.total, 2);
+    assert_eq!(page.tasks.len(), 2);
+    assert!(page.next_cursor.is_none());
+}
+
+#[actix_rt::test]
+async fn test_cursor_pages_through_every_sort() {
+    let (manager, reader, clock) = manual_manager_and_reader();
+    let names = ["delta", "alpha", "echo", "charlie", "bravo", "alpha"];
+    let created = create_all(&manager, &clock, &names).await;
+
+    let (ids, pages) = all_pages(
+        &reader,
+        TaskQuery {
+            limit: Some(2),
+            ..Default::default()
+        },
+    );
+    assert_eq!(ids, created);
+    assert_eq!(pages, 3);
+
+    let (ids, _) = all_pages(
+        &reader,
+        TaskQuery {
+            order: SortOrder::Desc,
+            limit: Some(4),
+            ..Default::default()
+        },
+    );
+    assert_eq!(ids, created.iter().rev().copied().collect::<Vec<_>>());
+
+    // Equal names are ordered by id, and neither is skipped between pages
+    let mut by_name: Vec<(&str, Uuid)> = names.iter().copied().zip(created.clone()).collect();
+    by_name.sort();
+    let (ids, _) = all_pages(
+        &reader,
+        TaskQuery {
+            sort: SortField::Name,
+            limit: Some(1),
+            ..Default::default()
+        },
+    );
+    assert_eq!(ids, by_name.iter().map(|(_, id)| *id).collect::<Vec<_>>());
+}
+
+#[actix_rt::test]
+async fn test_invalid_cursors_and_limits_are_rejected() {
+    let (manager, reader, clock) = manual_manager_and_reader();
+    create_all(&manager, &clock, &["one", "two", "three"]).await;
+
+    let page = reader.query(
+        &TaskQuery {
+            limit: Some(1),
+            ..Default::default()
+        },
+        None,
+    );
+    let cursor = TaskCursor::decode(&page.next_cursor.unwrap()).unwrap();
+    let query = TaskQuery {
+        sort: SortField::Name,
+        cursor: Some(cursor),
+        ..Default::default()
+    };
+    assert!(query.validate().is_err());
+
+    assert!(TaskCursor::decode("not a cursor").is_err());
+    assert!(TaskCursor::decode("abc").is_err());
+    for limit in [0, MAX_PAGE_SIZE + 1] {
+        let query = TaskQuery {
+            limit: Some(limit),
+            ..Default::default()
+        };
+        assert!(query.validate().is_err());
+    }
+}
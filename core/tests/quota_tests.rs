@@ -0,0 +1,194 @@
+use actix::Addr;
+use task_core::*;
+use uuid::Uuid;
+
+mod common;
+use common::*;
+
+fn task_in(namespace: &str, work_ms: u64) -> CreateTask {
+    CreateTask {
+        name: "Metered".to_string(),
+        message: "Counts against a quota".to_string(),
+        task_type: TaskType::Quick {
+            timeout_ms: Some(10_000),
+            duration: Some(DurationDistribution::Constant { ms: work_ms }),
+        },
+        namespace: Some(namespace.to_string()),
+        ..Default::default()
+    }
+}
+
+async fn submit(manager: &Addr<TaskManagerActor>, task: CreateTask) -> Result<Uuid, ApiError> {
+    manager.send(SubmitTask { task }).await.unwrap()
+}
+
+async fn set_quota(manager: &Addr<TaskManagerActor>, name: &str, quota: Quota) {
+    manager
+        .send(ConfigureNamespace {
+            name: name.to_string(),
+            config: NamespaceConfig {
+                quota,
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[actix_rt::test]
+async fn test_active_quota_rejects_until_a_task_finishes() {
+    let (manager, clock) = manual_manager();
+    set_quota(
+        &manager,
+        "noisy",
+        Quota {
+            max_active: Some(2),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    submit(&manager, task_in("noisy", 3000)).await.unwrap();
+    submit(&manager, task_in("noisy", 3000)).await.unwrap();
+    let error = submit(&manager, task_in("noisy", 3000)).await.unwrap_err();
+    assert_eq!(error.error_type, ApiErrorType::ConcurrencyError);
+    assert_eq!(error.error_code, "QUOTA_EXCEEDED");
+    // Bounded by the 10s timeout of the running tasks
+    assert_eq!(error.retry_after_secs(), Some(10));
+
+    // Other namespaces are not affected
+    submit(&manager, task_in("quiet", 3000)).await.unwrap();
+
+    // CreateTask is counted but never rejected
+    manager.send(task_in("noisy", 3000)).await.unwrap();
+
+    advance_clock(&clock, 3000).await;
+    submit(&manager, task_in("noisy", 3000)).await.unwrap();
+}
+
+#[actix_rt::test]
+async fn test_hourly_quota_opens_up_as_the_window_slides() {
+    let (manager, clock) = manual_manager();
+    set_quota(
+        &manager,
+        "batch",
+        Quota {
+            max_tasks_per_hour: Some(2),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    submit(&manager, task_in("batch", 100)).await.unwrap();
+    advance_clock(&clock, 600_000).await;
+    submit(&manager, task_in("batch", 100)).await.unwrap();
+
+    let error = submit(&manager, task_in("batch", 100)).await.unwrap_err();
+    assert_eq!(error.retry_after_secs(), Some(3000));
+    let info = manager
+        .send(GetNamespace {
+            name: "batch".to_string(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(info.usage.tasks_last_hour, 2);
+
+    advance_clock(&clock, 3_000_000).await;
+    submit(&manager, task_in("batch", 100)).await.unwrap();
+}
+
+#[actix_rt::test]
+async fn test_runtime_quota_counts_finished_and_running_work() {
+    let (manager, clock) = manual_manager();
+    set_quota(
+        &manager,
+        "heavy",
+        Quota {
+            max_runtime_secs_per_hour: Some(5),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    submit(&manager, task_in("heavy", 3000)).await.unwrap();
+    advance_clock(&clock, 3000).await;
+    submit(&manager, task_in("heavy", 3000)).await.unwrap();
+    advance_clock(&clock, 2000).await;
+
+    // 3s finished plus 2s of the running task
+    let error = submit(&manager, task_in("heavy", 3000)).await.unwrap_err();
+    assert_eq!(error.error_type, ApiErrorType::ConcurrencyError);
+    let details = error.details.unwrap();
+    assert_eq!(details["quota"], "max_runtime_secs_per_hour");
+    assert_eq!(details["used"], 5);
+    // The finished run leaves the window an hour after it finished
+    assert_eq!(details["retry_after_secs"], 3598);
+}
+
+#[actix_rt::test]
+async fn test_usage_follows_status_changes() {
+    let (manager, clock) = manual_manager();
+    set_quota(
+        &manager,
+        "tracked",
+        Quota {
+            max_active: Some(2),
+            ..Default::default()
+        },
+    )
+    .await;
+    let usage = || async {
+        manager
+            .send(GetNamespace {
+                name: "tracked".to_string(),
+            })
+            .await
+            .unwrap()
+            .unwrap()
+    };
+
+    let cancelled = submit(&manager, task_in("tracked", 3000)).await.unwrap();
+    submit(&manager, task_in("tracked", 1000)).await.unwrap();
+    assert!(submit(&manager, task_in("tracked", 1000)).await.is_err());
+    settle().await;
+    assert_eq!(usage().await.usage.active, 2);
+
+    // Cancelling frees a slot right away
+    assert!(manager
+        .send(CancelTaskById { id: cancelled })
+        .await
+        .unwrap());
+    settle().await;
+    assert_eq!(usage().await.usage.active, 1);
+    submit(&manager, task_in("tracked", 1000)).await.unwrap();
+
+    advance_clock(&clock, 1000).await;
+    let info = usage().await;
+    assert_eq!(info.usage.active, 0);
+    assert_eq!(info.running, 0);
+    assert_eq!(info.total, 3);
+    // Two runs of a second each
+    assert_eq!(info.usage.runtime_secs_last_hour, 2);
+}
+
+#[actix_rt::test]
+async fn test_zero_quota_is_rejected() {
+    let (manager, _clock) = manual_manager();
+    let error = manager
+        .send(ConfigureNamespace {
+            name: "team".to_string(),
+            config: NamespaceConfig {
+                quota: Quota {
+                    max_tasks_per_hour: Some(0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(error.error_type, ApiErrorType::ValidationError);
+}
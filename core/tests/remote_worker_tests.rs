@@ -0,0 +1,504 @@
+use actix::{Actor, Addr, Context, Handler, MessageResult};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use task_core::*;
+use uuid::Uuid;
+
+mod common;
+use common::*;
+
+// Stands in for a worker connection and records what the manager sends
+#[derive(Default)]
+struct FakeWorker {
+    commands: Vec<WorkerCommand>,
+}
+
+impl Actor for FakeWorker {
+    type Context = Context<Self>;
+}
+
+impl Handler<WorkerCommand> for FakeWorker {
+    type Result = ();
+
+    fn handle(&mut self, msg: WorkerCommand, _ctx: &mut Self::Context) -> Self::Result {
+        self.commands.push(msg);
+    }
+}
+
+#[derive(actix::Message)]
+#[rtype(result = "Vec<WorkerCommand>")]
+struct Commands;
+
+impl Handler<Commands> for FakeWorker {
+    type Result = MessageResult<Commands>;
+
+    fn handle(&mut self, _msg: Commands, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.commands.clone())
+    }
+}
+
+async fn register(
+    manager: &Addr<TaskManagerActor>,
+    name: &str,
+    capabilities: &[&str],
+) -> (Uuid, Addr<FakeWorker>) {
+    let worker = FakeWorker::default().start();
+    let worker_id = manager
+        .send(RegisterWorker {
+            name: name.to_string(),
+            capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+            labels: BTreeMap::new(),
+            max_concurrent: 2,
+            recipient: worker.clone().recipient(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    (worker_id, worker)
+}
+
+async fn report(
+    manager: &Addr<TaskManagerActor>,
+    worker_id: Uuid,
+    request: WorkerRequest,
+) -> Result<(), ApiError> {
+    manager
+        .send(WorkerReport { worker_id, request })
+        .await
+        .unwrap()
+}
+
+async fn assigned(worker: &Addr<FakeWorker>) -> Vec<TaskAssignment> {
+    settle().await;
+    worker
+        .send(Commands)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter_map(|command| match command {
+            WorkerCommand::Assign { task } => Some(*task),
+            _ => None,
+        })
+        .collect()
+}
+
+fn remote_task(name: &str, task_type: TaskType) -> CreateTask {
+    CreateTask {
+        name: name.to_string(),
+        message: "Runs on a worker".to_string(),
+        task_type,
+        executor: Executor::Remote,
+        ..Default::default()
+    }
+}
+
+fn quick() -> TaskType {
+    TaskType::Quick {
+        timeout_ms: Some(1000),
+        duration: None,
+    }
+}
+
+#[actix_rt::test]
+async fn test_remote_task_is_queued_until_leased() {
+    let manager = TaskManagerActor::new().start();
+    let task_id = manager.send(remote_task("remote", quick())).await.unwrap();
+
+    let task = manager
+        .send(GetTask { id: task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.status, TaskStatus::Queued);
+    assert!(task.queued_at.is_some());
+
+    let (worker_id, worker) = register(&manager, "worker-1", &[]).await;
+    settle().await;
+    let commands = worker.send(Commands).await.unwrap();
+    assert!(matches!(
+        commands[0],
+        WorkerCommand::Registered { worker_id: id } if id == worker_id
+    ));
+    // Registering alone does not hand out work
+    assert!(assigned(&worker).await.is_empty());
+
+    report(&manager, worker_id, WorkerRequest::Lease { max_tasks: 1 })
+        .await
+        .unwrap();
+    let tasks = assigned(&worker).await;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].task_id, task_id);
+    assert_eq!(tasks[0].duration_ms, 750);
+
+    let task = manager
+        .send(GetTask { id: task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.status, TaskStatus::InProgress);
+    assert_eq!(task.worker.as_deref(), Some("worker-1"));
+
+    report(
+        &manager,
+        worker_id,
+        WorkerRequest::Progress {
+            task_id,
+            progress: 0.5,
+        },
+    )
+    .await
+    .unwrap();
+    report(
+        &manager,
+        worker_id,
+        WorkerRequest::Complete {
+            task_id,
+            result: "done remotely".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let task = manager
+        .send(GetTask { id: task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.status, TaskStatus::Completed);
+    assert_eq!(task.result.as_deref(), Some("done remotely"));
+
+    let workers = manager.send(GetWorkers).await.unwrap();
+    assert_eq!(workers[0].completed, 1);
+    assert!(workers[0].active_tasks.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_capabilities_route_tasks() {
+    let manager = TaskManagerActor::new().start();
+    let (quick_id, quick_worker) = register(&manager, "quick-only", &["quick"]).await;
+    let (long_id, long_worker) = register(&manager, "long-only", &["long"]).await;
+    for worker_id in [quick_id, long_id] {
+        report(&manager, worker_id, WorkerRequest::Lease { max_tasks: 2 })
+            .await
+            .unwrap();
+    }
+
+    let long_task = manager
+        .send(remote_task(
+            "long",
+            TaskType::Long {
+                timeout_ms: Some(5000),
+                duration: None,
+            },
+        ))
+        .await
+        .unwrap();
+    let error_task = manager
+        .send(remote_task(
+            "error",
+            TaskType::Error {
+                timeout_ms: None,
+                error_type: ErrorType::NetworkError,
+            },
+        ))
+        .await
+        .unwrap();
+
+    assert!(assigned(&quick_worker).await.is_empty());
+    let long_tasks = assigned(&long_worker).await;
+    assert_eq!(long_tasks.len(), 1);
+    assert_eq!(long_tasks[0].task_id, long_task);
+
+    // Nobody takes error tasks, it stays queued and can be cancelled there
+    let task = manager
+        .send(GetTask { id: error_task })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.status, TaskStatus::Queued);
+    assert!(manager
+        .send(CancelTaskById { id: error_task })
+        .await
+        .unwrap());
+    let task = manager
+        .send(GetTask { id: error_task })
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(task.was_cancelled());
+
+    let unknown = manager
+        .send(RegisterWorker {
+            name: "gpu".to_string(),
+            capabilities: vec!["gpu".to_string()],
+            labels: BTreeMap::new(),
+            max_concurrent: 1,
+            recipient: FakeWorker::default().start().recipient(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        unknown.unwrap_err().error_type,
+        ApiErrorType::InvalidTaskType
+    );
+}
+
+#[actix_rt::test]
+async fn test_registered_types_are_routed_by_name() {
+    let mut task_types = TaskTypeRegistry::with_builtin_types();
+    task_types.register(TaskTypeDefinition::new("gpu", "GPU Job", |_| quick()));
+    let manager = TaskManagerActor::new().with_task_types(task_types).start();
+    let (quick_id, quick_worker) = register(&manager, "cpu", &["quick"]).await;
+    let (gpu_id, gpu_worker) = register(&manager, "gpu", &["GPU"]).await;
+    for worker_id in [quick_id, gpu_id] {
+        report(&manager, worker_id, WorkerRequest::Lease { max_tasks: 1 })
+            .await
+            .unwrap();
+    }
+
+    // Runs as a quick task but is known by its registered name
+    let task_id = manager
+        .send(CreateTask {
+            type_name: Some("gpu".to_string()),
+            ..remote_task("render", quick())
+        })
+        .await
+        .unwrap();
+
+    assert!(assigned(&quick_worker).await.is_empty());
+    let tasks = assigned(&gpu_worker).await;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].type_name, "gpu");
+    let task = manager
+        .send(GetTask { id: task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.task_type, "gpu");
+}
+
+#[actix_rt::test]
+async fn test_lease_is_capped_by_free_slots() {
+    let manager = TaskManagerActor::new().start();
+    let (worker_id, worker) = register(&manager, "worker", &[]).await;
+    report(&manager, worker_id, WorkerRequest::Lease { max_tasks: 10 })
+        .await
+        .unwrap();
+
+    for index in 0..3 {
+        manager
+            .send(remote_task(&format!("task {}", index), quick()))
+            .await
+            .unwrap();
+    }
+
+    let tasks = assigned(&worker).await;
+    assert_eq!(tasks.len(), 2);
+    let queued = manager
+        .send(GetAllTasks)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|task| task.status == TaskStatus::Queued)
+        .count();
+    assert_eq!(queued, 1);
+
+    // A failure frees the slot, the next lease picks up the queued task
+    report(
+        &manager,
+        worker_id,
+        WorkerRequest::Fail {
+            task_id: tasks[0].task_id,
+            error: "worker crashed".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    report(&manager, worker_id, WorkerRequest::Lease { max_tasks: 1 })
+        .await
+        .unwrap();
+    assert_eq!(assigned(&worker).await.len(), 3);
+
+    // Reports for tasks the worker does not hold are rejected
+    let stale = report(
+        &manager,
+        worker_id,
+        WorkerRequest::Complete {
+            task_id: tasks[0].task_id,
+            result: "late".to_string(),
+        },
+    )
+    .await;
+    assert_eq!(stale.unwrap_err().error_type, ApiErrorType::NotFound);
+}
+
+#[actix_rt::test]
+async fn test_remote_timeout_and_disconnect_fail_tasks() {
+    let clock = ManualClock::default();
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .start();
+    let (worker_id, worker) = register(&manager, "flaky", &[]).await;
+    report(&manager, worker_id, WorkerRequest::Lease { max_tasks: 2 })
+        .await
+        .unwrap();
+
+    let timed_out = manager.send(remote_task("slow", quick())).await.unwrap();
+    let dropped = manager
+        .send(remote_task(
+            "dropped",
+            TaskType::Long {
+                timeout_ms: Some(10000),
+                duration: None,
+            },
+        ))
+        .await
+        .unwrap();
+    assert_eq!(assigned(&worker).await.len(), 2);
+
+    clock.advance(Duration::from_millis(1000));
+    settle().await;
+    let task = manager
+        .send(GetTask { id: timed_out })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.status, TaskStatus::Error);
+    assert!(task.was_timeout());
+    let commands = worker.send(Commands).await.unwrap();
+    assert!(commands.iter().any(|command| matches!(
+        command,
+        WorkerCommand::Cancel { task_id, .. } if *task_id == timed_out
+    )));
+
+    manager
+        .send(WorkerDisconnected { worker_id })
+        .await
+        .unwrap();
+    let task = manager
+        .send(GetTask { id: dropped })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.status, TaskStatus::Error);
+    assert_eq!(task.error.as_deref(), Some("Worker 'flaky' disconnected"));
+    assert!(manager.send(GetWorkers).await.unwrap().is_empty());
+}
+
+fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+async fn register_labeled(
+    manager: &Addr<TaskManagerActor>,
+    name: &str,
+    worker_labels: &[(&str, &str)],
+) -> (Uuid, Addr<FakeWorker>) {
+    let worker = FakeWorker::default().start();
+    let worker_id = manager
+        .send(RegisterWorker {
+            name: name.to_string(),
+            capabilities: Vec::new(),
+            labels: labels(worker_labels),
+            max_concurrent: 2,
+            recipient: worker.clone().recipient(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    report(manager, worker_id, WorkerRequest::Lease { max_tasks: 2 })
+        .await
+        .unwrap();
+    (worker_id, worker)
+}
+
+#[actix_rt::test]
+async fn test_required_labels_gate_placement() {
+    let manager = TaskManagerActor::new().start();
+    let (_, cpu_worker) = register_labeled(&manager, "cpu", &[("gpu", "false")]).await;
+
+    let task_id = manager
+        .send(CreateTask {
+            affinity: Affinity {
+                required: labels(&[("gpu", "true"), ("region", "a")]),
+                ..Default::default()
+            },
+            ..remote_task("render", quick())
+        })
+        .await
+        .unwrap();
+
+    assert!(assigned(&cpu_worker).await.is_empty());
+    let task = manager
+        .send(GetTask { id: task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.status, TaskStatus::Queued);
+    assert_eq!(
+        task.queue_reason.as_deref(),
+        Some("No eligible worker: requires capability 'quick', gpu=true, region=a")
+    );
+
+    // A matching worker picks it up as soon as it registers and leases
+    let (_, gpu_worker) =
+        register_labeled(&manager, "gpu", &[("gpu", "true"), ("region", "a")]).await;
+    let tasks = assigned(&gpu_worker).await;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].task_id, task_id);
+    let task = manager
+        .send(GetTask { id: task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.worker.as_deref(), Some("gpu"));
+    assert!(task.queue_reason.is_none());
+
+    let invalid = manager
+        .send(RegisterWorker {
+            name: "bad".to_string(),
+            capabilities: Vec::new(),
+            labels: labels(&[("", "x")]),
+            max_concurrent: 1,
+            recipient: FakeWorker::default().start().recipient(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        invalid.unwrap_err().error_type,
+        ApiErrorType::ValidationError
+    );
+}
+
+#[actix_rt::test]
+async fn test_preferred_labels_pick_between_eligible_workers() {
+    let manager = TaskManagerActor::new().start();
+    let (_, batch_worker) =
+        register_labeled(&manager, "batch", &[("region", "a"), ("pool", "batch")]).await;
+    let (_, other_region) =
+        register_labeled(&manager, "region-b", &[("region", "b"), ("pool", "batch")]).await;
+    let (_, interactive) = register_labeled(&manager, "interactive", &[("region", "a")]).await;
+
+    let affinity = Affinity {
+        required: labels(&[("region", "a")]),
+        preferred: labels(&[("pool", "batch")]),
+    };
+    for index in 0..3 {
+        manager
+            .send(CreateTask {
+                affinity: affinity.clone(),
+                ..remote_task(&format!("job {}", index), quick())
+            })
+            .await
+            .unwrap();
+    }
+
+    // The preferred worker fills up first, the rest still lands in region a
+    assert_eq!(assigned(&batch_worker).await.len(), 2);
+    assert_eq!(assigned(&interactive).await.len(), 1);
+    assert!(assigned(&other_region).await.is_empty());
+}
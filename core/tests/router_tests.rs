@@ -0,0 +1,248 @@
+use actix::{Actor, Addr, Context, Handler, MessageResult};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+use task_core::*;
+
+mod common;
+use common::*;
+
+const SHARDS: usize = 4;
+
+fn router() -> Addr<TaskManagerRouter> {
+    TaskManagerRouter::start_shards(SHARDS, |_| TaskManagerActor::new()).start()
+}
+
+#[derive(Default)]
+struct FakeWorker {
+    commands: Vec<WorkerCommand>,
+}
+
+impl Actor for FakeWorker {
+    type Context = Context<Self>;
+}
+
+impl Handler<WorkerCommand> for FakeWorker {
+    type Result = ();
+
+    fn handle(&mut self, msg: WorkerCommand, _ctx: &mut Self::Context) -> Self::Result {
+        self.commands.push(msg);
+    }
+}
+
+#[derive(actix::Message)]
+#[rtype(result = "Vec<WorkerCommand>")]
+struct Commands;
+
+impl Handler<Commands> for FakeWorker {
+    type Result = MessageResult<Commands>;
+
+    fn handle(&mut self, _msg: Commands, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.commands.clone())
+    }
+}
+
+#[actix_rt::test]
+async fn test_tasks_are_spread_and_found_through_the_router() {
+    let router = router();
+    let mut ids = Vec::new();
+    for _ in 0..40 {
+        ids.push(router.send(long_task(None)).await.unwrap());
+    }
+
+    let shards: BTreeSet<usize> = ids.iter().map(|id| shard_for(id, SHARDS)).collect();
+    assert!(shards.len() > 1, "all tasks landed on shards {:?}", shards);
+
+    assert_eq!(router.send(GetAllTasks).await.unwrap().len(), 40);
+    for id in &ids {
+        let task = router.send(GetTask { id: *id }).await.unwrap().unwrap();
+        assert_eq!(task.status, TaskStatus::InProgress);
+    }
+
+    assert!(router.send(CancelTaskById { id: ids[7] }).await.unwrap());
+    let task = router.send(GetTask { id: ids[7] }).await.unwrap().unwrap();
+    assert!(task.was_cancelled());
+}
+
+#[actix_rt::test]
+async fn test_limited_namespace_lives_on_one_shard() {
+    let router = router();
+    router
+        .send(ConfigureNamespace {
+            name: "team".to_string(),
+            config: NamespaceConfig {
+                max_concurrent: Some(1),
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        ids.push(router.send(long_task(Some("team"))).await.unwrap());
+    }
+    let shards: BTreeSet<usize> = ids.iter().map(|id| shard_for(id, SHARDS)).collect();
+    assert_eq!(shards.len(), 1);
+    // Picked for the shard, still random v4 ids
+    assert!(ids
+        .iter()
+        .all(|id| id.get_version() == Some(uuid::Version::Random)
+            && id.get_variant() == uuid::Variant::RFC4122));
+
+    let info = router
+        .send(GetNamespace {
+            name: "team".to_string(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((info.running, info.waiting, info.total), (1, 2, 3));
+
+    // Every shard knows the namespace, the listing shows it once
+    let namespaces = router.send(ListNamespaces).await.unwrap();
+    let names: Vec<&str> = namespaces.iter().map(|ns| ns.name.as_str()).collect();
+    assert_eq!(names, vec![DEFAULT_NAMESPACE, "team"]);
+}
+
+// Tasks created before the namespace was limited stay on their shards and
+// only the ones that landed on the home shard count towards its quota
+#[actix_rt::test]
+async fn test_tasks_from_before_a_quota_are_not_moved() {
+    let router = router();
+    let mut earlier = Vec::new();
+    for _ in 0..8 {
+        earlier.push(router.send(long_task(Some("team"))).await.unwrap());
+    }
+    router
+        .send(ConfigureNamespace {
+            name: "team".to_string(),
+            config: NamespaceConfig {
+                quota: Quota {
+                    max_active: Some(10),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    let mut accepted = Vec::new();
+    while let Ok(id) = router
+        .send(SubmitTask {
+            task: long_task(Some("team")),
+        })
+        .await
+        .unwrap()
+    {
+        accepted.push(id);
+    }
+    let home = shard_for(&accepted[0], SHARDS);
+    assert!(accepted.iter().all(|id| shard_for(id, SHARDS) == home));
+    let earlier_at_home = earlier
+        .iter()
+        .filter(|id| shard_for(id, SHARDS) == home)
+        .count();
+    assert_eq!(accepted.len(), 10 - earlier_at_home);
+
+    let info = router
+        .send(GetNamespace {
+            name: "team".to_string(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(info.usage.active, 8 + accepted.len());
+}
+
+#[actix_rt::test]
+async fn test_remote_tasks_reach_workers_through_the_router() {
+    let router = router();
+    let worker = FakeWorker::default().start();
+    let worker_id = router
+        .send(RegisterWorker {
+            name: "remote".to_string(),
+            capabilities: Vec::new(),
+            labels: BTreeMap::new(),
+            max_concurrent: 1,
+            recipient: worker.clone().recipient(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    router
+        .send(WorkerReport {
+            worker_id,
+            request: WorkerRequest::Lease { max_tasks: 1 },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    let id = router
+        .send(CreateTask {
+            executor: Executor::Remote,
+            ..long_task(None)
+        })
+        .await
+        .unwrap();
+    assert_eq!(shard_for(&id, SHARDS), 0);
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let task = router.send(GetTask { id }).await.unwrap().unwrap();
+    assert_eq!(task.worker.as_deref(), Some("remote"));
+    assert!(worker
+        .send(Commands)
+        .await
+        .unwrap()
+        .iter()
+        .any(|command| matches!(command, WorkerCommand::Assign { task } if task.task_id == id)));
+    assert_eq!(router.send(GetWorkers).await.unwrap().len(), 1);
+}
+
+// Finishes at once, so tasks of an earlier burst do not slow the next
+fn quick_task() -> CreateTask {
+    CreateTask {
+        name: "Burst".to_string(),
+        message: "Created as fast as the shards allow".to_string(),
+        task_type: TaskType::Quick {
+            timeout_ms: Some(30_000),
+            duration: Some(DurationDistribution::Constant { ms: 0 }),
+        },
+        ..Default::default()
+    }
+}
+
+// Time to create `count` tasks through a router over `shards` managers,
+// `IN_FLIGHT` requests at a time like concurrent web requests
+async fn submit_burst(shards: usize, count: usize) -> Duration {
+    const IN_FLIGHT: usize = 64;
+    let router = TaskManagerRouter::start_shards(shards, |_| TaskManagerActor::new()).start();
+    let start = std::time::Instant::now();
+    for _ in 0..count / IN_FLIGHT {
+        let requests: Vec<_> = (0..IN_FLIGHT).map(|_| router.send(quick_task())).collect();
+        for request in requests {
+            request.await.unwrap();
+        }
+    }
+    start.elapsed()
+}
+
+// The shards run on their own threads, so this needs at least SHARDS
+// cores to show, run it with `cargo test --release -- --ignored`
+#[actix_rt::test]
+#[ignore]
+async fn test_shards_create_tasks_faster_than_one_manager() {
+    const TASKS: usize = 20_000;
+    let single = submit_burst(1, TASKS).await;
+    let sharded = submit_burst(SHARDS, TASKS).await;
+    assert!(
+        sharded.as_secs_f64() < single.as_secs_f64() * 0.75,
+        "{} shards took {:?}, one took {:?}",
+        SHARDS,
+        sharded,
+        single
+    );
+}
@@ -0,0 +1,205 @@
+use actix::{Actor, Addr, Context, Handler, MessageResult};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use task_core::*;
+use uuid::Uuid;
+
+mod common;
+use common::*;
+
+#[derive(Default)]
+struct FakeWorker {
+    commands: Vec<WorkerCommand>,
+}
+
+impl Actor for FakeWorker {
+    type Context = Context<Self>;
+}
+
+impl Handler<WorkerCommand> for FakeWorker {
+    type Result = ();
+
+    fn handle(&mut self, msg: WorkerCommand, _ctx: &mut Self::Context) -> Self::Result {
+        self.commands.push(msg);
+    }
+}
+
+#[derive(actix::Message)]
+#[rtype(result = "Vec<WorkerCommand>")]
+struct Commands;
+
+impl Handler<Commands> for FakeWorker {
+    type Result = MessageResult<Commands>;
+
+    fn handle(&mut self, _msg: Commands, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.commands.clone())
+    }
+}
+
+async fn single_slot_worker(manager: &Addr<TaskManagerActor>) -> (Uuid, Addr<FakeWorker>) {
+    let worker = FakeWorker::default().start();
+    let worker_id = manager
+        .send(RegisterWorker {
+            name: "pool".to_string(),
+            capabilities: Vec::new(),
+            labels: BTreeMap::new(),
+            max_concurrent: 1,
+            recipient: worker.clone().recipient(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    (worker_id, worker)
+}
+
+async fn report(manager: &Addr<TaskManagerActor>, worker_id: Uuid, request: WorkerRequest) {
+    manager
+        .send(WorkerReport { worker_id, request })
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+async fn set_weight(manager: &Addr<TaskManagerActor>, name: &str, weight: u32) {
+    manager
+        .send(ConfigureNamespace {
+            name: name.to_string(),
+            config: NamespaceConfig {
+                weight,
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+fn remote_task(namespace: &str) -> CreateTask {
+    CreateTask {
+        name: namespace.to_string(),
+        message: "Competes for the pool".to_string(),
+        task_type: TaskType::Quick {
+            timeout_ms: Some(60_000),
+            duration: None,
+        },
+        executor: Executor::Remote,
+        namespace: Some(namespace.to_string()),
+        ..Default::default()
+    }
+}
+
+// Runs `count` tasks one at a time through the single slot and returns the
+// namespace of each in dispatch order
+async fn drain(
+    manager: &Addr<TaskManagerActor>,
+    clock: &ManualClock,
+    worker_id: Uuid,
+    worker: &Addr<FakeWorker>,
+    count: usize,
+) -> Vec<String> {
+    let mut order = Vec::new();
+    for _ in 0..count {
+        report(manager, worker_id, WorkerRequest::Lease { max_tasks: 1 }).await;
+        settle().await;
+        let Some(WorkerCommand::Assign { task }) = worker.send(Commands).await.unwrap().pop()
+        else {
+            panic!("expected an assignment");
+        };
+        order.push(task.name.clone());
+
+        clock.advance(Duration::from_millis(100));
+        report(
+            manager,
+            worker_id,
+            WorkerRequest::Complete {
+                task_id: task.task_id,
+                result: "done".to_string(),
+            },
+        )
+        .await;
+    }
+    order
+}
+
+#[actix_rt::test]
+async fn test_pool_is_shared_by_weight() {
+    let (manager, clock) = manual_manager();
+    set_weight(&manager, "big", 3).await;
+    let (worker_id, worker) = single_slot_worker(&manager).await;
+
+    // The noisy namespace submits everything first
+    for _ in 0..12 {
+        manager.send(remote_task("noisy")).await.unwrap();
+    }
+    for _ in 0..12 {
+        manager.send(remote_task("big")).await.unwrap();
+    }
+
+    let order = drain(&manager, &clock, worker_id, &worker, 8).await;
+    let big = order.iter().filter(|name| *name == "big").count();
+    assert_eq!(big, 6, "dispatch order {:?}", order);
+
+    let report = manager.send(GetSchedulerReport).await.unwrap();
+    let big = report
+        .queues
+        .iter()
+        .find(|queue| queue.namespace == "big")
+        .unwrap();
+    assert_eq!(big.weight, 3);
+    assert_eq!(big.dispatched, 6);
+    assert_eq!(big.queued, 6);
+    assert_eq!(big.share, 0.75);
+    assert_eq!(big.target_share, 0.75);
+}
+
+#[actix_rt::test]
+async fn test_lone_namespace_gets_the_whole_pool() {
+    let (manager, clock) = manual_manager();
+    set_weight(&manager, "big", 5).await;
+    let (worker_id, worker) = single_slot_worker(&manager).await;
+
+    for _ in 0..3 {
+        manager.send(remote_task("small")).await.unwrap();
+    }
+    let order = drain(&manager, &clock, worker_id, &worker, 3).await;
+    assert_eq!(order, vec!["small"; 3]);
+}
+
+#[actix_rt::test]
+async fn test_report_tracks_wait_times() {
+    let (manager, clock) = manual_manager();
+    let (worker_id, worker) = single_slot_worker(&manager).await;
+    manager.send(remote_task("team")).await.unwrap();
+    manager.send(remote_task("team")).await.unwrap();
+
+    clock.advance(Duration::from_millis(400));
+    let report = manager.send(GetSchedulerReport).await.unwrap();
+    assert_eq!(report.queues[0].queued, 2);
+    assert_eq!(report.queues[0].oldest_wait_ms, 400);
+
+    // The first waits 400ms, the second another 100ms for the slot
+    drain(&manager, &clock, worker_id, &worker, 2).await;
+    let report = manager.send(GetSchedulerReport).await.unwrap();
+    let team = &report.queues[0];
+    assert_eq!(team.dispatched, 2);
+    assert_eq!(team.max_wait_ms, 500);
+    assert_eq!(team.avg_wait_ms, 450);
+    assert_eq!(team.oldest_wait_ms, 0);
+}
+
+#[actix_rt::test]
+async fn test_zero_weight_is_rejected() {
+    let (manager, _clock) = manual_manager();
+    let error = manager
+        .send(ConfigureNamespace {
+            name: "team".to_string(),
+            config: NamespaceConfig {
+                weight: 0,
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(error.error_type, ApiErrorType::ValidationError);
+}
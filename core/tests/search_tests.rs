@@ -0,0 +1,228 @@
+use actix::{Actor, Addr, Context, Handler};
+use std::collections::BTreeMap;
+use task_core::*;
+use uuid::Uuid;
+
+mod common;
+use common::*;
+
+fn described(name: &str, message: &str) -> CreateTask {
+    CreateTask {
+        name: name.to_string(),
+        message: message.to_string(),
+        ..long_task(None)
+    }
+}
+
+struct NullWorker;
+
+impl Actor for NullWorker {
+    type Context = Context<Self>;
+}
+
+impl Handler<WorkerCommand> for NullWorker {
+    type Result = ();
+
+    fn handle(&mut self, _msg: WorkerCommand, _ctx: &mut Self::Context) -> Self::Result {}
+}
+
+fn manager() -> (Addr<TaskManagerActor>, TaskReader) {
+    let manager = TaskManagerActor::new().with_snapshot_interval(INTERVAL);
+    let reader = manager.reader();
+    (manager.start(), reader)
+}
+
+// Runs the tasks on a worker that reports each outcome
+async fn run_remotely(manager: &Addr<TaskManagerActor>, outcomes: Vec<(&str, WorkerRequest)>) {
+    let worker_id = manager
+        .send(RegisterWorker {
+            name: "worker-1".to_string(),
+            capabilities: Vec::new(),
+            labels: BTreeMap::new(),
+            max_concurrent: 10,
+            recipient: NullWorker.start().recipient(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    let mut requests = Vec::new();
+    for (name, request) in outcomes {
+        let task_id = manager
+            .send(CreateTask {
+                executor: Executor::Remote,
+                ..described(name, "Runs on a worker")
+            })
+            .await
+            .unwrap();
+        requests.push(match request {
+            WorkerRequest::Complete { result, .. } => WorkerRequest::Complete { task_id, result },
+            WorkerRequest::Fail { error, .. } => WorkerRequest::Fail { task_id, error },
+            other => other,
+        });
+    }
+    manager
+        .send(WorkerReport {
+            worker_id,
+            request: WorkerRequest::Lease { max_tasks: 10 },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    for request in requests {
+        manager
+            .send(WorkerReport { worker_id, request })
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    published().await;
+}
+
+fn fail(error: &str) -> WorkerRequest {
+    WorkerRequest::Fail {
+        task_id: Uuid::nil(),
+        error: error.to_string(),
+    }
+}
+
+fn names(results: &SearchResults) -> Vec<&str> {
+    results
+        .hits
+        .iter()
+        .map(|hit| hit.task.name.as_str())
+        .collect()
+}
+
+fn matched(highlight: &Highlight) -> Vec<&str> {
+    highlight
+        .fragments
+        .iter()
+        .filter(|fragment| fragment.matched)
+        .map(|fragment| fragment.text.as_str())
+        .collect()
+}
+
+#[actix_rt::test]
+async fn test_errors_are_found_by_host() {
+    let (manager, reader) = manager();
+    run_remotely(
+        &manager,
+        vec![
+            ("sync orders", fail("connect to db-3.internal:5432 refused")),
+            ("sync users", fail("connect to db-4.internal:5432 refused")),
+            (
+                "export",
+                WorkerRequest::Complete {
+                    task_id: Uuid::nil(),
+                    result: "wrote 40 rows".to_string(),
+                },
+            ),
+        ],
+    )
+    .await;
+
+    let results = reader.search("db-3.internal", &TaskQuery::default(), None, 10);
+    // Both share the parts of the host name, only one has all of it
+    assert_eq!(names(&results), vec!["sync orders", "sync users"]);
+    assert_eq!(results.total, 2);
+    assert!(results.hits[0].score > results.hits[1].score);
+
+    let highlight = &results.hits[0].highlights[0];
+    assert_eq!(highlight.field, "error");
+    assert_eq!(matched(highlight), vec!["db-3.internal"]);
+    let text: String = highlight
+        .fragments
+        .iter()
+        .map(|fragment| fragment.text.as_str())
+        .collect();
+    assert_eq!(text, "connect to db-3.internal:5432 refused");
+
+    let results = reader.search("40 ROWS", &TaskQuery::default(), None, 10);
+    assert_eq!(names(&results), vec!["export"]);
+    assert_eq!(results.hits[0].highlights[0].field, "result");
+    assert!(reader
+        .search("nowhere.example", &TaskQuery::default(), None, 10)
+        .hits
+        .is_empty());
+}
+
+#[actix_rt::test]
+async fn test_ranking_filters_and_scopes() {
+    let (manager, reader) = manager();
+    manager
+        .send(described("Nightly backup", "Copies the warehouse"))
+        .await
+        .unwrap();
+    manager
+        .send(described(
+            "Vacuum",
+            "Runs after the nightly backup finished",
+        ))
+        .await
+        .unwrap();
+    let cancelled = manager
+        .send(described("Backup logs", "Ships logs"))
+        .await
+        .unwrap();
+    manager
+        .send(CreateTask {
+            namespace: Some("team-a".to_string()),
+            ..described("Backup team files", "Private copy")
+        })
+        .await
+        .unwrap();
+    manager
+        .send(CancelTaskById { id: cancelled })
+        .await
+        .unwrap();
+    published().await;
+
+    // A match in the name counts for more than one in the message
+    let results = reader.search("backup", &TaskQuery::default(), None, 10);
+    assert_eq!(results.total, 4);
+    assert_eq!(*names(&results).last().unwrap(), "Vacuum");
+
+    let in_progress = TaskQuery::parse(
+        "status:in_progress",
+        chrono::Utc::now(),
+        &TaskTypeRegistry::with_builtin_types(),
+    )
+    .unwrap();
+    let results = reader.search("backup", &in_progress, None, 10);
+    assert_eq!(results.total, 3);
+    assert!(!names(&results).contains(&"Backup logs"));
+
+    let results = reader.search("backup", &TaskQuery::default(), Some("team-a"), 10);
+    assert_eq!(names(&results), vec!["Backup team files"]);
+
+    let results = reader.search("backup", &TaskQuery::default(), None, 1);
+    assert_eq!(results.hits.len(), 1);
+    assert_eq!(results.total, 4);
+}
+
+#[actix_rt::test]
+async fn test_long_fields_are_cut_around_the_match() {
+    let (manager, reader) = manager();
+    let message = format!(
+        "{} checksum mismatch on shard 12 {}",
+        "padding ".repeat(40),
+        "tail ".repeat(40)
+    );
+    manager.send(described("Verify", &message)).await.unwrap();
+    published().await;
+
+    let results = reader.search("checksum", &TaskQuery::default(), None, 10);
+    let highlight = &results.hits[0].highlights[0];
+    assert_eq!(highlight.field, "message");
+    assert_eq!(matched(highlight), vec!["checksum"]);
+    let first = &highlight.fragments.first().unwrap().text;
+    let last = &highlight.fragments.last().unwrap().text;
+    assert!(first.starts_with('…'));
+    assert!(last.ends_with('…'));
+    let length: usize = highlight
+        .fragments
+        .iter()
+        .map(|fragment| fragment.text.len())
+        .sum();
+    assert!(length < message.len() / 2);
+}
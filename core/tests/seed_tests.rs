@@ -0,0 +1,132 @@
+use actix::{Actor, Addr};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::sync::Arc;
+use std::time::Duration;
+use task_core::*;
+use uuid::Uuid;
+
+mod common;
+use common::*;
+
+fn coin_flip_task(seed: Option<u64>) -> CreateTask {
+    CreateTask {
+        name: "Coin Flip".to_string(),
+        message: "Fails half of the time".to_string(),
+        task_type: TaskType::Custom {
+            name: "Coin Flip".to_string(),
+            timeout_ms: 1000,
+            failure_rate: Some(0.5),
+            duration: None,
+        },
+        seed,
+        ..Default::default()
+    }
+}
+
+async fn run_to_completion(
+    manager: &Addr<TaskManagerActor>,
+    clock: &ManualClock,
+    ids: &[Uuid],
+) -> Vec<TaskMetadata> {
+    settle().await;
+    clock.advance(Duration::from_secs(1));
+    settle().await;
+
+    let mut tasks = Vec::new();
+    for id in ids {
+        tasks.push(manager.send(GetTask { id: *id }).await.unwrap().unwrap());
+    }
+    tasks
+}
+
+#[test]
+fn test_should_fail_with_is_reproducible() {
+    let task_type = coin_flip_task(None).task_type;
+
+    let mut first = StdRng::seed_from_u64(7);
+    let mut second = StdRng::seed_from_u64(7);
+    let a: Vec<bool> = (0..50)
+        .map(|_| task_type.should_fail_with(&mut first).is_some())
+        .collect();
+    let b: Vec<bool> = (0..50)
+        .map(|_| task_type.should_fail_with(&mut second).is_some())
+        .collect();
+
+    assert_eq!(a, b);
+    assert!(a.contains(&true) && a.contains(&false));
+}
+
+#[actix_rt::test]
+async fn test_seed_is_recorded_in_metadata() {
+    let manager = TaskManagerActor::new().start();
+
+    let explicit = manager.send(coin_flip_task(Some(42))).await.unwrap();
+    let drawn = manager.send(coin_flip_task(None)).await.unwrap();
+
+    let explicit = manager
+        .send(GetTask { id: explicit })
+        .await
+        .unwrap()
+        .unwrap();
+    let drawn = manager.send(GetTask { id: drawn }).await.unwrap().unwrap();
+    assert_eq!(explicit.seed, Some(42));
+    assert!(drawn.seed.is_some());
+}
+
+#[actix_rt::test]
+async fn test_manager_seed_replays_run() {
+    let mut runs = Vec::new();
+
+    for _ in 0..2 {
+        let clock = ManualClock::default();
+        let manager = TaskManagerActor::new()
+            .with_seed(1234)
+            .with_clock(Arc::new(clock.clone()))
+            .start();
+
+        let mut ids = Vec::new();
+        for _ in 0..8 {
+            ids.push(manager.send(coin_flip_task(None)).await.unwrap());
+        }
+
+        let tasks = run_to_completion(&manager, &clock, &ids).await;
+        runs.push(
+            tasks
+                .into_iter()
+                .map(|task| (task.seed, task.status))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    assert_eq!(runs[0], runs[1]);
+    let statuses: Vec<_> = runs[0].iter().map(|(_, status)| status.clone()).collect();
+    assert!(statuses.contains(&TaskStatus::Completed));
+    assert!(statuses.contains(&TaskStatus::Error));
+}
+
+#[actix_rt::test]
+async fn test_recorded_seed_replays_single_task() {
+    let clock = ManualClock::default();
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .start();
+
+    let mut ids = Vec::new();
+    for _ in 0..8 {
+        ids.push(manager.send(coin_flip_task(None)).await.unwrap());
+    }
+    let original = run_to_completion(&manager, &clock, &ids).await;
+
+    let mut replay_ids = Vec::new();
+    for task in &original {
+        replay_ids.push(manager.send(coin_flip_task(task.seed)).await.unwrap());
+    }
+    let replayed = run_to_completion(&manager, &clock, &replay_ids).await;
+
+    for (original, replayed) in original.iter().zip(&replayed) {
+        assert_eq!(original.seed, replayed.seed);
+        assert_eq!(original.status, replayed.status);
+        assert_eq!(original.error, replayed.error);
+    }
+}
@@ -17,6 +17,7 @@ fn test_task_metadata_serialization() {
         actual_duration_ms: Some(1000),
         cancelled_at: None,
         timeout_at: None,
+        ..Default::default()
     };
 
     // Test JSON serialization
@@ -29,6 +30,21 @@ fn test_task_metadata_serialization() {
     assert_eq!(metadata.result, deserialized.result);
 }
 
+#[test]
+fn test_task_metadata_default_matches_serde_defaults() {
+    let json = serde_json::to_value(TaskMetadata::default()).unwrap();
+    let mut minimal = json.clone();
+    for optional in ["task_type", "attempt", "max_attempts", "namespace"] {
+        minimal.as_object_mut().unwrap().remove(optional);
+    }
+    let deserialized: TaskMetadata = serde_json::from_value(minimal).unwrap();
+
+    assert_eq!(serde_json::to_value(deserialized).unwrap(), json);
+    assert_eq!(TaskMetadata::default().attempt, 1);
+    assert_eq!(TaskMetadata::default().max_attempts, 1);
+    assert_eq!(TaskMetadata::default().namespace, DEFAULT_NAMESPACE);
+}
+
 #[test]
 fn test_task_status_transitions() {
     // Test that status enum values are as expected
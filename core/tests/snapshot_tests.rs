@@ -0,0 +1,96 @@
+use actix::Actor;
+use task_core::*;
+
+mod common;
+use common::*;
+
+#[actix_rt::test]
+async fn test_changes_are_published_in_new_versions() {
+    let manager = TaskManagerActor::new().with_snapshot_interval(INTERVAL);
+    let reader = manager.reader();
+    let manager = manager.start();
+    assert_eq!(reader.version(), 0);
+    assert!(reader.all_tasks().is_empty());
+
+    let id = manager.send(long_task(None)).await.unwrap();
+    published().await;
+    let version = reader.version();
+    assert!(version > 0);
+    assert_eq!(reader.task(&id).unwrap().status, TaskStatus::InProgress);
+
+    // Nothing changed, nothing published
+    let held = reader.task(&id).unwrap();
+    published().await;
+    assert_eq!(reader.version(), version);
+
+    assert!(manager.send(CancelTaskById { id }).await.unwrap());
+    published().await;
+    assert!(reader.version() > version);
+    assert!(reader.task(&id).unwrap().was_cancelled());
+    assert!(!held.was_cancelled());
+}
+
+#[actix_rt::test]
+async fn test_private_namespaces_are_left_out_of_unscoped_reads() {
+    let manager = TaskManagerActor::new().with_snapshot_interval(INTERVAL);
+    let reader = manager.reader();
+    let manager = manager.start();
+    manager
+        .send(ConfigureNamespace {
+            name: "secret".to_string(),
+            config: NamespaceConfig {
+                visibility: Visibility::Private,
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    let hidden = manager.send(long_task(Some("secret"))).await.unwrap();
+    let shared = manager.send(long_task(None)).await.unwrap();
+    published().await;
+
+    let ids: Vec<_> = reader
+        .namespace_tasks(None)
+        .iter()
+        .map(|task| task.id)
+        .collect();
+    assert_eq!(ids, vec![shared]);
+    assert!(reader.namespace_task(&hidden, None).is_none());
+    assert!(reader.namespace_task(&hidden, Some("secret")).is_some());
+    assert_eq!(reader.namespace_tasks(Some("secret")).len(), 1);
+    assert_eq!(reader.all_tasks().len(), 2);
+
+    // Making it shared is published even though no task changed
+    manager
+        .send(ConfigureNamespace {
+            name: "secret".to_string(),
+            config: NamespaceConfig::default(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    published().await;
+    assert!(reader.namespace_task(&hidden, None).is_some());
+}
+
+#[actix_rt::test]
+async fn test_router_reader_spans_every_shard() {
+    let router = TaskManagerRouter::start_shards(4, |_| {
+        TaskManagerActor::new().with_snapshot_interval(INTERVAL)
+    });
+    let reader = router.reader();
+    let router = router.start();
+
+    let mut ids = Vec::new();
+    for _ in 0..20 {
+        ids.push(router.send(long_task(None)).await.unwrap());
+    }
+    published().await;
+
+    assert_eq!(reader.all_tasks().len(), 20);
+    for id in &ids {
+        assert_eq!(reader.task(id).unwrap().id, *id);
+    }
+}
@@ -0,0 +1,228 @@
+use actix::Actor;
+use chrono::{TimeZone, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use task_core::*;
+
+mod common;
+use common::*;
+
+fn start_time() -> chrono::DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap()
+}
+
+fn quick(ms: u64) -> CreateTask {
+    CreateTask {
+        name: format!("Quick {}ms", ms),
+        message: "Counted in the stats".to_string(),
+        task_type: TaskType::Quick {
+            timeout_ms: Some(60_000),
+            duration: Some(DurationDistribution::Constant { ms }),
+        },
+        ..Default::default()
+    }
+}
+
+fn timing_out(timeout_ms: u64) -> CreateTask {
+    CreateTask {
+        name: "Slow".to_string(),
+        message: "Times out".to_string(),
+        task_type: TaskType::Error {
+            timeout_ms: Some(timeout_ms),
+            error_type: ErrorType::Timeout,
+        },
+        ..Default::default()
+    }
+}
+
+// Quick tasks of 1 to 4 seconds, one that times out after 5 and one still
+// running, each finishing as the clock reaches it
+async fn finished_mix(clock: &ManualClock) -> (actix::Addr<TaskManagerActor>, TaskReader) {
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_snapshot_interval(INTERVAL);
+    let reader = manager.reader();
+    let manager = manager.start();
+    for ms in [1_000, 2_000, 3_000, 4_000] {
+        manager.send(quick(ms)).await.unwrap();
+    }
+    manager.send(timing_out(5_000)).await.unwrap();
+    manager.send(long_task(None)).await.unwrap();
+    settle().await;
+    for _ in 0..5 {
+        clock.advance(Duration::from_secs(1));
+        settle().await;
+    }
+    published().await;
+    (manager, reader)
+}
+
+#[actix_rt::test]
+async fn test_counts_rates_and_percentiles_by_type() {
+    let clock = ManualClock::new(start_time());
+    let (_manager, reader) = finished_mix(&clock).await;
+
+    let stats = reader.stats(None, clock.now());
+    assert_eq!(
+        stats.generated_at,
+        start_time() + chrono::Duration::seconds(5)
+    );
+    let names: Vec<&str> = stats.windows.iter().map(|w| w.window.as_str()).collect();
+    assert_eq!(names, vec![ALL_TASKS_WINDOW, "1m", "5m", "15m"]);
+
+    let all = stats.window(ALL_TASKS_WINDOW).unwrap();
+    assert_eq!(all.overall.total, 6);
+    assert_eq!(
+        all.overall.by_status,
+        StatusCounts {
+            queued: 0,
+            in_progress: 1,
+            completed: 4,
+            error: 1,
+        }
+    );
+    // Of the five that finished
+    assert_eq!(all.overall.success_rate, Some(0.8));
+    assert_eq!(all.overall.timeout_rate, Some(0.2));
+    assert_eq!(
+        all.overall.duration_ms,
+        DurationPercentiles {
+            p50: Some(3_000),
+            p90: Some(5_000),
+            p99: Some(5_000),
+        }
+    );
+
+    let quick = &all.by_type["quick"];
+    assert_eq!(quick.total, 4);
+    assert_eq!(quick.success_rate, Some(1.0));
+    assert_eq!(quick.timeout_rate, Some(0.0));
+    assert_eq!(quick.duration_ms.p50, Some(2_000));
+    assert_eq!(quick.duration_ms.p90, Some(4_000));
+    assert_eq!(all.by_type["error"].timeout_rate, Some(1.0));
+    let long = &all.by_type["long"];
+    assert_eq!(long.by_status.in_progress, 1);
+    assert_eq!(long.success_rate, None);
+    assert_eq!(long.duration_ms, DurationPercentiles::default());
+}
+
+#[actix_rt::test]
+async fn test_windows_leave_out_older_tasks() {
+    let clock = ManualClock::new(start_time());
+    let (manager, reader) = finished_mix(&clock).await;
+
+    clock.advance(Duration::from_secs(2 * 60));
+    settle().await;
+    manager.send(quick(1_000)).await.unwrap();
+    settle().await;
+    published().await;
+
+    let stats = reader.stats(None, clock.now());
+    let totals: Vec<usize> = stats.windows.iter().map(|w| w.overall.total).collect();
+    assert_eq!(totals, vec![7, 1, 7, 7]);
+    let last_minute = stats.window("1m").unwrap();
+    assert_eq!(last_minute.overall.by_status.in_progress, 1);
+    assert_eq!(last_minute.overall.success_rate, None);
+    assert_eq!(
+        last_minute.by_type.keys().collect::<Vec<_>>(),
+        vec!["quick"]
+    );
+}
+
+#[actix_rt::test]
+async fn test_router_merges_shards_within_a_scope() {
+    let clock = ManualClock::new(start_time());
+    let shard_clock = clock.clone();
+    let router = TaskManagerRouter::start_shards(4, move |_| {
+        TaskManagerActor::new()
+            .with_clock(Arc::new(shard_clock.clone()))
+            .with_snapshot_interval(INTERVAL)
+    });
+    let reader = router.reader();
+    let router = router.start();
+    router
+        .send(ConfigureNamespace {
+            name: "team-a".to_string(),
+            config: NamespaceConfig {
+                visibility: Visibility::Private,
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    for seconds in 1..=8 {
+        router.send(quick(seconds * 1_000)).await.unwrap();
+    }
+    for _ in 0..2 {
+        router.send(long_task(Some("team-a"))).await.unwrap();
+    }
+    // The shards run on their own threads
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    for _ in 0..8 {
+        clock.advance(Duration::from_secs(1));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    published().await;
+
+    let stats = reader.stats(None, clock.now());
+    assert_eq!(
+        stats.generated_at,
+        start_time() + chrono::Duration::seconds(8)
+    );
+    let all = stats.window(ALL_TASKS_WINDOW).unwrap();
+    assert_eq!(all.overall.total, 8);
+    assert_eq!(all.overall.by_status.completed, 8);
+    // Taken over every shard's durations, not averaged per shard
+    assert_eq!(
+        all.overall.duration_ms,
+        DurationPercentiles {
+            p50: Some(4_000),
+            p90: Some(8_000),
+            p99: Some(8_000),
+        }
+    );
+
+    let stats = reader.stats(Some("team-a"), clock.now());
+    let all = stats.window(ALL_TASKS_WINDOW).unwrap();
+    assert_eq!(all.overall.total, 2);
+    assert_eq!(all.overall.by_status.in_progress, 2);
+    assert_eq!(all.by_type.keys().collect::<Vec<_>>(), vec!["long"]);
+}
+
+#[actix_rt::test]
+async fn test_windows_stay_within_the_retention() {
+    let clock = ManualClock::new(start_time());
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_snapshot_interval(INTERVAL);
+    let reader = manager.reader();
+    let manager = manager.start();
+    manager
+        .send(ConfigureNamespace {
+            name: "short".to_string(),
+            config: NamespaceConfig {
+                retention_secs: Some(5 * 60),
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    manager.send(long_task(Some("short"))).await.unwrap();
+    manager.send(long_task(None)).await.unwrap();
+    published().await;
+
+    let names = |stats: &TaskStats| -> Vec<String> {
+        stats.windows.iter().map(|w| w.window.clone()).collect()
+    };
+    // Its tasks are gone after 5 minutes, a longer window would miss them
+    let stats = reader.stats(Some("short"), clock.now());
+    assert_eq!(names(&stats), vec![ALL_TASKS_WINDOW, "1m", "5m"]);
+    assert_eq!(stats.window("5m").unwrap().overall.total, 1);
+    let stats = reader.stats(None, clock.now());
+    assert_eq!(names(&stats), vec![ALL_TASKS_WINDOW, "1m", "5m"]);
+    let stats = reader.stats(Some("default"), clock.now());
+    assert_eq!(names(&stats), vec![ALL_TASKS_WINDOW, "1m", "5m", "15m"]);
+}
@@ -1,7 +1,11 @@
 use actix::Actor;
+use std::sync::Arc;
 use std::time::Duration;
 use task_core::*;
 
+mod common;
+use common::*;
+
 #[test]
 fn test_task_manager_creation() {
     let _manager = TaskManagerActor::new();
@@ -17,7 +21,11 @@ async fn test_create_quick_task() {
         .send(CreateTask {
             name: "Quick Test".to_string(),
             message: "Test quick task".to_string(),
-            task_type: TaskType::Quick { timeout_ms: None },
+            task_type: TaskType::Quick {
+                timeout_ms: None,
+                duration: None,
+            },
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -50,6 +58,7 @@ async fn test_create_error_task() {
                 timeout_ms: None,
                 error_type: ErrorType::Immediate,
             },
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -70,7 +79,11 @@ async fn test_get_all_tasks() {
         .send(CreateTask {
             name: "Task 1".to_string(),
             message: "First task".to_string(),
-            task_type: TaskType::Quick { timeout_ms: None },
+            task_type: TaskType::Quick {
+                timeout_ms: None,
+                duration: None,
+            },
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -79,7 +92,11 @@ async fn test_get_all_tasks() {
         .send(CreateTask {
             name: "Task 2".to_string(),
             message: "Second task".to_string(),
-            task_type: TaskType::Long { timeout_ms: None },
+            task_type: TaskType::Long {
+                timeout_ms: None,
+                duration: None,
+            },
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -104,7 +121,11 @@ async fn test_cancel_task() {
         .send(CreateTask {
             name: "Cancellable Task".to_string(),
             message: "This task will be cancelled".to_string(),
-            task_type: TaskType::Long { timeout_ms: None },
+            task_type: TaskType::Long {
+                timeout_ms: None,
+                duration: None,
+            },
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -155,7 +176,11 @@ async fn test_task_types() {
         .send(CreateTask {
             name: "Quick".to_string(),
             message: "Quick task".to_string(),
-            task_type: TaskType::Quick { timeout_ms: None },
+            task_type: TaskType::Quick {
+                timeout_ms: None,
+                duration: None,
+            },
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -164,7 +189,11 @@ async fn test_task_types() {
         .send(CreateTask {
             name: "Long".to_string(),
             message: "Long task".to_string(),
-            task_type: TaskType::Long { timeout_ms: None },
+            task_type: TaskType::Long {
+                timeout_ms: None,
+                duration: None,
+            },
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -177,6 +206,7 @@ async fn test_task_types() {
                 timeout_ms: None,
                 error_type: ErrorType::Immediate,
             },
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -189,19 +219,26 @@ async fn test_task_types() {
 
 #[actix_rt::test]
 async fn test_task_completion_lifecycle() {
-    let manager = TaskManagerActor::new().start();
+    let clock = ManualClock::default();
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .start();
 
     let task_id = manager
         .send(CreateTask {
             name: "Lifecycle Test".to_string(),
             message: "Test full lifecycle".to_string(),
-            task_type: TaskType::Quick { timeout_ms: None },
+            task_type: TaskType::Quick {
+                timeout_ms: None,
+                duration: None,
+            },
+            ..Default::default()
         })
         .await
         .unwrap();
 
     // Initial state
-    tokio::time::sleep(Duration::from_millis(10)).await;
+    settle().await;
     let task = manager
         .send(GetTask { id: task_id })
         .await
@@ -209,13 +246,15 @@ async fn test_task_completion_lifecycle() {
         .unwrap();
     assert_eq!(task.status, TaskStatus::InProgress);
 
-    // Wait for completion (Quick tasks take 2 seconds + buffer)
-    tokio::time::sleep(Duration::from_secs(3)).await;
+    // Quick tasks finish their work at 75% of the 2s timeout
+    clock.advance(Duration::from_millis(1500));
+    settle().await;
 
-    // Task should still be in metadata but completed
-    let task = manager.send(GetTask { id: task_id }).await.unwrap();
-    if let Some(task_metadata) = task {
-        // Task might be completed or cleaned up
-        println!("Final task status: {:?}", task_metadata.status);
-    }
+    let task = manager
+        .send(GetTask { id: task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.status, TaskStatus::Completed);
+    assert_eq!(task.actual_duration_ms, Some(1500));
 }
@@ -0,0 +1,160 @@
+use actix::Actor;
+use serde_json::json;
+use task_core::*;
+
+fn params(value: serde_json::Value) -> TaskParams {
+    value.as_object().cloned().unwrap_or_default()
+}
+
+#[test]
+fn test_builtin_types_registered() {
+    let registry = TaskTypeRegistry::with_builtin_types();
+    assert_eq!(registry.names(), vec!["quick", "long", "error", "custom"]);
+
+    let custom = registry.get("custom").unwrap();
+    assert!(custom.has_param("failure_rate"));
+    assert!(!registry.get("quick").unwrap().has_param("failure_rate"));
+}
+
+#[test]
+fn test_resolve_builtin_types() {
+    let registry = TaskTypeRegistry::with_builtin_types();
+
+    let quick = registry.resolve("quick", &TaskParams::new()).unwrap();
+    assert!(matches!(
+        quick,
+        TaskType::Quick {
+            timeout_ms: None,
+            duration: None,
+        }
+    ));
+
+    let error = registry.resolve("error", &TaskParams::new()).unwrap();
+    assert!(matches!(
+        error,
+        TaskType::Error {
+            error_type: ErrorType::Random,
+            ..
+        }
+    ));
+
+    let error = registry
+        .resolve("error", &params(json!({ "error_type": "network" })))
+        .unwrap();
+    assert!(matches!(
+        error,
+        TaskType::Error {
+            error_type: ErrorType::NetworkError,
+            ..
+        }
+    ));
+
+    let custom = registry
+        .resolve(
+            "custom",
+            &params(json!({
+                "custom_name": "Nightly",
+                "timeout_ms": 1500,
+                "failure_rate": 0.5
+            })),
+        )
+        .unwrap();
+    match custom {
+        TaskType::Custom {
+            name,
+            timeout_ms,
+            failure_rate,
+            ..
+        } => {
+            assert_eq!(name, "Nightly");
+            assert_eq!(timeout_ms, 1500);
+            assert_eq!(failure_rate, Some(0.5));
+        }
+        other => panic!("unexpected task type: {:?}", other),
+    }
+}
+
+#[test]
+fn test_resolve_validates_params() {
+    let registry = TaskTypeRegistry::with_builtin_types();
+
+    let unknown = registry.resolve("batch", &TaskParams::new()).unwrap_err();
+    assert_eq!(unknown.error_type, ApiErrorType::InvalidTaskType);
+    assert_eq!(
+        unknown.details.unwrap()["valid_types"],
+        json!(["quick", "long", "error", "custom"])
+    );
+
+    let timeout = registry
+        .resolve("long", &params(json!({ "timeout_ms": 50 })))
+        .unwrap_err();
+    assert_eq!(timeout.error_type, ApiErrorType::ValidationError);
+    assert_eq!(timeout.details.unwrap()["field"], "task_type.timeout_ms");
+
+    let wrong_kind = registry.resolve("custom", &params(json!({ "failure_rate": "high" })));
+    assert!(wrong_kind.is_err());
+
+    let bad_choice = registry.resolve("error", &params(json!({ "error_type": "meltdown" })));
+    assert!(bad_choice.is_err());
+
+    let long_name = registry.resolve("custom", &params(json!({ "custom_name": "x".repeat(101) })));
+    assert!(long_name.is_err());
+}
+
+#[actix_rt::test]
+async fn test_registered_type_is_usable_from_templates() {
+    let mut registry = TaskTypeRegistry::with_builtin_types();
+    registry.register(
+        TaskTypeDefinition::new("batch", "Batch Job", |params| TaskType::Long {
+            timeout_ms: params.get("timeout_ms").and_then(|t| t.as_u64()),
+            duration: None,
+        })
+        .with_param(
+            ParamSchema::new("timeout_ms", ParamKind::Integer, "Timeout").range(100.0, 60000.0),
+        )
+        .with_param(
+            ParamSchema::new("batch_size", ParamKind::Integer, "Rows per batch").required(),
+        ),
+    );
+
+    let templates = TaskTemplateActor::new().with_registry(registry).start();
+    let template = TaskTemplate {
+        name: "import".to_string(),
+        display_name: "Import".to_string(),
+        description: String::new(),
+        task_type: "batch".to_string(),
+        default_message: "Import rows".to_string(),
+        timeout_ms: 3000,
+        failure_rate: None,
+        tags: Vec::new(),
+        params: TaskParams::new(),
+    };
+
+    // batch_size is required by the schema
+    let missing = templates
+        .send(CreateTemplate {
+            template: template.clone(),
+        })
+        .await
+        .unwrap();
+    assert!(missing.is_err());
+
+    let mut template = template;
+    template.params = params(json!({ "batch_size": 500 }));
+    templates
+        .send(CreateTemplate { template })
+        .await
+        .unwrap()
+        .unwrap();
+
+    let task = templates
+        .send(InstantiateTemplate {
+            name: "import".to_string(),
+            overrides: TemplateOverrides::default(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.task_type.get_timeout().as_millis(), 3000);
+    assert_eq!(task.type_name(), "batch");
+}
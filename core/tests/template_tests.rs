@@ -0,0 +1,226 @@
+use actix::Actor;
+use task_core::*;
+
+fn etl_template() -> TaskTemplate {
+    TaskTemplate {
+        name: "etl".to_string(),
+        display_name: "ETL Job".to_string(),
+        description: "Nightly extract".to_string(),
+        task_type: "custom".to_string(),
+        default_message: "Run ETL".to_string(),
+        timeout_ms: 8000,
+        failure_rate: Some(0.25),
+        tags: vec!["batch".to_string()],
+        params: TaskParams::new(),
+    }
+}
+
+#[test]
+fn test_builtin_templates_are_valid() {
+    let registry = TaskTypeRegistry::with_builtin_types();
+    let templates = TaskTemplate::builtin();
+    let names: Vec<_> = templates.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["quick", "long", "error", "custom"]);
+
+    for template in &templates {
+        assert!(
+            template.validate(&registry).is_ok(),
+            "{} is invalid",
+            template.name
+        );
+    }
+}
+
+#[test]
+fn test_template_validation() {
+    let registry = TaskTypeRegistry::with_builtin_types();
+
+    let mut template = etl_template();
+    template.name = "has spaces".to_string();
+    assert_eq!(
+        template.validate(&registry).unwrap_err().error_type,
+        ApiErrorType::ValidationError
+    );
+
+    let mut template = etl_template();
+    template.task_type = "batch".to_string();
+    assert_eq!(
+        template.validate(&registry).unwrap_err().error_type,
+        ApiErrorType::InvalidTaskType
+    );
+
+    let mut template = etl_template();
+    template.timeout_ms = 50;
+    assert!(template.validate(&registry).is_err());
+
+    let mut template = etl_template();
+    template.task_type = "quick".to_string();
+    assert!(template.validate(&registry).is_err()); // failure rate only allowed on custom
+}
+
+#[test]
+fn test_builtin_error_template_uses_params() {
+    let registry = TaskTypeRegistry::with_builtin_types();
+    let template = TaskTemplate::builtin()
+        .into_iter()
+        .find(|t| t.name == "error")
+        .unwrap();
+
+    let task = template
+        .instantiate(&registry, TemplateOverrides::default())
+        .unwrap();
+    assert!(matches!(
+        task.task_type,
+        TaskType::Error {
+            error_type: ErrorType::Random,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_instantiate_uses_defaults() {
+    let registry = TaskTypeRegistry::with_builtin_types();
+    let task = etl_template()
+        .instantiate(&registry, TemplateOverrides::default())
+        .unwrap();
+
+    assert_eq!(task.message, "Run ETL");
+    assert_eq!(task.tags, vec!["batch".to_string()]);
+    match task.task_type {
+        TaskType::Custom {
+            name,
+            timeout_ms,
+            failure_rate,
+            ..
+        } => {
+            assert_eq!(name, "ETL Job");
+            assert_eq!(timeout_ms, 8000);
+            assert_eq!(failure_rate, Some(0.25));
+        }
+        other => panic!("unexpected task type: {:?}", other),
+    }
+}
+
+#[test]
+fn test_instantiate_applies_overrides() {
+    let registry = TaskTypeRegistry::with_builtin_types();
+    let task = etl_template()
+        .instantiate(
+            &registry,
+            TemplateOverrides {
+                name: Some("Backfill".to_string()),
+                message: Some("Backfill March".to_string()),
+                timeout_ms: Some(20000),
+                failure_rate: Some(0.0),
+                tags: Some(vec!["adhoc".to_string()]),
+            },
+        )
+        .unwrap();
+
+    assert_eq!(task.name, "Backfill");
+    assert_eq!(task.message, "Backfill March");
+    assert_eq!(task.tags, vec!["adhoc".to_string()]);
+    assert_eq!(task.task_type.get_timeout().as_millis(), 20000);
+
+    let invalid = etl_template().instantiate(
+        &registry,
+        TemplateOverrides {
+            failure_rate: Some(2.0),
+            ..Default::default()
+        },
+    );
+    assert!(invalid.is_err());
+}
+
+#[actix_rt::test]
+async fn test_template_actor_crud() {
+    let templates = TaskTemplateActor::new().start();
+
+    let created = templates
+        .send(CreateTemplate {
+            template: etl_template(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(created.name, "etl");
+
+    let duplicate = templates
+        .send(CreateTemplate {
+            template: etl_template(),
+        })
+        .await
+        .unwrap();
+    assert!(duplicate.is_err());
+
+    let mut changed = etl_template();
+    changed.name = "ignored".to_string();
+    changed.timeout_ms = 9000;
+    let updated = templates
+        .send(UpdateTemplate {
+            name: "etl".to_string(),
+            template: changed,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.name, "etl");
+    assert_eq!(updated.timeout_ms, 9000);
+
+    let all = templates.send(ListTemplates).await.unwrap();
+    assert_eq!(all.len(), 5);
+
+    templates
+        .send(DeleteTemplate {
+            name: "etl".to_string(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    let missing = templates
+        .send(GetTemplate {
+            name: "etl".to_string(),
+        })
+        .await
+        .unwrap();
+    assert!(missing.is_none());
+
+    let not_found = templates
+        .send(DeleteTemplate {
+            name: "etl".to_string(),
+        })
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(not_found.error_type, ApiErrorType::NotFound);
+}
+
+#[actix_rt::test]
+async fn test_template_instantiation_creates_tagged_task() {
+    let templates = TaskTemplateActor::new().start();
+    let manager = TaskManagerActor::new().start();
+
+    let task_request = templates
+        .send(InstantiateTemplate {
+            name: "long".to_string(),
+            overrides: TemplateOverrides {
+                tags: Some(vec!["demo".to_string()]),
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    let task_id = manager.send(task_request).await.unwrap();
+    let task = manager
+        .send(GetTask { id: task_id })
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(task.name, "Long Task");
+    assert_eq!(task.timeout_ms, 10000);
+    assert_eq!(task.tags, vec!["demo".to_string()]);
+}
@@ -0,0 +1,162 @@
+use actix::Actor;
+use serde_json::Value;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use task_core::*;
+
+mod common;
+use common::*;
+
+// Collects the JSON lines a subscriber writes
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Captured {
+    fn subscriber(&self) -> impl tracing::Subscriber + Send + Sync {
+        let writer = self.clone();
+        tracing_subscriber::fmt()
+            .json()
+            .with_current_span(true)
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(move || writer.clone())
+            .finish()
+    }
+
+    fn events(&self) -> Vec<Value> {
+        let bytes = self.0.lock().unwrap().clone();
+        String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+fn message(event: &Value) -> &str {
+    event["fields"]["message"].as_str().unwrap_or_default()
+}
+
+// The events of the task with this id, in the order they were logged
+fn task_events<'a>(events: &'a [Value], id: &str) -> Vec<&'a Value> {
+    events
+        .iter()
+        .filter(|event| event["span"]["task.id"] == id)
+        .collect()
+}
+
+// Its lifecycle, without the debug events in between
+fn lifecycle(events: &[&Value]) -> Vec<String> {
+    events
+        .iter()
+        .filter(|event| event["level"] != "DEBUG")
+        .map(|event| message(event).to_string())
+        .collect()
+}
+
+fn task(name: &str, task_type: TaskType, namespace: Option<&str>) -> CreateTask {
+    CreateTask {
+        name: name.to_string(),
+        message: "Traced".to_string(),
+        task_type,
+        namespace: namespace.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[actix_rt::test]
+async fn test_task_lifecycle_is_logged_in_its_span() {
+    let captured = Captured::default();
+    let _guard = tracing::subscriber::set_default(captured.subscriber());
+    let clock = ManualClock::default();
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .start();
+
+    let quick = TaskType::Quick {
+        timeout_ms: Some(60_000),
+        duration: Some(DurationDistribution::Constant { ms: 1_000 }),
+    };
+    let quick_id = manager
+        .send(task("Quick", quick, Some("team-a")))
+        .await
+        .unwrap();
+    let timing_out = TaskType::Error {
+        timeout_ms: Some(2_000),
+        error_type: ErrorType::Timeout,
+    };
+    let timeout_id = manager.send(task("Slow", timing_out, None)).await.unwrap();
+    settle().await;
+    for _ in 0..2 {
+        clock.advance(Duration::from_secs(1));
+        settle().await;
+    }
+
+    let events = captured.events();
+    let quick_events = task_events(&events, &quick_id.to_string());
+    assert_eq!(
+        lifecycle(&quick_events),
+        vec!["task created", "task started", "task completed"]
+    );
+    for event in &quick_events {
+        assert_eq!(event["span"]["name"], "task");
+        assert_eq!(event["span"]["task.name"], "Quick");
+        assert_eq!(event["span"]["task.type"], "quick");
+        assert_eq!(event["span"]["task.namespace"], "team-a");
+    }
+    let completed = quick_events
+        .iter()
+        .find(|event| message(event) == "task completed")
+        .unwrap();
+    assert_eq!(completed["level"], "INFO");
+    assert_eq!(completed["fields"]["duration_ms"], 1_000);
+
+    let timeout_events = task_events(&events, &timeout_id.to_string());
+    let timed_out = timeout_events
+        .iter()
+        .find(|event| message(event) == "task timed out")
+        .unwrap();
+    assert_eq!(timed_out["level"], "WARN");
+    assert_eq!(timed_out["fields"]["timeout_ms"], 2_000);
+    assert_eq!(timed_out["span"]["task.type"], "error");
+    assert_eq!(timed_out["span"]["task.namespace"], DEFAULT_NAMESPACE);
+}
+
+#[actix_rt::test]
+async fn test_cancelled_task_is_logged_once() {
+    let captured = Captured::default();
+    let _guard = tracing::subscriber::set_default(captured.subscriber());
+    let clock = ManualClock::default();
+    let manager = TaskManagerActor::new()
+        .with_clock(Arc::new(clock.clone()))
+        .start();
+
+    let long = TaskType::Long {
+        timeout_ms: Some(3_600_000),
+        duration: None,
+    };
+    let id = manager.send(task("Cancelled", long, None)).await.unwrap();
+    settle().await;
+    assert!(manager.send(CancelTaskById { id }).await.unwrap());
+    settle().await;
+    // Cancelling again finds nothing to log
+    manager.send(CancelTaskById { id }).await.unwrap();
+    settle().await;
+
+    let events = captured.events();
+    assert_eq!(
+        lifecycle(&task_events(&events, &id.to_string())),
+        vec!["task created", "task started", "task cancelled"]
+    );
+}
@@ -0,0 +1,161 @@
+// Minimal out-of-process worker speaking the /ws/worker protocol.
+//
+// Start the server, then run one or more workers:
+//
+//   cargo run -p task-web --example remote_worker -- [name] [quick,long,...] [region=a,gpu=false]
+//
+// and create tasks with `"executor": "remote"`, e.g.
+//
+//   curl -X POST localhost:3333/api/tasks -H 'content-type: application/json' \
+//     -d '{"name":"r","message":"remote","task_type":{"type":"quick"},"executor":"remote"}'
+//
+// Set WORKER_URL to connect somewhere other than the local server.
+use futures::{SinkExt, StreamExt};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use task_core::{TaskAssignment, WorkerCommand, WorkerRequest};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use uuid::Uuid;
+
+const SERVER_URL: &str = "ws://127.0.0.1:3333/ws/worker";
+const MAX_CONCURRENT: u32 = 2;
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let name = args
+        .next()
+        .unwrap_or_else(|| format!("worker-{}", std::process::id()));
+    let capabilities: Vec<String> = args
+        .next()
+        .map(|list| {
+            list.split(',')
+                .filter(|kind| !kind.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let labels: BTreeMap<String, String> = args
+        .next()
+        .map(|list| {
+            list.split(',')
+                .filter_map(|label| label.split_once('='))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let url = std::env::var("WORKER_URL").unwrap_or_else(|_| SERVER_URL.to_string());
+
+    let (socket, _) = connect_async(url.as_str()).await?;
+    println!("🔌 Connected to {}", url);
+    let (mut write, mut read) = socket.split();
+
+    // Running tasks report through this channel so only the loop below
+    // writes to the socket
+    let (reports, mut outgoing) = mpsc::unbounded_channel::<WorkerRequest>();
+    reports.send(WorkerRequest::Register {
+        name: name.clone(),
+        capabilities,
+        max_concurrent: MAX_CONCURRENT,
+        labels,
+    })?;
+
+    let mut running: HashMap<Uuid, JoinHandle<()>> = HashMap::new();
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+
+    loop {
+        tokio::select! {
+            Some(request) = outgoing.recv() => {
+                let finished = match &request {
+                    WorkerRequest::Complete { task_id, .. } | WorkerRequest::Fail { task_id, .. } => {
+                        running.remove(task_id).is_some()
+                    }
+                    _ => false,
+                };
+                write.send(Message::Text(serde_json::to_string(&request)?.into())).await?;
+
+                // Each finished task frees a slot for one more lease
+                if finished {
+                    let lease = WorkerRequest::Lease { max_tasks: 1 };
+                    write.send(Message::Text(serde_json::to_string(&lease)?.into())).await?;
+                }
+            }
+            _ = heartbeat.tick() => {
+                let beat = serde_json::to_string(&WorkerRequest::Heartbeat)?;
+                write.send(Message::Text(beat.into())).await?;
+            }
+            frame = read.next() => match frame {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<WorkerCommand>(&text)? {
+                    WorkerCommand::Registered { worker_id } => {
+                        println!("✅ Registered as {} ({})", name, worker_id);
+                        reports.send(WorkerRequest::Lease { max_tasks: MAX_CONCURRENT })?;
+                    }
+                    WorkerCommand::Assign { task } => {
+                        println!("📥 Running {} for {}ms", task.name, task.duration_ms);
+                        let task_id = task.task_id;
+                        running.insert(task_id, tokio::spawn(run_task(*task, reports.clone())));
+                    }
+                    WorkerCommand::Cancel { task_id, reason } => {
+                        println!("🛑 Dropping {}: {}", task_id, reason);
+                        if let Some(handle) = running.remove(&task_id) {
+                            handle.abort();
+                        }
+                        reports.send(WorkerRequest::Lease { max_tasks: 1 })?;
+                    }
+                    WorkerCommand::Error { message } => eprintln!("❌ Server error: {}", message),
+                },
+                Some(Ok(Message::Close(_))) | None => break,
+                // Pings are answered by tungstenite itself
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+            }
+        }
+    }
+
+    println!("👋 Connection closed");
+    Ok(())
+}
+
+// Simulates the work the same way a local TaskActor does: the rolled
+// duration plus any latency spike, split into progress steps
+async fn run_task(task: TaskAssignment, reports: mpsc::UnboundedSender<WorkerRequest>) {
+    let plan = &task.faults;
+    // This worker keeps heartbeating for its other tasks, so a frozen task
+    // only hangs here and is ended by its timeout
+    if plan.hang || plan.heartbeat_loss {
+        return;
+    }
+
+    let total = Duration::from_millis(task.duration_ms + plan.extra_latency_ms);
+    let steps = plan.steps.max(1);
+    for step in 1..=steps {
+        let progress = step as f32 / steps as f32;
+        if plan.stall_at.is_some_and(|stall_at| progress > stall_at) {
+            return;
+        }
+        tokio::time::sleep(total / steps).await;
+        let _ = reports.send(WorkerRequest::Progress {
+            task_id: task.task_id,
+            progress,
+        });
+    }
+
+    let failure = if plan.panic {
+        Some("Task panicked on remote worker".to_string())
+    } else {
+        task.failure.clone()
+    };
+    let _ = reports.send(match failure {
+        Some(error) => WorkerRequest::Fail {
+            task_id: task.task_id,
+            error,
+        },
+        None => WorkerRequest::Complete {
+            task_id: task.task_id,
+            result: "Task completed by remote worker".to_string(),
+        },
+    });
+}
@@ -3,53 +3,241 @@ use actix::{Actor, ActorContext, Addr, AsyncContext, Handler, Message, StreamHan
 use actix_web::web;
 use actix_web_actors::ws;
 use maud::{html, Markup, PreEscaped, DOCTYPE};
-use serde_json;
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use task_core::*;
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 
-#[derive(Clone)]
+// The grid's columns, newest task first
+#[derive(Clone, Default)]
 pub struct LiveViewState {
-    pub tasks: Vec<TaskMetadata>,
+    // Queued remote tasks share the In Progress column
+    pub active: Vec<TaskMetadata>,
+    pub completed: Vec<TaskMetadata>,
+    pub errored: Vec<TaskMetadata>,
 }
 
-impl Default for LiveViewState {
-    fn default() -> Self {
-        Self { tasks: Vec::new() }
+impl LiveViewState {
+    pub fn len(&self) -> usize {
+        self.active.len() + self.completed.len() + self.errored.len()
     }
 }
 
 pub struct LiveViewSession {
     id: Uuid,
     hb: Instant,
-    task_manager: Addr<TaskManagerActor>,
+    task_manager: Addr<TaskManagerRouter>,
+    tasks: TaskReader,
     ws_monitor: Addr<WebSocketMonitorActor>,
+    template_actor: Addr<TaskTemplateActor>,
+    templates: Vec<TaskTemplate>,
+    task_types: Arc<TaskTypeRegistry>,
+    load_generator: Addr<LoadGeneratorActor>,
+    // Counts this session in the connected sessions gauge
+    metrics: Metrics,
+    load_stats: LoadStats,
+    // Header statistics for the namespace shown, None until the first refresh
+    task_stats: Option<TaskStats>,
+    // Namespace the grid shows and new tasks go to, None shows every shared one
+    namespace: Option<String>,
+    namespaces: Vec<NamespaceInfo>,
+    // Query language search narrowing every column, see TaskQuery::parse
+    search: Option<String>,
+    search_error: Option<String>,
+    // Snapshot version the grid's tasks were read at
+    tasks_version: Option<u64>,
     state: LiveViewState,
     last_html: String,
+    last_load_html: String,
+    last_stats_html: String,
 }
 
 impl LiveViewSession {
     pub fn new(
-        task_manager: Addr<TaskManagerActor>,
+        task_manager: Addr<TaskManagerRouter>,
+        tasks: TaskReader,
         ws_monitor: Addr<WebSocketMonitorActor>,
+        template_actor: Addr<TaskTemplateActor>,
+        templates: Vec<TaskTemplate>,
+        task_types: Arc<TaskTypeRegistry>,
+        load_generator: Addr<LoadGeneratorActor>,
     ) -> Self {
         Self {
             id: Uuid::new_v4(),
             hb: Instant::now(),
             task_manager,
+            tasks,
             ws_monitor,
+            template_actor,
+            templates,
+            task_types,
+            load_generator,
+            metrics: Metrics::default(),
+            load_stats: LoadStats::default(),
+            task_stats: None,
+            namespace: None,
+            namespaces: Vec::new(),
+            search: None,
+            search_error: None,
+            tasks_version: None,
             state: LiveViewState::default(),
             last_html: String::new(),
+            last_load_html: String::new(),
+            last_stats_html: String::new(),
         }
     }
 
+    pub fn with_namespace(
+        mut self,
+        namespace: Option<String>,
+        namespaces: Vec<NamespaceInfo>,
+    ) -> Self {
+        self.namespace = namespace;
+        self.namespaces = namespaces;
+        self
+    }
+
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    // A search that does not parse is shown with its error and left unapplied
+    pub fn with_search(mut self, search: Option<String>) -> Self {
+        self.set_search(search);
+        self
+    }
+
+    fn set_search(&mut self, search: Option<String>) -> bool {
+        let search = search.filter(|q| !q.trim().is_empty());
+        if let Some(q) = &search {
+            if let Err(error) = TaskQuery::parse(q, chrono::Utc::now(), &self.task_types) {
+                self.search_error = Some(error.message);
+                return false;
+            }
+        }
+        self.search = search;
+        self.search_error = None;
+        true
+    }
+
+    // Reads the session's tasks from the published snapshot, false when
+    // nothing was published since the last read
+    fn load_tasks(&mut self) -> bool {
+        let version = self.tasks.version();
+        if self.tasks_version == Some(version) {
+            return false;
+        }
+
+        // Each column is read straight off the status index, parsed again
+        // every time so relative terms like since:1h keep moving
+        let scope = self.namespace.as_deref();
+        let search = self
+            .search
+            .as_deref()
+            .and_then(|q| TaskQuery::parse(q, chrono::Utc::now(), &self.task_types).ok())
+            .unwrap_or_default();
+        let column = |status: TaskStatus| {
+            if search
+                .filter
+                .status
+                .as_ref()
+                .is_some_and(|wanted| *wanted != status)
+            {
+                return Vec::new();
+            }
+            let filter = TaskFilter {
+                status: Some(status),
+                ..search.filter.clone()
+            };
+            let mut tasks: Vec<TaskMetadata> = self
+                .tasks
+                .select(&filter, scope)
+                .into_iter()
+                .filter(|task| search.matches(task))
+                .collect();
+            tasks.reverse();
+            tasks
+        };
+        let mut active = column(TaskStatus::InProgress);
+        active.extend(column(TaskStatus::Queued));
+        active.sort_by_key(|task| std::cmp::Reverse(task_key(task)));
+        self.state = LiveViewState {
+            active,
+            completed: column(TaskStatus::Completed),
+            errored: column(TaskStatus::Error),
+        };
+        self.tasks_version = Some(version);
+        true
+    }
+
+    // Load generator settings for the panel's pattern and rate inputs,
+    // spreading the tasks evenly over the session's templates
+    fn load_config(
+        &self,
+        pattern: &str,
+        rate_per_sec: f64,
+    ) -> Result<LoadGeneratorConfig, ApiError> {
+        let pattern = match pattern {
+            "constant" => ArrivalPattern::Constant { rate_per_sec },
+            "poisson" => ArrivalPattern::Poisson { rate_per_sec },
+            "bursty_ramp" => ArrivalPattern::BurstyRamp {
+                start_rate_per_sec: rate_per_sec,
+                peak_rate_per_sec: rate_per_sec * 4.0,
+                ramp_secs: 30,
+                burst_every_secs: 10,
+                burst_size: (rate_per_sec * 5.0).ceil() as u32,
+            },
+            other => {
+                return Err(ApiError::validation_error(
+                    format!("Unknown arrival pattern '{}'", other),
+                    Some(serde_json::json!({
+                        "field": "pattern",
+                        "valid_values": ["constant", "poisson", "bursty_ramp"]
+                    })),
+                ));
+            }
+        };
+
+        let mix = self
+            .templates
+            .iter()
+            .map(|template| {
+                Ok(LoadMixEntry {
+                    weight: 1,
+                    task: template.instantiate(&self.task_types, TemplateOverrides::default())?,
+                })
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+
+        Ok(LoadGeneratorConfig {
+            pattern,
+            mix,
+            duration_secs: None,
+            max_tasks: None,
+            seed: None,
+        })
+    }
+
+    fn template_button_label(template: &TaskTemplate) -> String {
+        let icon = match template.task_type.as_str() {
+            "quick" => "⚡",
+            "long" => "⏰",
+            "error" => "💥",
+            _ => "🛠️",
+        };
+        format!("{} {}", icon, template.display_name)
+    }
+
     fn hb(&self, ctx: &mut ws::WebsocketContext<Self>) {
         ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
             if Instant::now().duration_since(act.hb) > CLIENT_TIMEOUT {
-                println!("LiveView client heartbeat failed, disconnecting!");
+                warn!(session.id = %act.id, "LiveView client heartbeat failed, disconnecting");
                 ctx.stop();
                 return;
             }
@@ -154,6 +342,68 @@ impl LiveViewSession {
                                 letter-spacing: 0.5px;
                             }
                             .btn:hover { transform: translateY(-2px); }
+                            .load-panel {
+                                display: flex;
+                                gap: 10px;
+                                justify-content: center;
+                                align-items: center;
+                                flex-wrap: wrap;
+                                margin-top: 20px;
+                                font-size: 14px;
+                            }
+                            .load-panel select, .load-panel input {
+                                padding: 8px;
+                                border: 1px solid #cbd5e0;
+                                border-radius: 6px;
+                            }
+                            .load-panel input { width: 80px; }
+                            .namespace-switcher {
+                                display: flex;
+                                gap: 10px;
+                                justify-content: center;
+                                align-items: center;
+                                margin-bottom: 20px;
+                                font-size: 14px;
+                            }
+                            .search-box {
+                                display: flex;
+                                gap: 10px;
+                                justify-content: center;
+                                align-items: center;
+                                flex-wrap: wrap;
+                                margin-bottom: 20px;
+                                font-size: 14px;
+                            }
+                            .search-box input {
+                                padding: 8px;
+                                border: 1px solid #cbd5e0;
+                                border-radius: 6px;
+                                width: 420px;
+                                max-width: 100%;
+                                font-family: monospace;
+                            }
+                            .search-box .btn { padding: 8px 16px; font-size: 13px; }
+                            .search-status { color: #4a5568; min-width: 140px; }
+                            .namespace-switcher select {
+                                padding: 8px;
+                                border: 1px solid #cbd5e0;
+                                border-radius: 6px;
+                            }
+                            .load-panel .btn { padding: 8px 16px; font-size: 13px; }
+                            .btn-load { background: #38a169; color: white; }
+                            .btn-load-stop { background: #718096; color: white; }
+                            .load-stats { color: #4a5568; min-width: 260px; }
+                            .task-stats {
+                                display: flex;
+                                justify-content: center;
+                                margin-bottom: 20px;
+                                font-size: 13px;
+                                color: #4a5568;
+                            }
+                            .task-stats table { border-collapse: collapse; }
+                            .task-stats th, .task-stats td { padding: 2px 10px; text-align: right; }
+                            .task-stats th:first-child, .task-stats td:first-child { text-align: left; }
+                            .task-stats .task-stats-type td { color: #718096; }
                             .btn-quick {
                                 background: linear-gradient(45deg, #4facfe, #00f2fe);
                                 color: white;
@@ -166,6 +416,10 @@ impl LiveViewSession {
                                 background: linear-gradient(45deg, #ff6b6b, #ffa500);
                                 color: white;
                             }
+                            .btn-custom {
+                                background: linear-gradient(45deg, #667eea, #764ba2);
+                                color: white;
+                            }
                             .task-grid {
                                 display: grid;
                                 grid-template-columns: repeat(auto-fit, minmax(350px, 1fr));
@@ -376,20 +630,29 @@ impl LiveViewSession {
                         (shared_header::render_header())
                         div class="header" {
                             h1 { "Task Overlord LiveView" }
+                            (self.render_task_stats())
+                            (self.render_namespace_switcher())
+                            (self.render_search_box())
                             div class="controls" {
-                                button class="btn btn-quick" onclick="openTaskModal('quick')" { "⚡ Quick Task (2s)" }
-                                button class="btn btn-long" onclick="openTaskModal('long')" { "⏰ Long Task (10s)" }
-                                button class="btn btn-error" onclick="openTaskModal('error')" { "💥 Error Task" }
+                                @for template in &self.templates {
+                                    button class={"btn btn-" (template.task_type)} onclick={"openTaskModal('" (template.name) "')"} title=(template.description) {
+                                        (Self::template_button_label(template))
+                                    }
+                                }
                             }
+                            (self.render_load_panel())
                         }
                         div class="task-grid" id="task-grid" {
-                            (self.render_task_column("In Progress", &self.get_tasks_by_status(TaskStatus::InProgress), "in-progress"))
-                            (self.render_task_column("Completed", &self.get_tasks_by_status(TaskStatus::Completed), "completed"))
-                            (self.render_task_column("Error", &self.get_tasks_by_status(TaskStatus::Error), "error"))
+                            (self.render_task_column("In Progress", &self.state.active, "in-progress"))
+                            (self.render_task_column("Completed", &self.state.completed, "completed"))
+                            (self.render_task_column("Error", &self.state.errored, "error"))
                         }
 
                         // Task Creation Modal (Server-Generated - will be updated by JS)
-                        (modal::render_task_modal("custom"))
+                        @if let Some(template) = self.templates.first() {
+                            (modal::render_task_modal(template, &self.task_types))
+                        }
+                        (modal::render_template_data(&self.templates, &self.task_types))
                     }
                     script src="/static/app.js" {}
                 }
@@ -424,7 +687,7 @@ impl LiveViewSession {
 
     fn render_task_card(&self, task: &TaskMetadata) -> Markup {
         let status_class = match task.status {
-            TaskStatus::InProgress => "in-progress",
+            TaskStatus::Queued | TaskStatus::InProgress => "in-progress",
             TaskStatus::Completed => "completed",
             TaskStatus::Error => "error",
         };
@@ -434,7 +697,28 @@ impl LiveViewSession {
                 div class="task-name" { (task.name) }
                 div class="task-message" { (task.message) }
                 div class="task-meta" {
-                    div { "Started: " (task.started_at.format("%H:%M:%S")) }
+                    @if task.status == TaskStatus::Queued && !task.attempt_errors.is_empty() {
+                        div style="color: #805ad5;" { "Waiting to retry" }
+                    } @else if task.status == TaskStatus::Queued {
+                        div style="color: #805ad5;" { "Queued for a remote worker" }
+                        @if let Some(reason) = &task.queue_reason {
+                            div style="color: #805ad5;" { (reason) }
+                        }
+                    } @else {
+                        div { "Started: " (task.started_at.format("%H:%M:%S")) }
+                    }
+                    @if self.namespace.is_none() && task.namespace != DEFAULT_NAMESPACE {
+                        div { "Namespace: " (task.namespace) }
+                    }
+                    @if let Some(worker) = &task.worker {
+                        div { "Worker: " (worker) }
+                    }
+                    @if task.max_attempts > 1 {
+                        div { "Attempt: " (task.attempt) "/" (task.max_attempts) }
+                    }
+                    @for attempt_error in &task.attempt_errors {
+                        div style="color: #d69e2e;" { (attempt_error) }
+                    }
                     @if let Some(finished_at) = task.finished_at {
                         div { "Finished: " (finished_at.format("%H:%M:%S")) }
                     }
@@ -447,8 +731,14 @@ impl LiveViewSession {
                     @if let Some(error) = &task.error {
                         div style="color: #e53e3e;" { "Error: " (error) }
                     }
+                    @if task.status == TaskStatus::InProgress && task.progress > 0.0 {
+                        div { "Progress: " ((task.progress * 100.0).round()) "%" }
+                    }
+                    @if !task.faults.is_empty() {
+                        div style="color: #d69e2e;" { "Faults: " (task.faults.join(", ")) }
+                    }
                 }
-                @if task.status == TaskStatus::InProgress {
+                @if task.status.is_active() {
                     div class="task-actions" {
                         button class="btn-cancel" onclick={"cancelTask('" (task.id) "')"} { "Cancel" }
                     }
@@ -457,49 +747,272 @@ impl LiveViewSession {
         }
     }
 
-    fn get_tasks_by_status(&self, status: TaskStatus) -> Vec<TaskMetadata> {
-        self.state
-            .tasks
-            .iter()
-            .filter(|task| task.status == status)
-            .cloned()
-            .collect()
+    // Private namespaces are not listed, only shown while the session is
+    // scoped to one
+    fn render_namespace_switcher(&self) -> Markup {
+        html! {
+            div class="namespace-switcher" {
+                label for="namespace-select" { "🗂️ Namespace" }
+                select id="namespace-select" onchange="switchNamespace(this.value)" {
+                    option value="" selected[self.namespace.is_none()] { "All shared" }
+                    @for namespace in &self.namespaces {
+                        option value=(namespace.name)
+                            selected[self.namespace.as_deref() == Some(namespace.name.as_str())]
+                        {
+                            (namespace.name)
+                        }
+                    }
+                    @if let Some(name) = self
+                        .namespace
+                        .as_deref()
+                        .filter(|name| !self.namespaces.iter().any(|namespace| namespace.name == *name))
+                    {
+                        option value=(name) selected { (name) }
+                    }
+                }
+            }
+        }
+    }
+
+    fn render_search_box(&self) -> Markup {
+        html! {
+            div class="search-box" {
+                input type="search" id="task-search" value=(self.search.as_deref().unwrap_or(""))
+                    placeholder="status:error type:custom name:\"etl*\" duration>5s since:1h"
+                    onkeydown="if (event.key === 'Enter') searchTasks(this.value)";
+                button class="btn btn-load" onclick="searchTasks(document.getElementById('task-search').value)" { "Search" }
+                (self.render_search_status())
+            }
+        }
+    }
+
+    fn render_search_status(&self) -> Markup {
+        html! {
+            span class="search-status" id="search-status" {
+                @if let Some(error) = &self.search_error {
+                    span style="color: #e53e3e;" { (error) }
+                } @else if self.search.is_some() {
+                    (self.state.len()) " matching tasks"
+                }
+            }
+        }
+    }
+
+    fn send_search_status_update(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        let message_str = serde_json::json!({
+            "type": "search_update",
+            "html": self.render_search_status().into_string()
+        })
+        .to_string();
+
+        let ws_monitor = self.ws_monitor.clone();
+        let session_id = self.id;
+        let content_for_log = message_str.clone();
+        let size_bytes = message_str.len();
+        actix::spawn(async move {
+            let _ = ws_monitor
+                .send(LogWebSocketMessage {
+                    session_id,
+                    direction: WsMessageDirection::Outgoing,
+                    message_type: "search_update".to_string(),
+                    content: content_for_log,
+                    size_bytes,
+                })
+                .await;
+        });
+
+        ctx.text(message_str);
+    }
+
+    fn render_load_panel(&self) -> Markup {
+        html! {
+            div class="load-panel" id="load-panel" {
+                span { "📈 Load generator" }
+                select id="load-pattern" {
+                    option value="constant" { "Constant" }
+                    option value="poisson" { "Poisson" }
+                    option value="bursty_ramp" { "Bursty ramp" }
+                }
+                input type="number" id="load-rate" value="2" min="0.1" max="1000" step="0.1" title="Tasks per second";
+                span { "tasks/s" }
+                button class="btn btn-load" onclick="startLoad()" { "Start" }
+                button class="btn btn-load-stop" onclick="stopLoad()" { "Stop" }
+                (self.render_load_stats())
+            }
+        }
+    }
+
+    fn render_load_stats(&self) -> Markup {
+        let stats = &self.load_stats;
+        html! {
+            span class="load-stats" id="load-stats" {
+                @if stats.running {
+                    "Running: " (format!("{:.1}", stats.recent_rate_per_sec)) " tasks/s (target "
+                    (format!("{:.1}", stats.target_rate_per_sec)) "), "
+                    (stats.tasks_created) " created"
+                } @else if stats.tasks_created > 0 {
+                    "Stopped after " (stats.tasks_created) " tasks, "
+                    (format!("{:.1}", stats.achieved_rate_per_sec)) " tasks/s on average"
+                } @else {
+                    "Idle"
+                }
+            }
+        }
+    }
+
+    fn send_load_stats_update(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        let load_html = self.render_load_stats().into_string();
+        if load_html == self.last_load_html {
+            return;
+        }
+
+        let message_str = serde_json::json!({
+            "type": "load_stats_update",
+            "html": load_html
+        })
+        .to_string();
+
+        let ws_monitor = self.ws_monitor.clone();
+        let session_id = self.id;
+        let content_for_log = message_str.clone();
+        let size_bytes = message_str.len();
+        actix::spawn(async move {
+            let _ = ws_monitor
+                .send(LogWebSocketMessage {
+                    session_id,
+                    direction: WsMessageDirection::Outgoing,
+                    message_type: "load_stats_update".to_string(),
+                    content: content_for_log,
+                    size_bytes,
+                })
+                .await;
+        });
+
+        ctx.text(message_str);
+        self.last_load_html = load_html;
     }
 
-    async fn update_tasks(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
-        match self.task_manager.send(GetAllTasks).await {
-            Ok(tasks) => {
-                self.state.tasks = tasks;
-                self.send_html_update(ctx);
+    // Every rolling window, with the longest broken down by task type
+    fn render_task_stats(&self) -> Markup {
+        let rows: Vec<(String, &StatsSummary, bool)> = match &self.task_stats {
+            Some(stats) => {
+                let mut rows = Vec::new();
+                let windows: Vec<&WindowStats> = STATS_WINDOWS
+                    .iter()
+                    .filter_map(|(name, _)| stats.window(name))
+                    .collect();
+                for window in &windows {
+                    rows.push((format!("Last {}", window.window), &window.overall, false));
+                }
+                if let Some(window) = windows.last() {
+                    for (task_type, summary) in &window.by_type {
+                        rows.push((format!("↳ {}", task_type), summary, true));
+                    }
+                }
+                rows
             }
-            Err(e) => {
-                println!("Failed to get tasks: {}", e);
+            None => Vec::new(),
+        };
+        let percent = |rate: Option<f64>| match rate {
+            Some(rate) => format!("{:.0}%", rate * 100.0),
+            None => "–".to_string(),
+        };
+        let ms = |duration: Option<u64>| match duration {
+            Some(duration) => format!("{}ms", duration),
+            None => "–".to_string(),
+        };
+        html! {
+            div class="task-stats" id="task-stats" {
+                @if rows.is_empty() {
+                    "No task statistics yet"
+                } @else {
+                    table {
+                        tr {
+                            th { "📊" } th { "Tasks" } th { "Success" } th { "Timeout" }
+                            th { "p50" } th { "p90" } th { "p99" }
+                        }
+                        @for (label, summary, by_type) in &rows {
+                            tr class=[by_type.then_some("task-stats-type")] {
+                                td { (label) }
+                                td { (summary.total) }
+                                td { (percent(summary.success_rate)) }
+                                td { (percent(summary.timeout_rate)) }
+                                td { (ms(summary.duration_ms.p50)) }
+                                td { (ms(summary.duration_ms.p90)) }
+                                td { (ms(summary.duration_ms.p99)) }
+                            }
+                        }
+                    }
+                }
             }
         }
     }
 
+    fn send_task_stats_update(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        let stats_html = self.render_task_stats().into_string();
+        if stats_html == self.last_stats_html {
+            return;
+        }
+
+        let message_str = serde_json::json!({
+            "type": "stats_update",
+            "html": stats_html
+        })
+        .to_string();
+
+        let ws_monitor = self.ws_monitor.clone();
+        let session_id = self.id;
+        let content_for_log = message_str.clone();
+        let size_bytes = message_str.len();
+        actix::spawn(async move {
+            let _ = ws_monitor
+                .send(LogWebSocketMessage {
+                    session_id,
+                    direction: WsMessageDirection::Outgoing,
+                    message_type: "stats_update".to_string(),
+                    content: content_for_log,
+                    size_bytes,
+                })
+                .await;
+        });
+
+        ctx.text(message_str);
+        self.last_stats_html = stats_html;
+    }
+
     fn render_task_grid(&self) -> Markup {
         html! {
-            (self.render_task_column("In Progress", &self.get_tasks_by_status(TaskStatus::InProgress), "in-progress"))
-            (self.render_task_column("Completed", &self.get_tasks_by_status(TaskStatus::Completed), "completed"))
-            (self.render_task_column("Error", &self.get_tasks_by_status(TaskStatus::Error), "error"))
+            (self.render_task_column("In Progress", &self.state.active, "in-progress"))
+            (self.render_task_column("Completed", &self.state.completed, "completed"))
+            (self.render_task_column("Error", &self.state.errored, "error"))
+        }
+    }
+
+    fn refresh_tasks(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        if !self.load_tasks() {
+            return;
+        }
+        debug!(
+            session.id = %self.id,
+            tasks = self.state.len(),
+            snapshot = ?self.tasks_version,
+            "session refreshed tasks from snapshot"
+        );
+        self.send_html_update(ctx);
+        // The match count follows the grid
+        if self.search.is_some() {
+            self.send_search_status_update(ctx);
         }
     }
 
     fn send_html_update(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
         let new_task_grid_html = self.render_task_grid().into_string();
-        println!(
-            "🔄 Session {} checking for updates: new={} chars, old={} chars",
-            self.id,
-            new_task_grid_html.len(),
-            self.last_html.len()
-        );
 
         if new_task_grid_html != self.last_html {
-            println!(
-                "📤 Session {} sending task grid update ({} chars)",
-                self.id,
-                new_task_grid_html.len()
+            debug!(
+                session.id = %self.id,
+                html_len = new_task_grid_html.len(),
+                "sending task grid update"
             );
             let message = serde_json::json!({
                 "type": "task_grid_update",
@@ -528,10 +1041,7 @@ impl LiveViewSession {
             ctx.text(message_str);
             self.last_html = new_task_grid_html;
         } else {
-            println!(
-                "⏸️  Session {} no changes detected, skipping update",
-                self.id
-            );
+            debug!(session.id = %self.id, "task grid unchanged, skipping update");
         }
     }
 }
@@ -540,42 +1050,18 @@ impl Actor for LiveViewSession {
     type Context = ws::WebsocketContext<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
-        println!("🔌 LiveView session started: {}", self.id);
+        info!(session.id = %self.id, "LiveView session started");
+        self.metrics.liveview_session_started();
         self.hb(ctx);
 
-        // Get initial tasks to render
-        println!("📋 Getting initial tasks for session {}", self.id);
-        let task_manager = self.task_manager.clone();
-        let ctx_addr = ctx.address();
-        let session_id = self.id;
-
-        actix::spawn(async move {
-            match task_manager.send(GetAllTasks).await {
-                Ok(tasks) => {
-                    println!(
-                        "✅ Initial tasks retrieved for session {}: {} tasks",
-                        session_id,
-                        tasks.len()
-                    );
-                    let _ = ctx_addr.send(UpdateTasks { tasks }).await;
-                }
-                Err(e) => {
-                    println!(
-                        "❌ Failed to get initial tasks for session {}: {}",
-                        session_id, e
-                    );
-                }
-            }
-        });
+        // Initial tasks to render
+        self.load_tasks();
 
         // Send initial task grid HTML for tracking
         let initial_task_grid_html = self.render_task_grid().into_string();
         self.last_html = initial_task_grid_html.clone();
-        println!(
-            "📤 Sending initial task grid HTML ({} chars) for session {}",
-            initial_task_grid_html.len(),
-            self.id
-        );
+        self.last_load_html = self.render_load_stats().into_string();
+        self.last_stats_html = self.render_task_stats().into_string();
 
         // Send the full page for first load
         let initial_full_html = self.render_page().into_string();
@@ -583,10 +1069,11 @@ impl Actor for LiveViewSession {
             "type": "full_page_load",
             "html": initial_full_html
         });
-        println!(
-            "📤 Sending full page load ({} chars) for session {}",
-            initial_full_html.len(),
-            self.id
+        debug!(
+            session.id = %self.id,
+            tasks = self.state.len(),
+            html_len = initial_full_html.len(),
+            "sending full page load"
         );
 
         let message_str = message.to_string();
@@ -612,7 +1099,8 @@ impl Actor for LiveViewSession {
     }
 
     fn stopped(&mut self, _: &mut Self::Context) {
-        println!("LiveView session stopped: {}", self.id);
+        info!(session.id = %self.id, "LiveView session stopped");
+        self.metrics.liveview_session_stopped();
     }
 }
 
@@ -628,19 +1116,17 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for LiveViewSession {
             }
             Ok(ws::Message::Text(text)) => {
                 self.hb = Instant::now();
-                println!(
-                    "📨 Session {} received WebSocket message: {}",
-                    self.id, text
-                );
 
                 if let Ok(data) = serde_json::from_str::<serde_json::Value>(&text) {
                     let msg_type = data
                         .get("type")
                         .and_then(|t| t.as_str())
                         .unwrap_or("unknown");
-                    println!(
-                        "📋 Session {} processing message type: {}",
-                        self.id, msg_type
+                    debug!(
+                        session.id = %self.id,
+                        message_type = msg_type,
+                        size_bytes = text.len(),
+                        "received WebSocket message"
                     );
 
                     // Log incoming message
@@ -663,7 +1149,104 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for LiveViewSession {
 
                     match msg_type {
                         "create_task" | "create_custom_task" => {
-                            if let Some(task_type_str) =
+                            // The browser span of the click, the task's span continues it
+                            let traceparent = data
+                                .get(TRACEPARENT)
+                                .and_then(|t| t.as_str())
+                                .map(|t| t.to_string());
+                            if let Some(template_name) =
+                                data.get("template").and_then(|t| t.as_str())
+                            {
+                                let overrides = TemplateOverrides {
+                                    name: data
+                                        .get("name")
+                                        .and_then(|n| n.as_str())
+                                        .map(|n| n.to_string()),
+                                    message: data
+                                        .get("message")
+                                        .and_then(|m| m.as_str())
+                                        .map(|m| m.to_string()),
+                                    timeout_ms: data.get("custom_timeout").and_then(|t| t.as_u64()),
+                                    failure_rate: data
+                                        .get("custom_failure_rate")
+                                        .and_then(|f| f.as_f64())
+                                        .map(|f| f as f32),
+                                    tags: None,
+                                };
+
+                                info!(
+                                    session.id = %self.id,
+                                    template = template_name,
+                                    "session creating task from template"
+                                );
+                                let template_actor = self.template_actor.clone();
+                                let task_manager = self.task_manager.clone();
+                                let ctx_addr = ctx.address();
+                                let session_id = self.id;
+                                let template_name = template_name.to_string();
+                                let namespace = self.namespace.clone();
+
+                                actix::spawn(async move {
+                                    let mut task_request = match template_actor
+                                        .send(InstantiateTemplate {
+                                            name: template_name,
+                                            overrides,
+                                        })
+                                        .await
+                                    {
+                                        Ok(Ok(task_request)) => task_request,
+                                        Ok(Err(e)) => {
+                                            warn!(
+                                                session.id = %session_id,
+                                                error = %e.message,
+                                                "session rejected template task"
+                                            );
+                                            return;
+                                        }
+                                        Err(e) => {
+                                            error!(
+                                                session.id = %session_id,
+                                                error = %e,
+                                                "session failed to instantiate template"
+                                            );
+                                            return;
+                                        }
+                                    };
+                                    task_request.namespace = namespace;
+                                    task_request.traceparent = traceparent;
+
+                                    match task_manager.send(SubmitTask { task: task_request }).await
+                                    {
+                                        Ok(Ok(task_id)) => {
+                                            info!(
+                                                session.id = %session_id,
+                                                task.id = %task_id,
+                                                "session created task"
+                                            );
+                                            // Trigger a refresh after task creation
+                                            tokio::time::sleep(tokio::time::Duration::from_millis(
+                                                100,
+                                            ))
+                                            .await;
+                                            let _ = ctx_addr.send(RefreshTasks).await;
+                                        }
+                                        Ok(Err(error)) => {
+                                            warn!(
+                                                session.id = %session_id,
+                                                error = %error.message,
+                                                "session task rejected"
+                                            );
+                                        }
+                                        Err(e) => {
+                                            error!(
+                                                session.id = %session_id,
+                                                error = %e,
+                                                "session failed to create task"
+                                            );
+                                        }
+                                    }
+                                });
+                            } else if let Some(task_type_str) =
                                 data.get("task_type").and_then(|t| t.as_str())
                             {
                                 // Extract custom parameters for custom tasks
@@ -679,81 +1262,87 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for LiveViewSession {
                                     .unwrap_or("LiveView task")
                                     .to_string();
 
-                                let task_type = match task_type_str {
-                                    "quick" => TaskType::Quick { timeout_ms: None },
-                                    "long" => TaskType::Long { timeout_ms: None },
-                                    "error" => TaskType::Error {
-                                        timeout_ms: None,
-                                        error_type: ErrorType::Random,
-                                    },
-                                    "custom" => {
-                                        let timeout_ms = data
-                                            .get("custom_timeout")
-                                            .and_then(|t| t.as_u64())
-                                            .unwrap_or(5000);
-                                        let failure_rate = data
-                                            .get("custom_failure_rate")
-                                            .and_then(|f| f.as_f64())
-                                            .map(|f| f as f32);
-
-                                        TaskType::Custom {
-                                            name: if task_name.is_empty() {
-                                                "Custom Task".to_string()
-                                            } else {
-                                                task_name.clone()
-                                            },
-                                            timeout_ms,
-                                            failure_rate,
+                                let params = crate::form_task_params(
+                                    task_type_str,
+                                    &task_name,
+                                    data.get("custom_timeout").and_then(|t| t.as_u64()),
+                                    data.get("custom_failure_rate").and_then(|f| f.as_f64()),
+                                    data.get("params").and_then(|p| p.as_object()),
+                                );
+                                let task_type =
+                                    match self.task_types.resolve(task_type_str, &params) {
+                                        Ok(task_type) => task_type,
+                                        Err(e) => {
+                                            warn!(
+                                                session.id = %self.id,
+                                                "task.type" = task_type_str,
+                                                error = %e.message,
+                                                "session rejected task type"
+                                            );
+                                            return;
                                         }
-                                    }
-                                    _ => TaskType::Quick { timeout_ms: None },
-                                };
+                                    };
 
-                                println!(
-                                    "🚀 Session {} creating task: {} (name: {}, message: {})",
-                                    self.id, task_type_str, task_name, task_message
+                                info!(
+                                    session.id = %self.id,
+                                    "task.type" = task_type_str,
+                                    task.name = %task_name,
+                                    "session creating task"
                                 );
                                 let task_manager = self.task_manager.clone();
                                 let ctx_addr = ctx.address();
                                 let session_id = self.id;
+                                let namespace = self.namespace.clone();
+                                let type_name = task_type_str.to_string();
 
                                 actix::spawn(async move {
                                     match task_manager
-                                        .send(CreateTask {
-                                            name: task_name,
-                                            message: task_message,
-                                            task_type,
+                                        .send(SubmitTask {
+                                            task: CreateTask {
+                                                name: task_name,
+                                                message: task_message,
+                                                task_type,
+                                                type_name: Some(type_name),
+                                                namespace,
+                                                traceparent,
+                                                ..Default::default()
+                                            },
                                         })
                                         .await
                                     {
-                                        Ok(task_id) => {
-                                            println!(
-                                                "✅ Session {} task created with ID: {}",
-                                                session_id, task_id
+                                        Ok(Ok(task_id)) => {
+                                            info!(
+                                                session.id = %session_id,
+                                                task.id = %task_id,
+                                                "session created task"
                                             );
                                             // Trigger a refresh after task creation
                                             tokio::time::sleep(tokio::time::Duration::from_millis(
                                                 100,
                                             ))
                                             .await;
-                                            if let Ok(tasks) = task_manager.send(GetAllTasks).await
-                                            {
-                                                println!("📋 Session {} refreshing with {} tasks after creation", session_id, tasks.len());
-                                                let _ = ctx_addr.send(UpdateTasks { tasks }).await;
-                                            }
+                                            let _ = ctx_addr.send(RefreshTasks).await;
+                                        }
+                                        Ok(Err(error)) => {
+                                            warn!(
+                                                session.id = %session_id,
+                                                error = %error.message,
+                                                "session task rejected"
+                                            );
                                         }
                                         Err(e) => {
-                                            println!(
-                                                "❌ Session {} failed to create task: {}",
-                                                session_id, e
+                                            error!(
+                                                session.id = %session_id,
+                                                error = %e,
+                                                "session failed to create task"
                                             );
                                         }
                                     }
                                 });
                             } else {
-                                println!(
-                                    "⚠️  Session {} create_task message missing task_type",
-                                    self.id
+                                warn!(
+                                    session.id = %self.id,
+                                    "create_task message missing template or task_type"
                                 );
                             }
                         }
@@ -761,10 +1350,9 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for LiveViewSession {
                             if let Some(task_id_str) = data.get("task_id").and_then(|t| t.as_str())
                             {
                                 if let Ok(task_id) = Uuid::parse_str(task_id_str) {
-                                    println!("🗑️  Session {} canceling task: {}", self.id, task_id);
+                                    info!(session.id = %self.id, task.id = %task_id, "session cancelling task");
                                     let task_manager = self.task_manager.clone();
                                     let session_id = self.id;
-
                                     let ctx_addr = ctx.address();
                                     actix::spawn(async move {
                                         match task_manager
@@ -772,75 +1360,178 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for LiveViewSession {
                                             .await
                                         {
                                             Ok(canceled) => {
-                                                println!(
-                                                    "✅ Session {} task cancellation result: {}",
-                                                    session_id, canceled
+                                                debug!(
+                                                    session.id = %session_id,
+                                                    task.id = %task_id,
+                                                    canceled,
+                                                    "session task cancellation result"
                                                 );
                                                 // Trigger a refresh after task cancellation
                                                 tokio::time::sleep(
                                                     tokio::time::Duration::from_millis(100),
                                                 )
                                                 .await;
-                                                if let Ok(tasks) =
-                                                    task_manager.send(GetAllTasks).await
-                                                {
-                                                    println!("📋 Session {} refreshing with {} tasks after cancellation", session_id, tasks.len());
-                                                    let _ =
-                                                        ctx_addr.send(UpdateTasks { tasks }).await;
-                                                }
+                                                let _ = ctx_addr.send(RefreshTasks).await;
                                             }
                                             Err(e) => {
-                                                println!(
-                                                    "❌ Session {} failed to cancel task: {}",
-                                                    session_id, e
+                                                error!(
+                                                    session.id = %session_id,
+                                                    task.id = %task_id,
+                                                    error = %e,
+                                                    "session failed to cancel task"
                                                 );
                                             }
                                         }
                                     });
                                 } else {
-                                    println!("⚠️  Session {} cancel_task message has invalid task_id: {}", self.id, task_id_str);
+                                    warn!(
+                                        session.id = %self.id,
+                                        task_id = task_id_str,
+                                        "cancel_task message has invalid task_id"
+                                    );
                                 }
                             } else {
-                                println!(
-                                    "⚠️  Session {} cancel_task message missing task_id",
-                                    self.id
-                                );
+                                warn!(session.id = %self.id, "cancel_task message missing task_id");
+                            }
+                        }
+                        "start_load" => {
+                            let pattern = data
+                                .get("pattern")
+                                .and_then(|p| p.as_str())
+                                .unwrap_or("constant");
+                            let rate_per_sec = data
+                                .get("rate_per_sec")
+                                .and_then(|r| r.as_f64())
+                                .unwrap_or(1.0);
+
+                            match self.load_config(pattern, rate_per_sec) {
+                                Ok(config) => {
+                                    info!(
+                                        session.id = %self.id,
+                                        pattern,
+                                        rate_per_sec,
+                                        "session starting load generator"
+                                    );
+                                    let load_generator = self.load_generator.clone();
+                                    let ctx_addr = ctx.address();
+                                    let session_id = self.id;
+                                    actix::spawn(async move {
+                                        match load_generator.send(StartLoad { config }).await {
+                                            Ok(Ok(stats)) => {
+                                                let _ =
+                                                    ctx_addr.send(UpdateLoadStats { stats }).await;
+                                            }
+                                            Ok(Err(error)) => warn!(
+                                                session.id = %session_id,
+                                                error = %error.message,
+                                                "load generator rejected config"
+                                            ),
+                                            Err(e) => error!(
+                                                session.id = %session_id,
+                                                error = %e,
+                                                "session failed to start load generator"
+                                            ),
+                                        }
+                                    });
+                                }
+                                Err(error) => {
+                                    warn!(
+                                        session.id = %self.id,
+                                        error = %error.message,
+                                        "session sent invalid load settings"
+                                    );
+                                }
                             }
                         }
+                        "stop_load" => {
+                            info!(session.id = %self.id, "session stopping load generator");
+                            let load_generator = self.load_generator.clone();
+                            let ctx_addr = ctx.address();
+                            actix::spawn(async move {
+                                if let Ok(stats) = load_generator.send(StopLoad).await {
+                                    let _ = ctx_addr.send(UpdateLoadStats { stats }).await;
+                                }
+                            });
+                        }
                         "refresh" => {
-                            println!("🔄 Session {} processing refresh request", self.id);
+                            debug!(session.id = %self.id, "session refresh requested");
                             let ctx_addr = ctx.address();
-                            let task_manager = self.task_manager.clone();
-                            let session_id = self.id;
+                            let load_generator = self.load_generator.clone();
 
                             actix::spawn(async move {
-                                match task_manager.send(GetAllTasks).await {
-                                    Ok(tasks) => {
-                                        println!(
-                                            "📋 Session {} refresh found {} tasks",
-                                            session_id,
-                                            tasks.len()
-                                        );
-                                        let _ = ctx_addr.send(UpdateTasks { tasks }).await;
-                                    }
-                                    Err(e) => {
-                                        println!("❌ Session {} refresh failed: {}", session_id, e);
-                                    }
+                                if let Ok(stats) = load_generator.send(GetLoadStats).await {
+                                    let _ = ctx_addr.send(UpdateLoadStats { stats }).await;
                                 }
                             });
+                            self.task_stats = Some(
+                                self.tasks
+                                    .stats(self.namespace.as_deref(), chrono::Utc::now()),
+                            );
+                            self.send_task_stats_update(ctx);
+                            self.refresh_tasks(ctx);
+                        }
+                        "switch_namespace" => {
+                            let namespace = data
+                                .get("namespace")
+                                .and_then(|n| n.as_str())
+                                .filter(|n| !n.is_empty());
+                            if let Some(Err(error)) = namespace.map(validate_namespace) {
+                                warn!(
+                                    session.id = %self.id,
+                                    error = %error.message,
+                                    "session sent invalid namespace"
+                                );
+                                return;
+                            }
+
+                            info!(
+                                session.id = %self.id,
+                                namespace = namespace.unwrap_or("(all shared)"),
+                                "session switching namespace"
+                            );
+                            self.namespace = namespace.map(|n| n.to_string());
+                            // Read again even if nothing changed since
+                            self.tasks_version = None;
+                            self.refresh_tasks(ctx);
+                        }
+                        "search" => {
+                            let q = data
+                                .get("q")
+                                .and_then(|q| q.as_str())
+                                .map(|q| q.to_string());
+                            if self.set_search(q) {
+                                debug!(
+                                    session.id = %self.id,
+                                    query = self.search.as_deref().unwrap_or("(everything)"),
+                                    "session searching"
+                                );
+                                self.tasks_version = None;
+                                self.refresh_tasks(ctx);
+                            } else {
+                                debug!(
+                                    session.id = %self.id,
+                                    error = self.search_error.as_deref().unwrap_or_default(),
+                                    "session sent invalid search"
+                                );
+                            }
+                            // A search that applied already sent its count with the grid
+                            if self.search_error.is_some() || self.search.is_none() {
+                                self.send_search_status_update(ctx);
+                            }
                         }
                         _ => {
-                            println!("⚠️  Session {} unknown message type: {}", self.id, msg_type);
+                            warn!(session.id = %self.id, message_type = msg_type, "unknown message type");
                         }
                     }
                 } else {
-                    println!(
-                        "❌ Session {} failed to parse JSON message: {}",
-                        self.id, text
+                    warn!(
+                        session.id = %self.id,
+                        message = %text,
+                        "failed to parse JSON message"
                     );
                 }
             }
-            Ok(ws::Message::Binary(_)) => println!("Unexpected binary message"),
+            Ok(ws::Message::Binary(_)) => warn!(session.id = %self.id, "unexpected binary message"),
             Ok(ws::Message::Close(reason)) => {
                 ctx.close(reason);
                 ctx.stop();
@@ -850,30 +1541,31 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for LiveViewSession {
     }
 }
 
+// Re-renders the grid if a newer snapshot was published
 #[derive(Message)]
 #[rtype(result = "()")]
-pub struct UpdateTasks {
-    pub tasks: Vec<TaskMetadata>,
-}
+pub struct RefreshTasks;
 
-impl Handler<UpdateTasks> for LiveViewSession {
+impl Handler<RefreshTasks> for LiveViewSession {
     type Result = ();
 
-    fn handle(&mut self, msg: UpdateTasks, ctx: &mut Self::Context) {
-        println!(
-            "📝 Session {} updating tasks: received {} tasks",
-            self.id,
-            msg.tasks.len()
-        );
+    fn handle(&mut self, _msg: RefreshTasks, ctx: &mut Self::Context) {
+        self.refresh_tasks(ctx);
+    }
+}
 
-        // Log task details for debugging
-        for task in &msg.tasks {
-            println!("   Task: {} - {} ({:?})", task.id, task.name, task.status);
-        }
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct UpdateLoadStats {
+    pub stats: LoadStats,
+}
 
-        self.state.tasks = msg.tasks;
-        println!("📤 Session {} triggering HTML update", self.id);
-        self.send_html_update(ctx);
+impl Handler<UpdateLoadStats> for LiveViewSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: UpdateLoadStats, ctx: &mut Self::Context) {
+        self.load_stats = msg.stats;
+        self.send_load_stats_update(ctx);
     }
 }
 
@@ -882,6 +1574,33 @@ pub async fn websocket_handler(
     stream: web::Payload,
     data: web::Data<crate::AppState>,
 ) -> Result<actix_web::HttpResponse, actix_web::Error> {
-    let session = LiveViewSession::new(data.task_manager.clone(), data.ws_monitor.clone());
+    let templates = data.templates.send(ListTemplates).await.unwrap_or_default();
+    let namespaces = data
+        .task_manager
+        .send(ListNamespaces)
+        .await
+        .unwrap_or_default();
+    let query = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+        .map(|query| query.into_inner())
+        .unwrap_or_default();
+    // ?ns=name scopes the session, an invalid name falls back to all shared
+    let namespace = query
+        .get("ns")
+        .cloned()
+        .filter(|name| validate_namespace(name).is_ok());
+    // ?q= starts the session with a search
+    let search = query.get("q").cloned();
+    let session = LiveViewSession::new(
+        data.task_manager.clone(),
+        data.tasks.clone(),
+        data.ws_monitor.clone(),
+        data.templates.clone(),
+        templates,
+        data.task_types.clone(),
+        data.load_generator.clone(),
+    )
+    .with_namespace(namespace, namespaces)
+    .with_search(search)
+    .with_metrics(data.metrics.clone());
     ws::start(session, &req, stream)
 }
@@ -1,21 +1,102 @@
 use actix::Actor;
 use actix_files as fs;
 use actix_web::{
-    delete, get, http::header, middleware::Logger, post, web, App, HttpResponse, HttpServer,
-    Responder, Result,
+    delete, get, http::header, middleware::Logger, post, put, web, App, HttpRequest, HttpResponse,
+    HttpServer, Responder, Result,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
 use task_core::*;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 mod liveview;
 mod modal;
 mod shared_header;
+mod telemetry;
+mod worker;
+
+const ARCHIVE_DIR: &str = "data/archive";
+// Finished tasks stay in memory this long before moving to the archive
+const TASK_RETENTION: Duration = Duration::from_secs(15 * 60);
+// The task managers are configured through the environment:
+//
+//   TASK_MANAGER_SHARDS=8             task manager actors, each on its own
+//                                     thread, see TaskManagerRouter, 4
+//                                     when unset
+//   TASK_EXECUTION_MODE=lightweight   trades the per-task actors for far
+//                                     more concurrent tasks, actor when
+//                                     unset
+const DEFAULT_TASK_MANAGER_SHARDS: usize = 4;
+
+fn task_manager_shards() -> usize {
+    let requested = std::env::var("TASK_MANAGER_SHARDS").unwrap_or_default();
+    if requested.is_empty() {
+        return DEFAULT_TASK_MANAGER_SHARDS;
+    }
+    match requested.trim().parse::<usize>() {
+        Ok(shards) if shards > 0 => shards,
+        _ => {
+            warn!(
+                task_manager_shards = %requested,
+                "invalid TASK_MANAGER_SHARDS, expected a positive number, using {}",
+                DEFAULT_TASK_MANAGER_SHARDS
+            );
+            DEFAULT_TASK_MANAGER_SHARDS
+        }
+    }
+}
+
+fn task_execution_mode() -> ExecutionMode {
+    let requested = std::env::var("TASK_EXECUTION_MODE").unwrap_or_default();
+    match requested.to_lowercase().as_str() {
+        "" | "actor" => ExecutionMode::Actor,
+        "lightweight" => ExecutionMode::Lightweight,
+        _ => {
+            warn!(
+                task_execution_mode = %requested,
+                "unknown TASK_EXECUTION_MODE, expected actor or lightweight, using actor"
+            );
+            ExecutionMode::Actor
+        }
+    }
+}
 
 // Application state
 pub struct AppState {
-    pub task_manager: actix::Addr<TaskManagerActor>,
+    pub task_manager: actix::Addr<TaskManagerRouter>,
+    // Task queries are answered from here without messaging the managers
+    pub tasks: TaskReader,
     pub ws_monitor: actix::Addr<WebSocketMonitorActor>,
+    pub archive: TaskArchive,
+    pub templates: actix::Addr<TaskTemplateActor>,
+    pub task_types: Arc<TaskTypeRegistry>,
+    pub load_generator: actix::Addr<LoadGeneratorActor>,
+    // Shared by the manager shards, the WebSocket monitor and the LiveView
+    // sessions, served on /metrics
+    pub metrics: Metrics,
+}
+
+// Maps errors returned by the actors onto the matching HTTP status
+fn api_error_response(error: ApiError) -> HttpResponse {
+    match error.error_type {
+        ApiErrorType::NotFound | ApiErrorType::TaskNotFound => {
+            HttpResponse::NotFound().json(ApiResponse::<()>::error(error))
+        }
+        ApiErrorType::InternalError => {
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(error))
+        }
+        // Namespace quotas, the error says when a retry can succeed
+        ApiErrorType::ConcurrencyError => {
+            let mut response = HttpResponse::TooManyRequests();
+            if let Some(retry_after) = error.retry_after_secs() {
+                response.insert_header((header::RETRY_AFTER, retry_after.to_string()));
+            }
+            response.json(ApiResponse::<()>::error(error))
+        }
+        _ => HttpResponse::BadRequest().json(ApiResponse::<()>::error(error)),
+    }
 }
 
 // Request/Response types
@@ -24,6 +105,20 @@ struct CreateTaskRequest {
     name: String,
     message: String,
     task_type: TaskTypeRequest,
+    // Replays a recorded run, see TaskMetadata::seed
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default)]
+    faults: Option<FaultProfile>,
+    // "remote" queues the task for a worker connected on /ws/worker
+    #[serde(default)]
+    executor: Executor,
+    // Attempts when the executor is lost, see RetryPolicy
+    #[serde(default)]
+    retry: Option<RetryPolicy>,
+    // Required and preferred worker labels, remote tasks only
+    #[serde(default)]
+    affinity: Affinity,
 }
 
 #[derive(Deserialize)]
@@ -33,6 +128,35 @@ struct CreateTaskFormRequest {
     task_type: String,
     custom_timeout: Option<u64>,
     custom_failure_rate: Option<f32>,
+    // Extra type-specific parameters, e.g. error_type
+    #[serde(default)]
+    params: TaskParams,
+}
+
+// Maps the flat fields the dashboard forms send onto task type parameters.
+// The custom timeout and failure rate only apply to the custom type, the
+// other types keep their own defaults.
+fn form_task_params(
+    task_type: &str,
+    name: &str,
+    timeout_ms: Option<u64>,
+    failure_rate: Option<f64>,
+    extra: Option<&TaskParams>,
+) -> TaskParams {
+    let mut params = extra.cloned().unwrap_or_default();
+    if !name.trim().is_empty() {
+        params.insert("custom_name".to_string(), name.into());
+    }
+    if task_type != "custom" {
+        return params;
+    }
+    if let Some(timeout_ms) = timeout_ms {
+        params.insert("timeout_ms".to_string(), timeout_ms.into());
+    }
+    if let Some(rate) = failure_rate {
+        params.insert("failure_rate".to_string(), rate.into());
+    }
+    params
 }
 
 impl CreateTaskFormRequest {
@@ -71,67 +195,17 @@ impl CreateTaskFormRequest {
             ));
         }
 
-        // Validate custom task parameters
-        if self.task_type == "custom" {
-            if let Some(timeout) = self.custom_timeout {
-                if timeout > 300000 {
-                    return Err(ApiError::validation_error(
-                        "Custom task timeout cannot exceed 5 minutes (300000ms)".to_string(),
-                        Some(serde_json::json!({
-                            "field": "custom_timeout",
-                            "provided_value": timeout,
-                            "max_value": 300000
-                        })),
-                    ));
-                }
-                if timeout < 100 {
-                    return Err(ApiError::validation_error(
-                        "Custom task timeout must be at least 100ms".to_string(),
-                        Some(serde_json::json!({
-                            "field": "custom_timeout",
-                            "provided_value": timeout,
-                            "min_value": 100
-                        })),
-                    ));
-                }
-            }
-
-            if let Some(rate) = self.custom_failure_rate {
-                if rate < 0.0 || rate > 1.0 {
-                    return Err(ApiError::validation_error(
-                        "Failure rate must be between 0.0 and 1.0".to_string(),
-                        Some(serde_json::json!({
-                            "field": "custom_failure_rate",
-                            "provided_value": rate,
-                            "valid_range": "0.0-1.0"
-                        })),
-                    ));
-                }
-            }
-        }
-
         Ok(())
     }
 
-    fn to_task_type(&self) -> TaskType {
-        match self.task_type.as_str() {
-            "quick" => TaskType::Quick { timeout_ms: None },
-            "long" => TaskType::Long { timeout_ms: None },
-            "error" => TaskType::Error {
-                timeout_ms: None,
-                error_type: ErrorType::Random,
-            },
-            "custom" => TaskType::Custom {
-                name: if self.name.trim().is_empty() {
-                    "Custom Task".to_string()
-                } else {
-                    self.name.clone()
-                },
-                timeout_ms: self.custom_timeout.unwrap_or(5000),
-                failure_rate: self.custom_failure_rate,
-            },
-            _ => TaskType::Quick { timeout_ms: None },
-        }
+    fn task_params(&self) -> TaskParams {
+        form_task_params(
+            &self.task_type,
+            &self.name,
+            self.custom_timeout,
+            self.custom_failure_rate.map(f64::from),
+            Some(&self.params),
+        )
     }
 }
 
@@ -161,96 +235,374 @@ impl CreateTaskRequest {
             ));
         }
 
-        // Validate task type specific constraints
-        match &self.task_type {
-            TaskTypeRequest::Custom {
-                timeout_ms,
-                failure_rate,
-                ..
-            } => {
-                if *timeout_ms > 300000 {
-                    // 5 minutes max
+        if let Some(faults) = &self.faults {
+            faults.validate()?;
+        }
+        if let Some(retry) = &self.retry {
+            retry.validate()?;
+        }
+        self.affinity.validate()?;
+        if !self.affinity.is_empty() && self.executor != Executor::Remote {
+            return Err(ApiError::validation_error(
+                "Worker affinity only applies to remote tasks".to_string(),
+                Some(serde_json::json!({
+                    "field": "affinity",
+                    "executor": self.executor
+                })),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct ArchiveQueryRequest {
+    from: Option<String>,
+    to: Option<String>,
+    status: Option<String>,
+    name: Option<String>,
+    limit: Option<usize>,
+}
+
+impl ArchiveQueryRequest {
+    fn to_query(&self) -> Result<ArchiveQuery, ApiError> {
+        let status = match &self.status {
+            Some(status) => Some(status.parse::<TaskStatus>().map_err(|e| {
+                ApiError::validation_error(
+                    e,
+                    Some(serde_json::json!({
+                        "field": "status",
+                        "provided_value": status,
+                        "valid_values": ["Queued", "InProgress", "Completed", "Error"]
+                    })),
+                )
+            })?),
+            None => None,
+        };
+
+        Ok(ArchiveQuery {
+            from: self
+                .from
+                .as_deref()
+                .map(|from| parse_archive_date("from", from, false))
+                .transpose()?,
+            to: self
+                .to
+                .as_deref()
+                .map(|to| parse_archive_date("to", to, true))
+                .transpose()?,
+            status,
+            name: self.name.clone().filter(|name| !name.trim().is_empty()),
+            limit: self.limit,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct TaskListRequest {
+    // Query language search, see task_core::query_language
+    q: Option<String>,
+    status: Option<String>,
+    #[serde(rename = "type")]
+    task_type: Option<String>,
+    name: Option<String>,
+    tag: Option<String>,
+    created_after: Option<String>,
+    created_before: Option<String>,
+    sort: Option<String>,
+    order: Option<String>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+fn invalid_param(message: String, field: &str, value: &str, valid: &[impl Serialize]) -> ApiError {
+    ApiError::validation_error(
+        message,
+        Some(serde_json::json!({
+            "field": field,
+            "provided_value": value,
+            "valid_values": valid
+        })),
+    )
+}
+
+impl TaskListRequest {
+    fn to_query(&self, task_types: &TaskTypeRegistry) -> Result<TaskQuery, ApiError> {
+        let mut query = match self.q.as_deref().filter(|q| !q.trim().is_empty()) {
+            Some(q) => {
+                if self.has_filter_params() {
                     return Err(ApiError::validation_error(
-                        "Custom task timeout cannot exceed 5 minutes (300000ms)".to_string(),
+                        "q cannot be combined with status, type, name, tag, created_after or created_before".to_string(),
                         Some(serde_json::json!({
-                            "field": "task_type.timeout_ms",
-                            "provided_value": timeout_ms,
-                            "max_value": 300000
+                            "field": "q",
+                            "provided_value": q
                         })),
                     ));
                 }
-
-                if let Some(rate) = failure_rate {
-                    if *rate < 0.0 || *rate > 1.0 {
-                        return Err(ApiError::validation_error(
-                            "Failure rate must be between 0.0 and 1.0".to_string(),
-                            Some(serde_json::json!({
-                                "field": "task_type.failure_rate",
-                                "provided_value": rate,
-                                "valid_range": "0.0-1.0"
-                            })),
-                        ));
-                    }
-                }
+                TaskQuery::parse(q, chrono::Utc::now(), task_types)?
             }
-            _ => {}
+            None => self.filter_query(task_types)?,
+        };
+
+        if let Some(sort) = &self.sort {
+            query.sort = sort
+                .parse::<SortField>()
+                .map_err(|e| invalid_param(e, "sort", sort, &SortField::NAMES))?;
+        }
+        if let Some(order) = &self.order {
+            query.order = order
+                .parse::<SortOrder>()
+                .map_err(|e| invalid_param(e, "order", order, &["asc", "desc"]))?;
         }
+        query.cursor = self
+            .cursor
+            .as_deref()
+            .filter(|cursor| !cursor.is_empty())
+            .map(TaskCursor::decode)
+            .transpose()?;
+        query.limit = self.limit;
+        query.validate()?;
+        Ok(query)
+    }
 
-        Ok(())
+    fn has_filter_params(&self) -> bool {
+        [
+            &self.status,
+            &self.task_type,
+            &self.name,
+            &self.tag,
+            &self.created_after,
+            &self.created_before,
+        ]
+        .iter()
+        .any(|param| param.is_some())
+    }
+
+    fn filter_query(&self, task_types: &TaskTypeRegistry) -> Result<TaskQuery, ApiError> {
+        let status = match &self.status {
+            Some(status) => Some(status.parse::<TaskStatus>().map_err(|e| {
+                invalid_param(
+                    e,
+                    "status",
+                    status,
+                    &["Queued", "InProgress", "Completed", "Error"],
+                )
+            })?),
+            None => None,
+        };
+        let task_type = match &self.task_type {
+            Some(task_type) => Some(task_types.canonical_name(task_type).map_err(|_| {
+                invalid_param(
+                    format!("Invalid task type: '{}'", task_type),
+                    "type",
+                    task_type,
+                    &task_types.names(),
+                )
+            })?),
+            None => None,
+        };
+
+        Ok(TaskQuery {
+            filter: TaskFilter {
+                status,
+                task_type,
+                tag: self.tag.clone().filter(|tag| !tag.trim().is_empty()),
+                started_after: self
+                    .created_after
+                    .as_deref()
+                    .map(|after| parse_archive_date("created_after", after, false))
+                    .transpose()?,
+                started_before: self
+                    .created_before
+                    .as_deref()
+                    .map(|before| parse_archive_date("created_before", before, false))
+                    .transpose()?,
+            },
+            name: self.name.clone().filter(|name| !name.trim().is_empty()),
+            ..Default::default()
+        })
+    }
+}
+
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+#[derive(Deserialize)]
+struct SearchRequest {
+    // Free text ranked against names, messages, results and errors
+    text: Option<String>,
+    // Query language filter the hits must also match
+    q: Option<String>,
+    limit: Option<usize>,
+}
+
+impl SearchRequest {
+    fn to_search(
+        &self,
+        task_types: &TaskTypeRegistry,
+    ) -> Result<(String, TaskQuery, usize), ApiError> {
+        let text = self
+            .text
+            .clone()
+            .filter(|text| !text.trim().is_empty())
+            .ok_or_else(|| {
+                ApiError::validation_error(
+                    "text is required".to_string(),
+                    Some(serde_json::json!({ "field": "text" })),
+                )
+            })?;
+        let filter = match self.q.as_deref().filter(|q| !q.trim().is_empty()) {
+            Some(q) => TaskQuery::parse(q, chrono::Utc::now(), task_types)?,
+            None => TaskQuery::default(),
+        };
+        let limit = self.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+        if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+            return Err(ApiError::validation_error(
+                format!("limit must be between 1 and {}", MAX_PAGE_SIZE),
+                Some(serde_json::json!({
+                    "field": "limit",
+                    "provided_value": limit,
+                    "max_value": MAX_PAGE_SIZE
+                })),
+            ));
+        }
+        Ok((text, filter, limit))
     }
 }
 
+// Accepts RFC 3339 timestamps or plain dates; a plain `to` date covers the whole day
+fn parse_archive_date(
+    field: &str,
+    value: &str,
+    end_of_day: bool,
+) -> Result<chrono::DateTime<chrono::Utc>, ApiError> {
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&chrono::Utc));
+    }
+
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let time = if end_of_day {
+            chrono::NaiveTime::from_hms_milli_opt(23, 59, 59, 999)
+        } else {
+            chrono::NaiveTime::from_hms_opt(0, 0, 0)
+        };
+        if let Some(time) = time {
+            return Ok(date.and_time(time).and_utc());
+        }
+    }
+
+    Err(ApiError::validation_error(
+        format!(
+            "Invalid date for '{}': expected RFC 3339 or YYYY-MM-DD",
+            field
+        ),
+        Some(serde_json::json!({
+            "field": field,
+            "provided_value": value
+        })),
+    ))
+}
+
+// `{"type": "<name>", ...params}`, resolved through the TaskTypeRegistry
 #[derive(Clone, Deserialize)]
-#[serde(tag = "type")]
-enum TaskTypeRequest {
-    #[serde(rename = "quick")]
-    Quick { timeout_ms: Option<u64> },
-    #[serde(rename = "long")]
-    Long { timeout_ms: Option<u64> },
-    #[serde(rename = "error")]
-    Error {
-        timeout_ms: Option<u64>,
-        error_type: Option<String>,
-    },
-    #[serde(rename = "custom")]
-    Custom {
-        custom_name: String,
-        timeout_ms: u64,
-        failure_rate: Option<f32>,
-    },
-}
-
-impl TaskTypeRequest {
-    fn to_task_type(self) -> TaskType {
-        match self {
-            TaskTypeRequest::Quick { timeout_ms } => TaskType::Quick { timeout_ms },
-            TaskTypeRequest::Long { timeout_ms } => TaskType::Long { timeout_ms },
-            TaskTypeRequest::Error {
-                timeout_ms,
-                error_type,
-            } => {
-                let error_type = match error_type.as_deref() {
-                    Some("immediate") => ErrorType::Immediate,
-                    Some("timeout") => ErrorType::Timeout,
-                    Some("random") => ErrorType::Random,
-                    Some("network") => ErrorType::NetworkError,
-                    Some("validation") => ErrorType::ValidationError,
-                    _ => ErrorType::Immediate,
-                };
-                TaskType::Error {
-                    timeout_ms,
-                    error_type,
-                }
-            }
-            TaskTypeRequest::Custom {
-                custom_name,
-                timeout_ms,
-                failure_rate,
-            } => TaskType::Custom {
-                name: custom_name,
-                timeout_ms,
-                failure_rate,
-            },
+struct TaskTypeRequest {
+    #[serde(rename = "type")]
+    name: String,
+    #[serde(flatten)]
+    params: TaskParams,
+}
+
+#[derive(Deserialize)]
+struct StartLoadRequest {
+    pattern: ArrivalPattern,
+    // Every template with weight 1 when empty
+    #[serde(default)]
+    mix: Vec<LoadMixRequest>,
+    #[serde(default)]
+    duration_secs: Option<u64>,
+    #[serde(default)]
+    max_tasks: Option<u64>,
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+// Either `{"template": "<name>"}` or `{"task_type": {"type": "<name>", ...}}`
+#[derive(Deserialize)]
+struct LoadMixRequest {
+    #[serde(default)]
+    template: Option<String>,
+    #[serde(default)]
+    task_type: Option<TaskTypeRequest>,
+    #[serde(default = "default_load_weight")]
+    weight: u32,
+}
+
+fn default_load_weight() -> u32 {
+    1
+}
+
+impl StartLoadRequest {
+    fn to_config(
+        &self,
+        templates: &[TaskTemplate],
+        registry: &TaskTypeRegistry,
+    ) -> Result<LoadGeneratorConfig, ApiError> {
+        let mix = if self.mix.is_empty() {
+            templates
+                .iter()
+                .map(|template| {
+                    Ok(LoadMixEntry {
+                        weight: 1,
+                        task: template.instantiate(registry, TemplateOverrides::default())?,
+                    })
+                })
+                .collect::<Result<Vec<_>, ApiError>>()?
+        } else {
+            self.mix
+                .iter()
+                .map(|entry| {
+                    Ok(LoadMixEntry {
+                        weight: entry.weight,
+                        task: entry.to_task(templates, registry)?,
+                    })
+                })
+                .collect::<Result<Vec<_>, ApiError>>()?
+        };
+
+        Ok(LoadGeneratorConfig {
+            pattern: self.pattern.clone(),
+            mix,
+            duration_secs: self.duration_secs,
+            max_tasks: self.max_tasks,
+            seed: self.seed,
+        })
+    }
+}
+
+impl LoadMixRequest {
+    fn to_task(
+        &self,
+        templates: &[TaskTemplate],
+        registry: &TaskTypeRegistry,
+    ) -> Result<CreateTask, ApiError> {
+        match (&self.template, &self.task_type) {
+            (Some(name), None) => templates
+                .iter()
+                .find(|template| &template.name == name)
+                .ok_or_else(|| ApiError::not_found("Template", name))?
+                .instantiate(registry, TemplateOverrides::default()),
+            (None, Some(task_type)) => Ok(CreateTask {
+                name: String::new(),
+                message: "Generated load".to_string(),
+                task_type: registry.resolve(&task_type.name, &task_type.params)?,
+                type_name: Some(task_type.name.clone()),
+                ..Default::default()
+            }),
+            _ => Err(ApiError::validation_error(
+                "Each mix entry needs exactly one of 'template' or 'task_type'".to_string(),
+                Some(serde_json::json!({ "field": "mix" })),
+            )),
         }
     }
 }
@@ -264,14 +616,53 @@ async fn index() -> impl Responder {
 #[post("/tasks")]
 async fn create_task(
     data: web::Data<AppState>,
+    http_req: HttpRequest,
+    req: web::Json<CreateTaskRequest>,
+) -> Result<impl Responder> {
+    create_task_in(&data, &req, None, traceparent(&http_req)).await
+}
+
+#[post("/ns/{ns}/tasks")]
+async fn create_namespace_task(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
     req: web::Json<CreateTaskRequest>,
 ) -> Result<impl Responder> {
+    let namespace = path.into_inner();
+    if let Err(error) = validate_namespace(&namespace) {
+        return Ok(api_error_response(error));
+    }
+    create_task_in(&data, &req, Some(namespace), traceparent(&http_req)).await
+}
+
+// W3C trace context the client sent along, the task's span continues it
+fn traceparent(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(TRACEPARENT)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+// Tasks created outside a /ns/{ns} scope go to the default namespace
+async fn create_task_in(
+    data: &AppState,
+    req: &CreateTaskRequest,
+    namespace: Option<String>,
+    traceparent: Option<String>,
+) -> Result<HttpResponse> {
     // Validate request
     if let Err(validation_error) = req.validate() {
         return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(validation_error)));
     }
 
-    let task_type = req.task_type.clone().to_task_type();
+    let task_type = match data
+        .task_types
+        .resolve(&req.task_type.name, &req.task_type.params)
+    {
+        Ok(task_type) => task_type,
+        Err(error) => return Ok(api_error_response(error)),
+    };
     let task_name = if req.name.is_empty() {
         task_type.get_name()
     } else {
@@ -281,14 +672,26 @@ async fn create_task(
     // Create task with proper error handling
     let task_id = match data
         .task_manager
-        .send(CreateTask {
-            name: task_name.clone(),
-            message: req.message.clone(),
-            task_type,
+        .send(SubmitTask {
+            task: CreateTask {
+                name: task_name.clone(),
+                message: req.message.clone(),
+                task_type,
+                type_name: Some(req.task_type.name.clone()),
+                seed: req.seed,
+                faults: req.faults.clone(),
+                executor: req.executor,
+                retry: req.retry.clone(),
+                affinity: req.affinity.clone(),
+                namespace,
+                traceparent,
+                ..Default::default()
+            },
         })
         .await
     {
-        Ok(id) => id,
+        Ok(Ok(id)) => id,
+        Ok(Err(error)) => return Ok(api_error_response(error)),
         Err(_) => {
             let error = ApiError::internal_error(
                 "Failed to create task - internal service error".to_string(),
@@ -300,13 +703,25 @@ async fn create_task(
     let response = TaskCreateResponse {
         id: task_id,
         name: task_name,
-        status: TaskStatus::InProgress,
+        status: created_status(data, task_id, req.executor).await,
         created_at: chrono::Utc::now(),
     };
 
     Ok(HttpResponse::Created().json(ApiResponse::success(response)))
 }
 
+// Status of a task just created. One held back by its namespace's
+// concurrency limit, or waiting for a remote worker, starts Queued.
+async fn created_status(data: &AppState, id: Uuid, executor: Executor) -> TaskStatus {
+    match data.task_manager.send(GetTask { id }).await {
+        Ok(Some(task)) => task.status,
+        _ => match executor {
+            Executor::Local => TaskStatus::InProgress,
+            Executor::Remote => TaskStatus::Queued,
+        },
+    }
+}
+
 #[post("/tasks/form")]
 async fn create_task_from_form(
     data: web::Data<AppState>,
@@ -317,7 +732,10 @@ async fn create_task_from_form(
         return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(validation_error)));
     }
 
-    let task_type = req.to_task_type();
+    let task_type = match data.task_types.resolve(&req.task_type, &req.task_params()) {
+        Ok(task_type) => task_type,
+        Err(error) => return Ok(api_error_response(error)),
+    };
     let task_name = if req.name.trim().is_empty() {
         task_type.get_name()
     } else {
@@ -327,14 +745,19 @@ async fn create_task_from_form(
     // Create task with comprehensive error handling
     let task_id = match data
         .task_manager
-        .send(CreateTask {
-            name: task_name.clone(),
-            message: req.message.clone(),
-            task_type,
+        .send(SubmitTask {
+            task: CreateTask {
+                name: task_name.clone(),
+                message: req.message.clone(),
+                task_type,
+                type_name: Some(req.task_type.clone()),
+                ..Default::default()
+            },
         })
         .await
     {
-        Ok(id) => id,
+        Ok(Ok(id)) => id,
+        Ok(Err(error)) => return Ok(api_error_response(error)),
         Err(_) => {
             let error = ApiError::internal_error(
                 "Failed to create task - internal service error".to_string(),
@@ -346,7 +769,7 @@ async fn create_task_from_form(
     let response = TaskCreateResponse {
         id: task_id,
         name: task_name,
-        status: TaskStatus::InProgress,
+        status: created_status(&data, task_id, Executor::Local).await,
         created_at: chrono::Utc::now(),
     };
 
@@ -354,30 +777,113 @@ async fn create_task_from_form(
 }
 
 #[get("/tasks")]
-async fn get_all_tasks(data: web::Data<AppState>) -> Result<impl Responder> {
-    let tasks = match data.task_manager.send(GetAllTasks).await {
-        Ok(tasks) => tasks,
-        Err(_) => {
-            let error = ApiError::internal_error(
-                "Failed to retrieve tasks - internal service error".to_string(),
-            );
-            return Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(error)));
+async fn get_all_tasks(
+    data: web::Data<AppState>,
+    query: web::Query<TaskListRequest>,
+) -> Result<impl Responder> {
+    list_tasks_in(&data, None, &query).await
+}
+
+#[get("/ns/{ns}/tasks")]
+async fn get_namespace_tasks(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<TaskListRequest>,
+) -> Result<impl Responder> {
+    list_tasks_in(&data, Some(path.into_inner()), &query).await
+}
+
+// Without a namespace only tasks of shared namespaces are listed
+async fn list_tasks_in(
+    data: &AppState,
+    namespace: Option<String>,
+    request: &TaskListRequest,
+) -> Result<HttpResponse> {
+    let query = match request.to_query(&data.task_types) {
+        Ok(query) => query,
+        Err(validation_error) => {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(validation_error)));
         }
     };
 
+    let page = data.tasks.query(&query, namespace.as_deref());
     let response = TaskListResponse {
-        total: tasks.len(),
-        tasks,
+        tasks: page.tasks,
+        total: page.total,
+        next_cursor: page.next_cursor,
     };
 
     Ok(HttpResponse::Ok().json(ApiResponse::success(response)))
 }
 
+#[get("/search")]
+async fn search_tasks(
+    data: web::Data<AppState>,
+    query: web::Query<SearchRequest>,
+) -> Result<impl Responder> {
+    search_tasks_in(&data, None, &query).await
+}
+
+#[get("/ns/{ns}/search")]
+async fn search_namespace_tasks(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<SearchRequest>,
+) -> Result<impl Responder> {
+    search_tasks_in(&data, Some(path.into_inner()), &query).await
+}
+
+async fn search_tasks_in(
+    data: &AppState,
+    namespace: Option<String>,
+    request: &SearchRequest,
+) -> Result<HttpResponse> {
+    let (text, filter, limit) = match request.to_search(&data.task_types) {
+        Ok(search) => search,
+        Err(validation_error) => {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(validation_error)));
+        }
+    };
+
+    let results = data
+        .tasks
+        .search(&text, &filter, namespace.as_deref(), limit);
+    Ok(HttpResponse::Ok().json(ApiResponse::success(results)))
+}
+
 #[get("/tasks/{id}")]
 async fn get_task(data: web::Data<AppState>, path: web::Path<Uuid>) -> Result<impl Responder> {
-    let task_id = path.into_inner();
+    get_task_in(&data, path.into_inner(), None).await
+}
+
+#[get("/ns/{ns}/tasks/{id}")]
+async fn get_namespace_task(
+    data: web::Data<AppState>,
+    path: web::Path<(String, Uuid)>,
+) -> Result<impl Responder> {
+    let (namespace, task_id) = path.into_inner();
+    get_task_in(&data, task_id, Some(namespace)).await
+}
 
-    let task = match data.task_manager.send(GetTask { id: task_id }).await {
+// Tasks outside the namespace, or in a private one when unscoped, are not found
+async fn get_task_in(
+    data: &AppState,
+    task_id: Uuid,
+    namespace: Option<String>,
+) -> Result<HttpResponse> {
+    if let Some(task) = data.tasks.namespace_task(&task_id, namespace.as_deref()) {
+        return Ok(HttpResponse::Ok().json(ApiResponse::success(task)));
+    }
+
+    // Created since the last snapshot, or not there at all
+    let task = match data
+        .task_manager
+        .send(GetNamespaceTask {
+            id: task_id,
+            namespace,
+        })
+        .await
+    {
         Ok(task) => task,
         Err(_) => {
             let error = ApiError::internal_error(
@@ -398,10 +904,32 @@ async fn get_task(data: web::Data<AppState>, path: web::Path<Uuid>) -> Result<im
 
 #[delete("/tasks/{id}")]
 async fn cancel_task(data: web::Data<AppState>, path: web::Path<Uuid>) -> Result<impl Responder> {
-    let task_id = path.into_inner();
+    cancel_task_in(&data, path.into_inner(), None).await
+}
+
+#[delete("/ns/{ns}/tasks/{id}")]
+async fn cancel_namespace_task(
+    data: web::Data<AppState>,
+    path: web::Path<(String, Uuid)>,
+) -> Result<impl Responder> {
+    let (namespace, task_id) = path.into_inner();
+    cancel_task_in(&data, task_id, Some(namespace)).await
+}
 
+async fn cancel_task_in(
+    data: &AppState,
+    task_id: Uuid,
+    namespace: Option<String>,
+) -> Result<HttpResponse> {
     // First check if task exists and get its current status
-    let task_status = match data.task_manager.send(GetTask { id: task_id }).await {
+    let task_status = match data
+        .task_manager
+        .send(GetNamespaceTask {
+            id: task_id,
+            namespace,
+        })
+        .await
+    {
         Ok(Some(task)) => task.status,
         Ok(None) => {
             let error = ApiError::not_found("Task", &task_id.to_string());
@@ -416,7 +944,7 @@ async fn cancel_task(data: web::Data<AppState>, path: web::Path<Uuid>) -> Result
     };
 
     // Check if task can be cancelled
-    if task_status != TaskStatus::InProgress {
+    if !task_status.is_active() {
         let error = ApiError::task_already_completed(&task_id.to_string());
         return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(error)));
     }
@@ -441,6 +969,444 @@ async fn cancel_task(data: web::Data<AppState>, path: web::Path<Uuid>) -> Result
     }
 }
 
+#[get("/ns")]
+async fn list_namespaces(data: web::Data<AppState>) -> Result<impl Responder> {
+    match data.task_manager.send(ListNamespaces).await {
+        Ok(namespaces) => Ok(HttpResponse::Ok().json(ApiResponse::success(namespaces))),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to list namespaces - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[get("/ns/{ns}")]
+async fn get_namespace(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let name = path.into_inner();
+    match data
+        .task_manager
+        .send(GetNamespace { name: name.clone() })
+        .await
+    {
+        Ok(Some(namespace)) => Ok(HttpResponse::Ok().json(ApiResponse::success(namespace))),
+        Ok(None) => Ok(api_error_response(ApiError::not_found("Namespace", &name))),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to retrieve namespace - internal service error".to_string(),
+        ))),
+    }
+}
+
+// Creates the namespace or replaces its concurrency limit, retention and visibility
+#[put("/ns/{ns}")]
+async fn configure_namespace(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    req: web::Json<NamespaceConfig>,
+) -> Result<impl Responder> {
+    match data
+        .task_manager
+        .send(ConfigureNamespace {
+            name: path.into_inner(),
+            config: req.into_inner(),
+        })
+        .await
+    {
+        Ok(Ok(namespace)) => Ok(HttpResponse::Ok().json(ApiResponse::success(namespace))),
+        Ok(Err(error)) => Ok(api_error_response(error)),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to configure namespace - internal service error".to_string(),
+        ))),
+    }
+}
+
+// How the remote worker pool is shared between namespaces
+#[get("/scheduler")]
+async fn get_scheduler(data: web::Data<AppState>) -> Result<impl Responder> {
+    match data.task_manager.send(GetSchedulerReport).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(ApiResponse::success(report))),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to retrieve scheduler report - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[get("/stats")]
+async fn get_task_stats(data: web::Data<AppState>) -> Result<impl Responder> {
+    task_stats_in(&data, None).await
+}
+
+#[get("/ns/{ns}/stats")]
+async fn get_namespace_stats(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    task_stats_in(&data, Some(path.into_inner())).await
+}
+
+// Read from the snapshots, so it never waits behind task submissions
+async fn task_stats_in(data: &AppState, namespace: Option<String>) -> Result<HttpResponse> {
+    let stats = data.tasks.stats(namespace.as_deref(), chrono::Utc::now());
+    Ok(HttpResponse::Ok().json(ApiResponse::success(stats)))
+}
+
+#[get("/archive")]
+async fn query_archive(
+    data: web::Data<AppState>,
+    query: web::Query<ArchiveQueryRequest>,
+) -> Result<impl Responder> {
+    query_archive_in(&data, None, &query).await
+}
+
+#[get("/ns/{ns}/archive")]
+async fn query_namespace_archive(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<ArchiveQueryRequest>,
+) -> Result<impl Responder> {
+    query_archive_in(&data, Some(path.into_inner()), &query).await
+}
+
+// Without a namespace only tasks of shared namespaces are returned
+async fn query_archive_in(
+    data: &AppState,
+    namespace: Option<String>,
+    request: &ArchiveQueryRequest,
+) -> Result<HttpResponse> {
+    let archive_query = match request.to_query() {
+        Ok(archive_query) => ArchiveQuery {
+            namespace,
+            private: data.tasks.private_namespaces(),
+            ..archive_query
+        },
+        Err(validation_error) => {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(validation_error)));
+        }
+    };
+
+    // Archive reads decompress files from disk, keep them off the async workers
+    let archive = data.archive.clone();
+    let tasks = match web::block(move || archive.query(&archive_query)).await {
+        Ok(Ok(tasks)) => tasks,
+        _ => {
+            let error = ApiError::internal_error(
+                "Failed to query task archive - internal service error".to_string(),
+            );
+            return Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(error)));
+        }
+    };
+
+    let response = TaskListResponse {
+        total: tasks.len(),
+        tasks,
+        next_cursor: None,
+    };
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(response)))
+}
+
+#[get("/task-types")]
+async fn list_task_types(data: web::Data<AppState>) -> Result<impl Responder> {
+    Ok(HttpResponse::Ok().json(ApiResponse::success(data.task_types.describe())))
+}
+
+#[get("/workers")]
+async fn list_workers(data: web::Data<AppState>) -> Result<impl Responder> {
+    match data.task_manager.send(GetWorkers).await {
+        Ok(workers) => Ok(HttpResponse::Ok().json(ApiResponse::success(workers))),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to retrieve workers - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[get("/templates")]
+async fn list_templates(data: web::Data<AppState>) -> Result<impl Responder> {
+    match data.templates.send(ListTemplates).await {
+        Ok(templates) => Ok(HttpResponse::Ok().json(ApiResponse::success(templates))),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to retrieve templates - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[get("/templates/{name}")]
+async fn get_template(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let name = path.into_inner();
+
+    match data
+        .templates
+        .send(GetTemplate { name: name.clone() })
+        .await
+    {
+        Ok(Some(template)) => Ok(HttpResponse::Ok().json(ApiResponse::success(template))),
+        Ok(None) => Ok(api_error_response(ApiError::not_found("Template", &name))),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to retrieve template - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[post("/templates")]
+async fn create_template(
+    data: web::Data<AppState>,
+    req: web::Json<TaskTemplate>,
+) -> Result<impl Responder> {
+    match data
+        .templates
+        .send(CreateTemplate {
+            template: req.into_inner(),
+        })
+        .await
+    {
+        Ok(Ok(template)) => Ok(HttpResponse::Created().json(ApiResponse::success(template))),
+        Ok(Err(error)) => Ok(api_error_response(error)),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to create template - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[put("/templates/{name}")]
+async fn update_template(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    req: web::Json<TaskTemplate>,
+) -> Result<impl Responder> {
+    match data
+        .templates
+        .send(UpdateTemplate {
+            name: path.into_inner(),
+            template: req.into_inner(),
+        })
+        .await
+    {
+        Ok(Ok(template)) => Ok(HttpResponse::Ok().json(ApiResponse::success(template))),
+        Ok(Err(error)) => Ok(api_error_response(error)),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to update template - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[delete("/templates/{name}")]
+async fn delete_template(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    match data
+        .templates
+        .send(DeleteTemplate {
+            name: path.into_inner(),
+        })
+        .await
+    {
+        Ok(Ok(template)) => Ok(HttpResponse::Ok().json(ApiResponse::success(template))),
+        Ok(Err(error)) => Ok(api_error_response(error)),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to delete template - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[post("/templates/{name}/tasks")]
+async fn create_task_from_template(
+    data: web::Data<AppState>,
+    http_req: HttpRequest,
+    path: web::Path<String>,
+    req: Option<web::Json<TemplateOverrides>>,
+) -> Result<impl Responder> {
+    let overrides = req.map(|req| req.into_inner()).unwrap_or_default();
+
+    let mut task_request = match data
+        .templates
+        .send(InstantiateTemplate {
+            name: path.into_inner(),
+            overrides,
+        })
+        .await
+    {
+        Ok(Ok(task_request)) => task_request,
+        Ok(Err(error)) => return Ok(api_error_response(error)),
+        Err(_) => {
+            return Ok(api_error_response(ApiError::internal_error(
+                "Failed to instantiate template - internal service error".to_string(),
+            )));
+        }
+    };
+    task_request.traceparent = traceparent(&http_req);
+
+    let task_name = if task_request.name.is_empty() {
+        task_request.task_type.get_name()
+    } else {
+        task_request.name.clone()
+    };
+
+    let executor = task_request.executor;
+    let task_id = match data
+        .task_manager
+        .send(SubmitTask { task: task_request })
+        .await
+    {
+        Ok(Ok(id)) => id,
+        Ok(Err(error)) => return Ok(api_error_response(error)),
+        Err(_) => {
+            return Ok(api_error_response(ApiError::internal_error(
+                "Failed to create task - internal service error".to_string(),
+            )));
+        }
+    };
+
+    let response = TaskCreateResponse {
+        id: task_id,
+        name: task_name,
+        status: created_status(&data, task_id, executor).await,
+        created_at: chrono::Utc::now(),
+    };
+
+    Ok(HttpResponse::Created().json(ApiResponse::success(response)))
+}
+
+#[get("/admin/chaos")]
+async fn get_chaos_config(data: web::Data<AppState>) -> Result<impl Responder> {
+    match data.task_manager.send(GetChaosConfig).await {
+        Ok(config) => Ok(HttpResponse::Ok().json(ApiResponse::success(config))),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to retrieve chaos config - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[put("/admin/chaos")]
+async fn set_global_fault_profile(
+    data: web::Data<AppState>,
+    req: web::Json<FaultProfile>,
+) -> Result<impl Responder> {
+    update_fault_profile(&data, None, Some(req.into_inner())).await
+}
+
+#[delete("/admin/chaos")]
+async fn clear_global_fault_profile(data: web::Data<AppState>) -> Result<impl Responder> {
+    update_fault_profile(&data, None, None).await
+}
+
+#[put("/admin/chaos/{task_type}")]
+async fn set_type_fault_profile(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    req: web::Json<FaultProfile>,
+) -> Result<impl Responder> {
+    update_fault_profile(&data, Some(path.into_inner()), Some(req.into_inner())).await
+}
+
+#[delete("/admin/chaos/{task_type}")]
+async fn clear_type_fault_profile(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    update_fault_profile(&data, Some(path.into_inner()), None).await
+}
+
+// Shared by the chaos admin endpoints, responds with the updated config
+async fn update_fault_profile(
+    data: &AppState,
+    task_type: Option<String>,
+    profile: Option<FaultProfile>,
+) -> Result<HttpResponse> {
+    let task_type = match task_type.map(|name| data.task_types.canonical_name(&name)) {
+        Some(Ok(name)) => Some(name),
+        Some(Err(error)) => return Ok(api_error_response(error)),
+        None => None,
+    };
+    if let Some(profile) = &profile {
+        if let Err(error) = profile.validate() {
+            return Ok(api_error_response(error));
+        }
+    }
+
+    let updated = data
+        .task_manager
+        .send(SetFaultProfile {
+            task_type: task_type.clone(),
+            profile,
+        })
+        .await;
+    let config = match updated {
+        Ok(()) => data.task_manager.send(GetChaosConfig).await,
+        Err(e) => Err(e),
+    };
+
+    match config {
+        Ok(config) => {
+            info!(
+                "task.type" = task_type.as_deref().unwrap_or("all"),
+                "fault profile updated"
+            );
+            Ok(HttpResponse::Ok().json(ApiResponse::success(config)))
+        }
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to update chaos config - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[get("/load")]
+async fn get_load_stats(data: web::Data<AppState>) -> Result<impl Responder> {
+    match data.load_generator.send(GetLoadStats).await {
+        Ok(stats) => Ok(HttpResponse::Ok().json(ApiResponse::success(stats))),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to retrieve load stats - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[post("/load/start")]
+async fn start_load(
+    data: web::Data<AppState>,
+    req: web::Json<StartLoadRequest>,
+) -> Result<impl Responder> {
+    let templates = match data.templates.send(ListTemplates).await {
+        Ok(templates) => templates,
+        Err(_) => {
+            return Ok(api_error_response(ApiError::internal_error(
+                "Failed to retrieve templates - internal service error".to_string(),
+            )));
+        }
+    };
+    let config = match req.to_config(&templates, &data.task_types) {
+        Ok(config) => config,
+        Err(error) => return Ok(api_error_response(error)),
+    };
+
+    match data.load_generator.send(StartLoad { config }).await {
+        Ok(Ok(stats)) => {
+            info!("load generator started");
+            Ok(HttpResponse::Ok().json(ApiResponse::success(stats)))
+        }
+        Ok(Err(error)) => Ok(api_error_response(error)),
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to start load generator - internal service error".to_string(),
+        ))),
+    }
+}
+
+#[post("/load/stop")]
+async fn stop_load(data: web::Data<AppState>) -> Result<impl Responder> {
+    match data.load_generator.send(StopLoad).await {
+        Ok(stats) => {
+            info!("load generator stopped");
+            Ok(HttpResponse::Ok().json(ApiResponse::success(stats)))
+        }
+        Err(_) => Ok(api_error_response(ApiError::internal_error(
+            "Failed to stop load generator - internal service error".to_string(),
+        ))),
+    }
+}
+
 #[get("/health")]
 async fn health_check() -> impl Responder {
     HttpResponse::Ok().json(serde_json::json!({
@@ -504,15 +1470,12 @@ async fn clear_websocket_messages(data: web::Data<AppState>) -> Result<impl Resp
 #[get("/tasks/stream")]
 async fn task_stream(data: web::Data<AppState>) -> Result<impl Responder> {
     // For now, return a simple response to test basic functionality
-    let tasks = data
-        .task_manager
-        .send(GetAllTasks)
-        .await
-        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to get tasks"))?;
+    let tasks = data.tasks.all_tasks();
 
     let response = TaskListResponse {
         total: tasks.len(),
         tasks,
+        next_cursor: None,
     };
 
     let json = serde_json::json!({
@@ -529,13 +1492,29 @@ async fn task_stream(data: web::Data<AppState>) -> Result<impl Responder> {
         .body(format!("data: {}\n\n", json)))
 }
 
+// Prometheus scrape target, outside /api like the other non-JSON routes
+async fn metrics_page(data: web::Data<AppState>) -> impl Responder {
+    let mailbox_latency = data
+        .task_manager
+        .send(MeasureMailboxLatency)
+        .await
+        .unwrap_or_default();
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(data.metrics.render(&mailbox_latency))
+}
+
 async fn liveview_page() -> impl Responder {
     let file_path = "web/static/redirect.html";
     match std::fs::read_to_string(file_path) {
         Ok(content) => HttpResponse::Ok().content_type("text/html").body(content),
         Err(e) => {
-            eprintln!("Error reading file '{}': {}", file_path, e);
-            eprintln!("Current working directory: {:?}", std::env::current_dir());
+            error!(
+                file = file_path,
+                cwd = ?std::env::current_dir(),
+                error = %e,
+                "failed to read the LiveView redirect page"
+            );
             HttpResponse::InternalServerError().body(format!(
                 "Error loading LiveView redirect page: {} (cwd: {:?})",
                 e,
@@ -546,7 +1525,7 @@ async fn liveview_page() -> impl Responder {
 }
 
 async fn websocket_monitor_page() -> impl Responder {
-    use maud::{html, Markup, PreEscaped, DOCTYPE};
+    use maud::{html, PreEscaped, DOCTYPE};
 
     let page = html! {
         (DOCTYPE)
@@ -807,20 +1786,49 @@ async fn websocket_monitor_page() -> impl Responder {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    env_logger::init();
+    let exporter = telemetry::init_tracing();
 
-    println!("🚀 Starting Task Overlord Dashboard Server");
+    info!("starting Task Overlord dashboard server");
 
     // Start the actors
-    let task_manager = TaskManagerActor::new().start();
-    let ws_monitor = WebSocketMonitorActor::new().start();
+    let archive = TaskArchive::new(ARCHIVE_DIR);
+    let shard_archive = archive.clone();
+    let metrics = Metrics::new();
+    let shard_metrics = metrics.clone();
+    let task_types = Arc::new(TaskTypeRegistry::with_builtin_types());
+    let shard_task_types = task_types.clone();
+    let shards = task_manager_shards();
+    let execution_mode = task_execution_mode();
+    info!(shards, execution_mode = ?execution_mode, "starting task managers");
+    let router = TaskManagerRouter::start_shards(shards, move |_| {
+        TaskManagerActor::new()
+            .with_task_types(shard_task_types.as_ref().clone())
+            .with_archive(shard_archive.clone(), TASK_RETENTION)
+            .with_execution_mode(execution_mode)
+            .with_metrics(shard_metrics.clone())
+    });
+    let tasks = router.reader();
+    let task_manager = router.start();
+    let ws_monitor = WebSocketMonitorActor::new()
+        .with_metrics(metrics.clone())
+        .start();
+    let templates = TaskTemplateActor::new()
+        .with_registry(task_types.as_ref().clone())
+        .start();
+    let load_generator = LoadGeneratorActor::new(task_manager.clone().recipient()).start();
 
     let app_state = web::Data::new(AppState {
         task_manager,
+        tasks,
         ws_monitor,
+        archive,
+        templates,
+        task_types,
+        load_generator,
+        metrics,
     });
 
-    println!("📡 Server starting on http://127.0.0.1:3333");
+    info!(address = "http://127.0.0.1:3333", "server starting");
 
     HttpServer::new(move || {
         App::new()
@@ -834,17 +1842,57 @@ async fn main() -> std::io::Result<()> {
                     .service(get_all_tasks)
                     .service(get_task)
                     .service(cancel_task)
+                    .service(list_namespaces)
+                    .service(get_namespace)
+                    .service(configure_namespace)
+                    .service(create_namespace_task)
+                    .service(get_namespace_tasks)
+                    .service(get_namespace_task)
+                    .service(search_tasks)
+                    .service(search_namespace_tasks)
+                    .service(cancel_namespace_task)
+                    .service(get_scheduler)
+                    .service(get_task_stats)
+                    .service(get_namespace_stats)
+                    .service(query_archive)
+                    .service(query_namespace_archive)
+                    .service(list_task_types)
+                    .service(list_workers)
+                    .service(list_templates)
+                    .service(get_template)
+                    .service(create_template)
+                    .service(update_template)
+                    .service(delete_template)
+                    .service(create_task_from_template)
+                    .service(get_chaos_config)
+                    .service(set_global_fault_profile)
+                    .service(clear_global_fault_profile)
+                    .service(set_type_fault_profile)
+                    .service(clear_type_fault_profile)
+                    .service(get_load_stats)
+                    .service(start_load)
+                    .service(stop_load)
                     .service(health_check)
                     .service(get_websocket_messages)
                     .service(clear_websocket_messages), // .service(task_stream) // Temporarily disabled
             )
             .route("/", web::get().to(liveview_page))
             .route("/ws/", web::get().to(liveview::websocket_handler))
+            .route("/ws/worker", web::get().to(worker::websocket_handler))
             .route("/liveview", web::get().to(liveview_page))
             .route("/monitor", web::get().to(websocket_monitor_page))
+            .route("/metrics", web::get().to(metrics_page))
             .service(fs::Files::new("/static/", "web/static"))
     })
     .bind("127.0.0.1:3333")?
     .run()
-    .await
+    .await?;
+
+    // Send the spans still waiting for a batch
+    if let Some(exporter) = exporter {
+        if let Err(e) = exporter.shutdown() {
+            error!(error = %e, "failed to export the last spans");
+        }
+    }
+    Ok(())
 }
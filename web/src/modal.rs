@@ -1,5 +1,5 @@
 use maud::{html, Markup, PreEscaped};
-use task_core::TaskType;
+use task_core::{TaskTemplate, TaskTypeRegistry};
 
 #[derive(Debug, Clone)]
 pub struct TaskTypeConfig {
@@ -12,47 +12,52 @@ pub struct TaskTypeConfig {
 }
 
 impl TaskTypeConfig {
-    pub fn get_config(task_type: &str) -> Self {
-        match task_type {
-            "quick" => Self {
-                name: "Quick Task".to_string(),
-                badge_class: "badge-quick".to_string(),
-                placeholder: "Quick task message (completes in ~2s)".to_string(),
-                description: "A fast task that completes in approximately 2 seconds".to_string(),
-                default_timeout: 2000,
-                has_custom_options: false,
-            },
-            "long" => Self {
-                name: "Long Task".to_string(),
-                badge_class: "badge-long".to_string(),
-                placeholder: "Long task message (completes in ~10s)".to_string(),
-                description: "A longer task that takes approximately 10 seconds to complete"
-                    .to_string(),
-                default_timeout: 10000,
-                has_custom_options: false,
-            },
-            "error" => Self {
-                name: "Error Task".to_string(),
-                badge_class: "badge-error".to_string(),
-                placeholder: "Error task message (will fail for testing)".to_string(),
-                description: "A task designed to fail for testing error handling".to_string(),
-                default_timeout: 5000,
-                has_custom_options: false,
-            },
-            "custom" => Self {
-                name: "Custom Task".to_string(),
-                badge_class: "badge-custom".to_string(),
-                placeholder: "Custom task message".to_string(),
-                description: "A fully customizable task with configurable timeout and failure rate"
-                    .to_string(),
-                default_timeout: 5000,
-                has_custom_options: true,
-            },
-            _ => Self::get_config("quick"),
+    pub fn from_template(template: &TaskTemplate, task_types: &TaskTypeRegistry) -> Self {
+        Self {
+            name: template.display_name.clone(),
+            badge_class: format!("badge-{}", template.task_type),
+            placeholder: template.default_message.clone(),
+            description: template.description.clone(),
+            default_timeout: template.timeout_ms,
+            // Types whose schema takes a failure rate get the advanced options
+            has_custom_options: task_types
+                .get(&template.task_type)
+                .is_some_and(|task_type| task_type.has_param("failure_rate")),
         }
     }
 }
 
+// Template configs embedded in the page so app.js can restyle the modal
+// without hard-coding the task types
+pub fn render_template_data(templates: &[TaskTemplate], task_types: &TaskTypeRegistry) -> Markup {
+    let configs: Vec<serde_json::Value> = templates
+        .iter()
+        .map(|template| {
+            let config = TaskTypeConfig::from_template(template, task_types);
+            serde_json::json!({
+                "template": template.name,
+                "task_type": template.task_type,
+                "name": config.name,
+                "badge": config.badge_class,
+                "placeholder": config.placeholder,
+                "description": config.description,
+                "default_timeout": config.default_timeout,
+                "default_failure_rate": template.failure_rate.unwrap_or(0.0),
+                "has_custom_options": config.has_custom_options
+            })
+        })
+        .collect();
+
+    // Escape '<' so template text can't close the script element
+    let json = serde_json::Value::Array(configs)
+        .to_string()
+        .replace('<', "\\u003c");
+
+    html! {
+        script type="application/json" id="task-templates" { (PreEscaped(json)) }
+    }
+}
+
 pub fn render_modal_styles() -> Markup {
     html! {
         style {
@@ -194,18 +199,18 @@ pub fn render_modal_styles() -> Markup {
     }
 }
 
-pub fn render_task_modal(task_type: &str) -> Markup {
-    let config = TaskTypeConfig::get_config(task_type);
+pub fn render_task_modal(template: &TaskTemplate, task_types: &TaskTypeRegistry) -> Markup {
+    let config = TaskTypeConfig::from_template(template, task_types);
 
     html! {
-        div class="modal" id="task-modal" onclick="closeModalOnBackdrop(event)" data-task-type=(task_type) {
+        div class="modal" id="task-modal" onclick="closeModalOnBackdrop(event)" data-template=(template.name) data-task-type=(template.task_type) {
             div class="modal-content" {
                 div class="modal-header" {
                     h2 { "Create Task" }
                     div class={"task-type-badge " (config.badge_class)} id="modal-task-type-badge" { (config.name) }
                     div class="task-description" id="modal-task-description" { (config.description) }
                 }
-                form id="task-form" onsubmit="createCustomTask(event)" data-task-type=(task_type) {
+                form id="task-form" onsubmit="createCustomTask(event)" data-template=(template.name) data-task-type=(template.task_type) {
                     div class="form-group" {
                         label for="task-name" { "Task Name (optional):" }
                         input type="text" id="task-name" placeholder="Leave empty for auto-generated name" maxlength="100";
@@ -225,7 +230,7 @@ pub fn render_task_modal(task_type: &str) -> Markup {
                         }
                         div class="form-group" {
                             label for="custom-failure-rate" { "Failure Rate:" }
-                            input type="number" id="custom-failure-rate" value="0" min="0" max="1" step="0.1";
+                            input type="number" id="custom-failure-rate" value=(template.failure_rate.unwrap_or(0.0)) min="0" max="1" step="0.1";
                             small style="color: #718096;" { "0.0 = never fails, 1.0 = always fails" }
                         }
                     }
@@ -0,0 +1,81 @@
+use task_core::{OtlpConfig, OtlpExporter};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+// Log output is configured through the environment:
+//
+//   RUST_LOG=debug                    level, or per-module filters such
+//   RUST_LOG=info,task_core=debug     as these, info when unset
+//   LOG_FORMAT=json                   one JSON object per line for a log
+//                                     pipeline, pretty when unset
+//   OTEL_EXPORTER_OTLP_ENDPOINT=http://localhost:4318
+//                                     also export spans to this OTLP/HTTP
+//                                     collector, see task_core::otel for
+//                                     the other OTEL_* variables
+//
+// Task spans are logged when they close, with how long the task was busy
+// and idle, see task_core::spans.
+const DEFAULT_FILTER: &str = "info";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LogFormat {
+    Pretty,
+    Json,
+}
+
+// The exporter, when there is one, should be shut down before exiting
+pub fn init_tracing() -> Option<OtlpExporter> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let requested = std::env::var("LOG_FORMAT").unwrap_or_default();
+    let format = match requested.to_lowercase().as_str() {
+        "json" => Some(LogFormat::Json),
+        "" | "pretty" => Some(LogFormat::Pretty),
+        _ => None,
+    };
+
+    let json = (format == Some(LogFormat::Json)).then(|| {
+        tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_span_events(FmtSpan::CLOSE)
+    });
+    let pretty = (format != Some(LogFormat::Json)).then(|| {
+        tracing_subscriber::fmt::layer()
+            .pretty()
+            .with_span_events(FmtSpan::CLOSE)
+    });
+    let otlp = OtlpConfig::from_env().map(|config| (OtlpExporter::start(&config), config));
+    let exporter = match &otlp {
+        Some((Ok(exporter), _)) => Some(exporter.clone()),
+        _ => None,
+    };
+    // Also picks up the `log` records of actix-web's Logger middleware
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(json)
+        .with(pretty)
+        .with(exporter.as_ref().map(OtlpExporter::layer))
+        .init();
+
+    if format.is_none() {
+        tracing::warn!(
+            log_format = %requested,
+            "unknown LOG_FORMAT, expected json or pretty, using pretty"
+        );
+    }
+    match otlp {
+        Some((Ok(_), config)) => tracing::info!(
+            endpoint = %config.endpoint,
+            protocol = ?config.protocol,
+            service = %config.service_name,
+            "exporting spans over OTLP"
+        ),
+        Some((Err(e), _)) => tracing::error!(error = %e, "not exporting spans"),
+        None => {}
+    }
+    exporter
+}
@@ -0,0 +1,164 @@
+use actix::{Actor, ActorContext, Addr, AsyncContext, Handler, StreamHandler};
+use actix_web::web;
+use actix_web_actors::ws;
+use std::time::{Duration, Instant};
+use task_core::*;
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// One remote worker connection. Parses the JSON protocol, forwards it to
+// the TaskManagerActor and writes the manager's commands back as JSON.
+pub struct WorkerSession {
+    hb: Instant,
+    worker_id: Option<Uuid>,
+    task_manager: Addr<TaskManagerRouter>,
+}
+
+impl WorkerSession {
+    pub fn new(task_manager: Addr<TaskManagerRouter>) -> Self {
+        Self {
+            hb: Instant::now(),
+            worker_id: None,
+            task_manager,
+        }
+    }
+
+    fn hb(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
+            if Instant::now().duration_since(act.hb) > CLIENT_TIMEOUT {
+                warn!(worker.id = ?act.worker_id, "worker heartbeat failed, disconnecting");
+                ctx.stop();
+                return;
+            }
+
+            ctx.ping(b"");
+        });
+    }
+
+    fn send_error(ctx: &mut ws::WebsocketContext<Self>, message: String) {
+        if let Ok(text) = serde_json::to_string(&WorkerCommand::Error { message }) {
+            ctx.text(text);
+        }
+    }
+
+    fn handle_request(&mut self, request: WorkerRequest, ctx: &mut ws::WebsocketContext<Self>) {
+        let addr = ctx.address();
+        let task_manager = self.task_manager.clone();
+
+        match (self.worker_id, request) {
+            (
+                None,
+                WorkerRequest::Register {
+                    name,
+                    capabilities,
+                    max_concurrent,
+                    labels,
+                },
+            ) => {
+                // The manager answers with Registered on success
+                actix::spawn(async move {
+                    let registered = task_manager
+                        .send(RegisterWorker {
+                            name,
+                            capabilities,
+                            labels,
+                            max_concurrent,
+                            recipient: addr.clone().recipient(),
+                        })
+                        .await;
+                    let message = match registered {
+                        Ok(Ok(_)) => return,
+                        Ok(Err(error)) => error.message,
+                        Err(e) => format!("Failed to register worker: {}", e),
+                    };
+                    addr.do_send(WorkerCommand::Error { message });
+                });
+            }
+            (None, _) => {
+                Self::send_error(ctx, "Register before sending other messages".to_string());
+            }
+            (Some(worker_id), request) => {
+                actix::spawn(async move {
+                    let message = match task_manager.send(WorkerReport { worker_id, request }).await
+                    {
+                        Ok(Ok(())) => return,
+                        Ok(Err(error)) => error.message,
+                        Err(e) => format!("Failed to process worker message: {}", e),
+                    };
+                    addr.do_send(WorkerCommand::Error { message });
+                });
+            }
+        }
+    }
+}
+
+impl Actor for WorkerSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        debug!("worker connection opened");
+        self.hb(ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        debug!(worker.id = ?self.worker_id, "worker connection closed");
+        if let Some(worker_id) = self.worker_id.take() {
+            self.task_manager.do_send(WorkerDisconnected { worker_id });
+        }
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WorkerSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => {
+                self.hb = Instant::now();
+                ctx.pong(&msg);
+            }
+            Ok(ws::Message::Pong(_)) => {
+                self.hb = Instant::now();
+            }
+            Ok(ws::Message::Text(text)) => {
+                self.hb = Instant::now();
+                match serde_json::from_str::<WorkerRequest>(&text) {
+                    Ok(request) => self.handle_request(request, ctx),
+                    Err(e) => Self::send_error(ctx, format!("Invalid worker message: {}", e)),
+                }
+            }
+            Ok(ws::Message::Binary(_)) => {
+                Self::send_error(ctx, "Worker messages must be JSON text frames".to_string())
+            }
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            _ => ctx.stop(),
+        }
+    }
+}
+
+impl Handler<WorkerCommand> for WorkerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: WorkerCommand, ctx: &mut Self::Context) {
+        if let WorkerCommand::Registered { worker_id } = &msg {
+            self.worker_id = Some(*worker_id);
+        }
+
+        match serde_json::to_string(&msg) {
+            Ok(text) => ctx.text(text),
+            Err(e) => error!(error = %e, "failed to serialize worker command"),
+        }
+    }
+}
+
+pub async fn websocket_handler(
+    req: actix_web::HttpRequest,
+    stream: web::Payload,
+    data: web::Data<crate::AppState>,
+) -> Result<actix_web::HttpResponse, actix_web::Error> {
+    ws::start(WorkerSession::new(data.task_manager.clone()), &req, stream)
+}
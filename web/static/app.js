@@ -15,9 +15,16 @@
  * Message Types:
  * - full_page_load: Complete HTML document replacement (initial load only)
  * - task_grid_update: Partial update of task grid content (DOM diffing)
+ * - load_stats_update: Replaces the load generator throughput readout
+ * - search_update: Replaces the search box's match count or error
+ * - stats_update: Replaces the header's task statistics table
  * - create_task: Create a new task (outbound)
+ * - create_custom_task: Create a task from a template with per-call overrides (outbound)
  * - cancel_task: Cancel an existing task (outbound)
  * - refresh: Request current task state (outbound)
+ * - start_load / stop_load: Control the server-side load generator (outbound)
+ * - switch_namespace: Scope the task grid and new tasks to a namespace (outbound)
+ * - search: Narrow the task grid with a query language search (outbound)
  *
  * DOM Diffing Strategy:
  * - Compares new HTML with existing DOM elements
@@ -78,6 +85,26 @@ function createSpan(name, attributes = {}) {
     return null;
 }
 
+// W3C trace context of a span, sent along when creating a task so the
+// server's span for the task joins the same trace
+function traceparent(span) {
+    const context = span?.spanContext?.();
+    if (!context?.traceId || !context?.spanId) {
+        return null;
+    }
+    const flags = (context.traceFlags ?? 0).toString(16).padStart(2, '0');
+    return `00-${context.traceId}-${context.spanId}-${flags}`;
+}
+
+// Adds the span's traceparent to a LiveView message or request headers
+function withTraceparent(fields, span) {
+    const value = traceparent(span);
+    if (value) {
+        fields.traceparent = value;
+    }
+    return fields;
+}
+
 // Log telemetry event (fallback when OpenTelemetry not available)
 function logTelemetryEvent(eventType, data) {
     const timestamp = new Date().toISOString();
@@ -127,7 +154,8 @@ function connect() {
     isConnecting = true;
 
     const protocol = location.protocol === 'https:' ? 'wss:' : 'ws:';
-    const wsUrl = protocol + '//' + location.host + '/ws/';
+    // ?ns= is passed along so a reconnect stays in the same namespace
+    const wsUrl = protocol + '//' + location.host + '/ws/' + location.search;
 
     const connectSpan = createSpan('websocket.connect', {
         'websocket.url': wsUrl,
@@ -205,6 +233,26 @@ function connect() {
 
                 // Partial update - use DOM diffing to update only changed content
                 updateTaskGrid(data.html);
+
+            } else if (data.type === 'load_stats_update') {
+                // Only the readout is replaced so the pattern and rate inputs keep their values
+                const loadStats = document.getElementById('load-stats');
+                if (loadStats) {
+                    loadStats.outerHTML = data.html;
+                }
+
+            } else if (data.type === 'search_update') {
+                // The search input keeps what was typed, even when it did not parse
+                const searchStatus = document.getElementById('search-status');
+                if (searchStatus) {
+                    searchStatus.outerHTML = data.html;
+                }
+
+            } else if (data.type === 'stats_update') {
+                const taskStats = document.getElementById('task-stats');
+                if (taskStats) {
+                    taskStats.outerHTML = data.html;
+                }
             }
 
         } catch (error) {
@@ -430,7 +478,7 @@ function updateTaskGridFromRestData(tasks) {
 
     // Group tasks by status
     const tasksByStatus = {
-        'InProgress': tasks.filter(t => t.status === 'InProgress'),
+        'InProgress': tasks.filter(t => t.status === 'InProgress' || t.status === 'Queued'),
         'Completed': tasks.filter(t => t.status === 'Completed'),
         'Error': tasks.filter(t => t.status === 'Error')
     };
@@ -458,7 +506,11 @@ function updateTaskGridFromRestData(tasks) {
                         <div class="task-name">${task.name}</div>
                         <div class="task-message">${task.message}</div>
                         <div class="task-meta">
-                            <div>Started: ${new Date(task.started_at).toLocaleTimeString()}</div>
+                            ${task.status === 'Queued'
+                                ? '<div style="color: #805ad5;">Queued for a remote worker</div>'
+                                : `<div>Started: ${new Date(task.started_at).toLocaleTimeString()}</div>`}
+                            ${task.status === 'Queued' && task.queue_reason ? `<div style="color: #805ad5;">${task.queue_reason}</div>` : ''}
+                            ${task.worker ? `<div>Worker: ${task.worker}</div>` : ''}
                             ${task.finished_at ? `<div>Finished: ${new Date(task.finished_at).toLocaleTimeString()}</div>` : ''}
                             ${task.actual_duration_ms ? `<div>Duration: ${task.actual_duration_ms}ms</div>` : ''}
                             ${task.result ? `<div>Result: ${task.result}</div>` : ''}
@@ -539,7 +591,7 @@ function updateTaskGrid(newHtml) {
  * Create a new task via WebSocket or REST API fallback
  * Sends a task creation message to the server
  *
- * @param {string} taskType - Type of task to create ('quick', 'long', 'error')
+ * @param {string} taskType - Name of a registered task type (see /api/task-types)
  */
 function createTask(taskType) {
     const span = createSpan('user.create_task', {
@@ -555,10 +607,10 @@ function createTask(taskType) {
 
     // Try WebSocket first
     if (window.ws && window.ws.readyState === WebSocket.OPEN && !isOfflineMode) {
-        const message = {
+        const message = withTraceparent({
             type: 'create_task',
             task_type: taskType
-        };
+        }, span);
 
         logTelemetryEvent('WEBSOCKET_MESSAGE_SENT', {
             type: 'create_task',
@@ -585,18 +637,19 @@ function createTask(taskType) {
  * Create task using REST API (fallback mode)
  */
 async function createTaskRestMode(taskType, span) {
+    const template = getTaskTemplate(taskType);
     try {
         const response = await fetch('/api/tasks', {
             method: 'POST',
-            headers: {
+            headers: withTraceparent({
                 'Content-Type': 'application/json',
-            },
+            }, span),
             body: JSON.stringify({
                 name: '',
                 message: 'Task created via REST API fallback',
                 task_type: {
                     type: taskType,
-                    timeout_ms: taskType === 'quick' ? 2000 : taskType === 'long' ? 10000 : 5000
+                    timeout_ms: template ? template.default_timeout : 5000
                 }
             })
         });
@@ -719,6 +772,91 @@ function refreshTasks() {
     }
 }
 
+/**
+ * Start the server-side load generator with the pattern and rate from the load panel.
+ * The server mixes all templates evenly.
+ */
+function startLoad() {
+    const pattern = document.getElementById('load-pattern').value;
+    const rate = parseFloat(document.getElementById('load-rate').value);
+
+    if (!(rate > 0)) {
+        showConnectionStatus('Load rate must be greater than 0', 'error');
+        return;
+    }
+
+    if (window.ws && window.ws.readyState === WebSocket.OPEN) {
+        const message = {
+            type: 'start_load',
+            pattern: pattern,
+            rate_per_sec: rate
+        };
+
+        logTelemetryEvent('WEBSOCKET_MESSAGE_SENT', {
+            type: 'start_load',
+            pattern: pattern,
+            rate: rate
+        });
+
+        window.ws.send(JSON.stringify(message));
+    } else {
+        showConnectionStatus('Load generator needs a live connection', 'error');
+    }
+}
+
+/**
+ * Stop the server-side load generator
+ */
+function stopLoad() {
+    if (window.ws && window.ws.readyState === WebSocket.OPEN) {
+        logTelemetryEvent('WEBSOCKET_MESSAGE_SENT', { type: 'stop_load' });
+        window.ws.send(JSON.stringify({ type: 'stop_load' }));
+    } else {
+        showConnectionStatus('Load generator needs a live connection', 'error');
+    }
+}
+
+/**
+ * Switch the task grid to another namespace, an empty name shows every shared one
+ */
+function switchNamespace(namespace) {
+    const url = new URL(location.href);
+    if (namespace) {
+        url.searchParams.set('ns', namespace);
+    } else {
+        url.searchParams.delete('ns');
+    }
+    history.replaceState(null, '', url);
+
+    if (window.ws && window.ws.readyState === WebSocket.OPEN) {
+        logTelemetryEvent('WEBSOCKET_MESSAGE_SENT', { type: 'switch_namespace', namespace });
+        window.ws.send(JSON.stringify({ type: 'switch_namespace', namespace }));
+    } else {
+        showConnectionStatus('Switching namespaces needs a live connection', 'error');
+    }
+}
+
+/**
+ * Narrow the task grid with a search such as `status:error duration>5s`,
+ * an empty search shows every task again
+ */
+function searchTasks(q) {
+    const url = new URL(location.href);
+    if (q.trim()) {
+        url.searchParams.set('q', q);
+    } else {
+        url.searchParams.delete('q');
+    }
+    history.replaceState(null, '', url);
+
+    if (window.ws && window.ws.readyState === WebSocket.OPEN) {
+        logTelemetryEvent('WEBSOCKET_MESSAGE_SENT', { type: 'search', q });
+        window.ws.send(JSON.stringify({ type: 'search', q }));
+    } else {
+        showConnectionStatus('Searching needs a live connection', 'error');
+    }
+}
+
 /**
  * Create a custom task with form inputs
  */
@@ -728,23 +866,19 @@ function createCustomTask(event) {
     const taskName = document.getElementById('task-name').value.trim();
     const taskMessage = document.getElementById('task-message').value.trim();
     const modal = document.getElementById('task-modal');
+    const templateName = modal.dataset.template || 'quick';
     const taskType = modal.dataset.taskType || 'quick';
 
-    if (!taskMessage) {
-        alert('Please enter a task message');
-        return;
-    }
-
-    // Build the message object based on task type
+    // An empty message falls back to the template's default message
     let message = {
         type: 'create_custom_task',
+        template: templateName,
         name: taskName,
-        message: taskMessage,
-        task_type: taskType
+        message: taskMessage
     };
 
-    // Add custom options if custom task type is selected
-    if (taskType === 'custom') {
+    // Add custom options if the template exposes them
+    if (getTaskTemplate(templateName)?.has_custom_options) {
         const timeout = document.getElementById('custom-timeout')?.value || 5000;
         const failureRate = document.getElementById('custom-failure-rate')?.value || 0;
 
@@ -753,12 +887,15 @@ function createCustomTask(event) {
     }
 
     const span = createSpan('user.create_custom_task', {
+        'task.template': templateName,
         'task.type': taskType,
         'task.name': taskName,
         'user.action': 'form_submit'
     });
+    withTraceparent(message, span);
 
     logTelemetryEvent('USER_CREATE_CUSTOM_TASK_CLICKED', {
+        template: templateName,
         taskType: taskType,
         taskName: taskName,
         timestamp: Date.now()
@@ -792,16 +929,15 @@ async function createCustomTaskRestMode(formData, span) {
         const requestBody = {
             name: formData.name,
             message: formData.message,
-            task_type: formData.task_type,
-            custom_timeout: formData.custom_timeout,
-            custom_failure_rate: formData.custom_failure_rate
+            timeout_ms: formData.custom_timeout,
+            failure_rate: formData.custom_failure_rate
         };
 
-        const response = await fetch('/api/tasks/form', {
+        const response = await fetch(`/api/templates/${encodeURIComponent(formData.template)}/tasks`, {
             method: 'POST',
-            headers: {
+            headers: withTraceparent({
                 'Content-Type': 'application/json',
-            },
+            }, span),
             body: JSON.stringify(requestBody)
         });
 
@@ -811,7 +947,7 @@ async function createCustomTaskRestMode(formData, span) {
             const errorMsg = data.error?.message || 'Unknown error occurred';
             showConnectionStatus(`❌ Task creation failed: ${errorMsg}`, 'error');
         } else {
-            showConnectionStatus(`✅ ${formData.task_type} task created successfully`, 'info');
+            showConnectionStatus(`✅ ${formData.template} task created successfully`, 'info');
             closeModal();
         }
 
@@ -862,9 +998,9 @@ function buildTaskTypeRequest(formData) {
 
 
 /**
- * Clear the form inputs
+ * Clear the form inputs, resetting advanced options to the template defaults
  */
-function clearForm() {
+function clearForm(config) {
     const nameInput = document.getElementById('task-name');
     const messageInput = document.getElementById('task-message');
     const timeoutInput = document.getElementById('custom-timeout');
@@ -872,46 +1008,40 @@ function clearForm() {
 
     if (nameInput) nameInput.value = '';
     if (messageInput) messageInput.value = '';
-    if (timeoutInput) timeoutInput.value = '5000';
-    if (failureRateInput) failureRateInput.value = '0';
+    if (timeoutInput) timeoutInput.value = String(config?.default_timeout ?? 5000);
+    if (failureRateInput) failureRateInput.value = String(config?.default_failure_rate ?? 0);
+}
+
+/**
+ * Look up a task template from the JSON the server embeds in the page
+ *
+ * @param {string} templateName - Template key ('quick', 'long', or a user-defined name)
+ */
+function getTaskTemplate(templateName) {
+    const dataEl = document.getElementById('task-templates');
+    if (!dataEl) return null;
+
+    try {
+        const templates = JSON.parse(dataEl.textContent);
+        return templates.find(t => t.template === templateName) || templates[0] || null;
+    } catch (error) {
+        logTelemetryEvent('TEMPLATE_PARSE_ERROR', { error: error.message });
+        return null;
+    }
 }
 
 /**
  * Open task creation modal (modal content is server-generated)
  */
-function openTaskModal(taskType) {
+function openTaskModal(templateName) {
     const modal = document.getElementById('task-modal');
     const badge = document.getElementById('modal-task-type-badge');
+    const config = getTaskTemplate(templateName);
 
-    // Task type configurations
-    const taskConfig = {
-        quick: {
-            name: 'Quick Task',
-            badge: 'badge-quick',
-            placeholder: 'Quick task message (completes in ~2s)',
-            description: 'A fast task that completes in approximately 2 seconds'
-        },
-        long: {
-            name: 'Long Task',
-            badge: 'badge-long',
-            placeholder: 'Long task message (completes in ~10s)',
-            description: 'A longer task that takes approximately 10 seconds to complete'
-        },
-        error: {
-            name: 'Error Task',
-            badge: 'badge-error',
-            placeholder: 'Error task message (will fail for testing)',
-            description: 'A task designed to fail for testing error handling'
-        },
-        custom: {
-            name: 'Custom Task',
-            badge: 'badge-custom',
-            placeholder: 'Custom task message',
-            description: 'A fully customizable task with configurable timeout and failure rate'
-        }
-    };
-
-    const config = taskConfig[taskType] || taskConfig.quick;
+    if (!modal || !config) {
+        logTelemetryEvent('MODAL_OPEN_FAILED', { template: templateName });
+        return;
+    }
 
     // Update modal UI dynamically
     if (badge) {
@@ -937,21 +1067,23 @@ function openTaskModal(taskType) {
         messageInput.placeholder = config.placeholder;
     }
 
-    // Update the modal task type in the modal and form data attributes
-    modal.dataset.taskType = taskType;
+    // Update the modal template in the modal and form data attributes
+    modal.dataset.template = config.template;
+    modal.dataset.taskType = config.task_type;
     const form = modal.querySelector('#task-form');
     if (form) {
-        form.dataset.taskType = taskType;
+        form.dataset.template = config.template;
+        form.dataset.taskType = config.task_type;
     }
 
-    // Show/hide custom options based on task type
+    // Show/hide custom options based on the template
     const customOptions = modal.querySelector('.custom-options');
     if (customOptions) {
-        customOptions.style.display = taskType === 'custom' ? 'block' : 'none';
+        customOptions.style.display = config.has_custom_options ? 'block' : 'none';
     }
 
     // Clear form and show modal
-    clearForm();
+    clearForm(config);
     modal.classList.add('show');
 
     // Focus on message input
@@ -960,7 +1092,8 @@ function openTaskModal(taskType) {
     }, 100);
 
     logTelemetryEvent('MODAL_OPENED', {
-        taskType: taskType,
+        template: config.template,
+        taskType: config.task_type,
         timestamp: Date.now()
     });
 }
@@ -1016,6 +1149,8 @@ if (typeof window !== 'undefined') {
     window.createTask = createTask;
     window.cancelTask = cancelTask;
     window.refreshTasks = refreshTasks;
+    window.startLoad = startLoad;
+    window.stopLoad = stopLoad;
     window.createCustomTask = createCustomTask;
     window.clearForm = clearForm;
     window.openTaskModal = openTaskModal;